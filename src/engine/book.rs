@@ -0,0 +1,254 @@
+use std::{cmp::Reverse, fs};
+
+use tabled::Tabled;
+
+use crate::{rules::{board::{Board, packed_move::PackedMove, squares::{get_col_and_row_from_square, get_square_from_col_and_row}}, pieces::{PieceType, movement::Move}}, util::{errors::InputError, notation::get_uci_notation_for_move, polyglot::get_polyglot_key, random::Random}};
+
+
+static ENTRY_SIZE: usize = 16;
+static NATIVE_MAGIC: &[u8; 8] = b"RCBOOK01";
+static NATIVE_ENTRY_SIZE: usize = 26;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BookSelection {
+    Best,
+    Weighted,
+}
+
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BookRecord {
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BookRecord {
+    pub fn get_weight(&self) -> u32 {
+        return self.wins.saturating_mul(2).saturating_add(self.draws);
+    }
+
+    pub fn describe(&self) -> String {
+        return format!("+{} ={} -{}", self.wins, self.draws, self.losses);
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mov: u16,
+    pub packed: Option<PackedMove>,
+    pub weight: u32,
+    pub learn: u32,
+    pub record: Option<BookRecord>,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        return Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mov: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            packed: None,
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()) as u32,
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+            record: None,
+        }
+    }
+
+    pub fn from_native_bytes(bytes: &[u8]) -> Self {
+        let read = |start: usize| u32::from_be_bytes(bytes[start..start + 4].try_into().unwrap());
+        let record = BookRecord { games: read(10), wins: read(14), draws: read(18), losses: read(22) };
+        let packed = PackedMove::from_bytes(bytes[8..10].try_into().unwrap());
+        return Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mov: Self::encode_packed_move(&packed),
+            packed: Some(packed),
+            weight: record.get_weight(),
+            learn: 0,
+            record: Some(record),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mov.to_be_bytes());
+        bytes[10..12].copy_from_slice(&(self.weight.min(u16::MAX as u32) as u16).to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        return bytes;
+    }
+
+    pub fn to_native_bytes(&self) -> [u8; 26] {
+        let record = self.record.unwrap_or_default();
+        let mut bytes = [0u8; 26];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.packed.unwrap_or(PackedMove::NULL).to_bytes());
+        for (index, value) in [record.games, record.wins, record.draws, record.losses].iter().enumerate() {
+            bytes[10 + index * 4..14 + index * 4].copy_from_slice(&value.to_be_bytes());
+        }
+        return bytes;
+    }
+
+    pub fn from_packed_move(key: u64, packed: PackedMove, record: BookRecord) -> Self {
+        return Self {
+            key: key,
+            mov: Self::encode_packed_move(&packed),
+            packed: Some(packed),
+            weight: record.get_weight(),
+            learn: 0,
+            record: Some(record),
+        }
+    }
+
+    pub fn encode_packed_move(packed: &PackedMove) -> u16 {
+        if packed.is_null() { return 0 }
+        let [start_col, start_row] = get_col_and_row_from_square(packed.get_start());
+        let [end_col, end_row] = get_col_and_row_from_square(packed.get_end());
+        let promotion = match packed.get_promotion() {
+            Some(PieceType::Knight) => 1,
+            Some(PieceType::Bishop) => 2,
+            Some(PieceType::Rook) => 3,
+            Some(PieceType::Queen) => 4,
+            _ => 0,
+        };
+        return end_col as u16 | (end_row as u16) << 3 | (start_col as u16) << 6 | (start_row as u16) << 9 | promotion << 12;
+    }
+
+    pub fn get_move(&self, board: &Board) -> Option<Move> {
+        if let Some(packed) = self.packed {
+            let mov = packed.to_move(board)?;
+            return board.get_legal_moves().into_iter().find(|m| *m == mov);
+        }
+        let start = get_square_from_col_and_row(((self.mov >> 6) & 7) as u8, ((self.mov >> 9) & 7) as u8);
+        let end = get_square_from_col_and_row((self.mov & 7) as u8, ((self.mov >> 3) & 7) as u8);
+        let promote_to = match (self.mov >> 12) & 7 {
+            0 => None,
+            1 => Some(PieceType::Knight),
+            2 => Some(PieceType::Bishop),
+            3 => Some(PieceType::Rook),
+            4 => Some(PieceType::Queen),
+            _ => return None,
+        };
+        return board.get_legal_moves().into_iter().find(|m| match m {
+            Move::Castle(c) => c.king_start == start && c.rook_start == end,
+            Move::Promotion(p) => p.basic_move.start == start && p.basic_move.end == end && Some(p.promote_to) == promote_to,
+            _ => promote_to.is_none() && m.get_primary_movement().is_some_and(|p| p.start_square == start && p.end_square == end),
+        });
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub struct BookMove {
+    pub mov: Move,
+    pub weight: u32,
+    pub learn: u32,
+    pub record: Option<BookRecord>,
+}
+
+impl BookMove {
+    pub fn to_printable(&self, total_weight: u64) -> PrintableBookMove {
+        return PrintableBookMove {
+            mov: get_uci_notation_for_move(&self.mov),
+            weight: self.weight.to_string(),
+            share: format!("{:.1}%", self.weight as f64 * 100.0 / total_weight.max(1) as f64),
+            learn: self.learn.to_string(),
+            record: self.record.map_or(String::from("-"), |r| r.describe()),
+        }
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableBookMove {
+    #[header("move")]
+    pub mov: String,
+    pub weight: String,
+    pub share: String,
+    pub learn: String,
+    pub record: String,
+}
+
+
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+    random: Random,
+}
+
+impl OpeningBook {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InputError> {
+        let mut entries: Vec<BookEntry> = match bytes.strip_prefix(NATIVE_MAGIC) {
+            Some(native) if native.len().is_multiple_of(NATIVE_ENTRY_SIZE) => native.chunks_exact(NATIVE_ENTRY_SIZE).map(BookEntry::from_native_bytes).collect(),
+            Some(native) => return Err(InputError::new(&format!("Native book size of {} bytes is not a multiple of {}", native.len(), NATIVE_ENTRY_SIZE))),
+            None if bytes.len().is_multiple_of(ENTRY_SIZE) => bytes.chunks_exact(ENTRY_SIZE).map(BookEntry::from_bytes).collect(),
+            None => return Err(InputError::new(&format!("Polyglot book size of {} bytes is not a multiple of {}", bytes.len(), ENTRY_SIZE))),
+        };
+        entries.sort_by_key(|e| e.key);
+        return Ok(Self {
+            entries: entries,
+            random: Random::new(),
+        });
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, InputError> {
+        return match fs::read(path) {
+            Ok(bytes) => Self::from_bytes(&bytes),
+            Err(e) => Err(InputError::new(&format!("Could not read Polyglot book {}: {}", path, e))),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn get_entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+        return &self.entries[start..end];
+    }
+
+    pub fn get_moves(&self, board: &Board) -> Vec<BookMove> {
+        let mut moves: Vec<BookMove> = self.get_entries(get_polyglot_key(board)).iter().filter_map(|e| {
+            Some(BookMove { mov: e.get_move(board)?, weight: e.weight, learn: e.learn, record: e.record })
+        }).collect();
+        moves.sort_by_key(|m| Reverse(m.weight));
+        return moves;
+    }
+
+    pub fn choose_move(&mut self, board: &Board, selection: BookSelection) -> Option<Move> {
+        let moves: Vec<BookMove> = self.get_moves(board).into_iter().filter(|m| m.weight > 0).collect();
+        if moves.is_empty() { return None }
+        return match selection {
+            BookSelection::Best => Some(moves[0].mov),
+            BookSelection::Weighted => {
+                let total: u64 = moves.iter().map(|m| m.weight as u64).sum();
+                let mut pick = self.random.next_u64() % total;
+                moves.iter().find(|m| {
+                    if pick < m.weight as u64 { return true }
+                    pick -= m.weight as u64;
+                    false
+                }).map(|m| m.mov)
+            },
+        }
+    }
+
+    pub fn write_polyglot(entries: &[BookEntry], path: &str) -> Result<(), InputError> {
+        let bytes: Vec<u8> = entries.iter().flat_map(|e| e.to_bytes()).collect();
+        return fs::write(path, bytes).map_err(|e| InputError::new(&format!("Could not write Polyglot book {}: {}", path, e)));
+    }
+
+    pub fn write_native(entries: &[BookEntry], path: &str) -> Result<(), InputError> {
+        if entries.iter().any(|e| e.packed.is_none()) {
+            return Err(InputError::new(&format!("Could not write native book {}: entries read from a Polyglot book have no packed moves", path)));
+        }
+        let bytes: Vec<u8> = NATIVE_MAGIC.iter().copied().chain(entries.iter().flat_map(|e| e.to_native_bytes())).collect();
+        return fs::write(path, bytes).map_err(|e| InputError::new(&format!("Could not write native book {}: {}", path, e)));
+    }
+}
@@ -0,0 +1,157 @@
+use fxhash::FxHashMap;
+
+use crate::{engine::book::{BookEntry, BookRecord, OpeningBook}, game::GameResult, rules::board::packed_move::PackedMove, util::{errors::InputError, pgn::{PgnGame, read_pgn_file}, polyglot::get_polyglot_key}};
+
+
+static MAX_REPORTED_ERRORS: usize = 10;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BookFormat {
+    Polyglot,
+    Native,
+}
+
+
+#[derive(Copy, Clone)]
+pub struct BookBuilderOptions {
+    pub max_ply: usize,
+    pub min_games: u32,
+    pub include_wins: bool,
+    pub include_draws: bool,
+    pub include_losses: bool,
+}
+
+impl BookBuilderOptions {
+    pub fn includes(&self, record: &BookRecord) -> bool {
+        return (record.wins > 0 && self.include_wins) ||
+            (record.draws > 0 && self.include_draws) ||
+            (record.losses > 0 && self.include_losses);
+    }
+}
+
+impl Default for BookBuilderOptions {
+    fn default() -> Self {
+        return Self {
+            max_ply: 24,
+            min_games: 3,
+            include_wins: true,
+            include_draws: true,
+            include_losses: true,
+        }
+    }
+}
+
+
+#[derive(Clone, Default)]
+pub struct BookBuildSummary {
+    pub games_read: u64,
+    pub games_used: u64,
+    pub games_skipped: u64,
+    pub positions: u64,
+    pub entries: u64,
+    pub errors: Vec<String>,
+}
+
+
+pub struct BookBuilder {
+    options: BookBuilderOptions,
+    records: FxHashMap<(u64, PackedMove), BookRecord>,
+    summary: BookBuildSummary,
+}
+
+impl BookBuilder {
+    pub fn new(options: BookBuilderOptions) -> Self {
+        return Self {
+            options: options,
+            records: Default::default(),
+            summary: Default::default(),
+        }
+    }
+
+    pub fn add_pgn_file(&mut self, path: &str) -> Result<(), InputError> {
+        for game in read_pgn_file(path)? {
+            if let Err(e) = self.add_game(&game) {
+                self.record_error(&format!("{} ({})", e.msg, self.describe_game(&game)));
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), InputError> {
+        self.summary.games_read += 1;
+        let result = game.get_result();
+        if result == GameResult::Unfinished {
+            self.summary.games_skipped += 1;
+            return Ok(());
+        }
+        let mut plies: Vec<(u64, PackedMove, BookRecord)> = Vec::new();
+        let replayed = game.replay(self.options.max_ply, |g, mov| {
+            let mover = g.get_current_turn();
+            let record = match result.get_winner() {
+                Some(winner) if winner == mover => BookRecord { games: 1, wins: 1, draws: 0, losses: 0 },
+                Some(_) => BookRecord { games: 1, wins: 0, draws: 0, losses: 1 },
+                None => BookRecord { games: 1, wins: 0, draws: 1, losses: 0 },
+            };
+            plies.push((get_polyglot_key(g.get_board()), PackedMove::from_move(mov), record));
+        });
+        if let Err(e) = replayed {
+            self.summary.games_skipped += 1;
+            return Err(e);
+        }
+        self.summary.games_used += 1;
+        let options = self.options;
+        for (key, mov, record) in plies.into_iter().filter(|(_, _, r)| options.includes(r)) {
+            let total = self.records.entry((key, mov)).or_default();
+            total.games += record.games;
+            total.wins += record.wins;
+            total.draws += record.draws;
+            total.losses += record.losses;
+        }
+        return Ok(());
+    }
+
+    pub fn get_entries(&self) -> Vec<BookEntry> {
+        let mut entries: Vec<BookEntry> = self.records.iter().filter(|(_, r)| r.games >= self.options.min_games).map(|((key, mov), record)| {
+            BookEntry::from_packed_move(*key, *mov, *record)
+        }).collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)).then(a.mov.cmp(&b.mov)));
+        return entries;
+    }
+
+    pub fn write(&mut self, path: &str, format: BookFormat) -> Result<BookBuildSummary, InputError> {
+        let mut entries = self.get_entries();
+        match format {
+            BookFormat::Polyglot => {
+                scale_polyglot_weights(&mut entries);
+                entries.retain(|e| e.weight > 0);
+                OpeningBook::write_polyglot(&entries, path)?;
+            },
+            BookFormat::Native => OpeningBook::write_native(&entries, path)?,
+        }
+        self.summary.entries = entries.len() as u64;
+        self.summary.positions = entries.iter().enumerate().filter(|(i, e)| *i == 0 || entries[i - 1].key != e.key).count() as u64;
+        return Ok(self.summary.clone());
+    }
+
+    fn describe_game(&self, game: &PgnGame) -> String {
+        return format!("{} vs {}", game.get_tag("White").unwrap_or("?"), game.get_tag("Black").unwrap_or("?"));
+    }
+
+    fn record_error(&mut self, error: &str) {
+        if self.summary.errors.len() < MAX_REPORTED_ERRORS {
+            self.summary.errors.push(String::from(error));
+        }
+    }
+}
+
+
+fn scale_polyglot_weights(entries: &mut [BookEntry]) {
+    for position in entries.chunk_by_mut(|a, b| a.key == b.key) {
+        let max = position.iter().map(|e| e.weight).max().unwrap_or(0);
+        if max <= u16::MAX as u32 { continue }
+        for entry in position.iter_mut() {
+            entry.weight = (entry.weight as u64 * u16::MAX as u64 / max as u64) as u32;
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use fxhash::FxHashMap;
+
+use crate::rules::Color;
+use crate::rules::board::Board;
+use crate::rules::board::bitboards::DARK_SQUARES;
+use crate::rules::board::material::MaterialSignature;
+use crate::rules::board::move_list::MoveList;
+use crate::rules::board::squares::{get_col_and_row_from_square, get_square_from_col_and_row};
+use crate::rules::pieces::PieceType;
+
+
+pub static SCALE_NORMAL: i32 = 64;
+
+static KNOWN_WIN: i16 = 10_000;
+static SCALE_DRAW: i32 = 0;
+static SCALE_OPPOSITE_BISHOPS: i32 = 16;
+static SCALE_MINOR_ADVANTAGE: i32 = 4;
+static SCALE_EXCHANGE_ADVANTAGE: i32 = 14;
+
+lazy_static! {
+    static ref RECOGNIZERS: FxHashMap<u64, (EndgameFunction, Color)> = build_recognizers();
+}
+
+
+type EndgameFunction = fn(&Board, &MaterialSignature, Color) -> Option<EndgameEvaluation>;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EndgameEvaluation {
+    Exact(i16),
+    Scaled(i32),
+}
+
+
+pub struct Endgames {}
+
+impl Endgames {
+    pub fn probe(board: &Board) -> Option<EndgameEvaluation> {
+        let signature = board.position.get_material_signature();
+        if let Some((recognizer, strong)) = RECOGNIZERS.get(&signature.get_key()) {
+            if let Some(evaluation) = recognizer(board, &signature, *strong) {
+                return Some(evaluation);
+            }
+        }
+        for strong in Color::iter() {
+            if signature.is_lone_king(strong.swap()) && has_mating_material(board, &signature, strong) {
+                return evaluate_kxk(board, &signature, strong);
+            }
+        }
+        if board.is_insufficient_material() {
+            return Some(EndgameEvaluation::Exact(0));
+        }
+        return get_scale_factor(board, &signature).map(EndgameEvaluation::Scaled);
+    }
+}
+
+
+fn build_recognizers() -> FxHashMap<u64, (EndgameFunction, Color)> {
+    let recognizers: [(&str, EndgameFunction); 6] = [
+        ("KvK", evaluate_draw),
+        ("KNvK", evaluate_draw),
+        ("KBvK", evaluate_draw),
+        ("KNNvK", evaluate_draw),
+        ("KBNvK", evaluate_kbnk),
+        ("KPvK", evaluate_kpk),
+    ];
+    let mut map = FxHashMap::default();
+    for (notation, recognizer) in recognizers {
+        let signature = MaterialSignature::from_notation(notation).unwrap();
+        map.insert(signature.swap().get_key(), (recognizer, Color::Black));
+        map.insert(signature.get_key(), (recognizer, Color::White));
+    }
+    return map;
+}
+
+
+fn evaluate_draw(_board: &Board, _signature: &MaterialSignature, _strong: Color) -> Option<EndgameEvaluation> {
+    return Some(EndgameEvaluation::Exact(0));
+}
+
+
+fn evaluate_kxk(board: &Board, signature: &MaterialSignature, strong: Color) -> Option<EndgameEvaluation> {
+    if is_stalemate(board) {
+        return Some(EndgameEvaluation::Exact(0));
+    }
+    let strong_king = board.position.find_king(strong);
+    let weak_king = board.position.find_king(strong.swap());
+    let score = KNOWN_WIN + get_material(signature, strong) + push_to_edge(weak_king) + push_close(strong_king, weak_king);
+    return Some(EndgameEvaluation::Exact(relative_score(score, strong)));
+}
+
+
+fn evaluate_kbnk(board: &Board, signature: &MaterialSignature, strong: Color) -> Option<EndgameEvaluation> {
+    if is_stalemate(board) {
+        return Some(EndgameEvaluation::Exact(0));
+    }
+    let strong_king = board.position.find_king(strong);
+    let weak_king = board.position.find_king(strong.swap());
+    let dark_bishop = board.position.get_piece_locations(strong, PieceType::Bishop) & DARK_SQUARES != 0;
+    let score = KNOWN_WIN + get_material(signature, strong) + push_to_edge(weak_king) + push_close(strong_king, weak_king)
+        + push_to_corner(weak_king, dark_bishop);
+    return Some(EndgameEvaluation::Exact(relative_score(score, strong)));
+}
+
+
+fn evaluate_kpk(board: &Board, _signature: &MaterialSignature, strong: Color) -> Option<EndgameEvaluation> {
+    let pawn = board.position.get_piece_locations(strong, PieceType::Pawn).trailing_zeros() as u8;
+    let strong_king = board.position.find_king(strong);
+    let weak_king = board.position.find_king(strong.swap());
+    let [col, row] = get_col_and_row_from_square(pawn);
+    let relative_row = match strong { Color::White => row, Color::Black => 7 - row };
+    let promotion = get_square_from_col_and_row(col, match strong { Color::White => 7, Color::Black => 0 });
+
+    let mut moves_to_promote = 7 - relative_row as i16;
+    if relative_row == 1 {
+        moves_to_promote -= 1;
+    }
+    let weak_to_move = board.state.get_move_color() != strong;
+    let defender_moves = get_distance(weak_king, promotion) - weak_to_move as i16;
+    let [king_col, king_row] = get_col_and_row_from_square(strong_king);
+    let relative_king_row = match strong { Color::White => king_row, Color::Black => 7 - king_row };
+    let king_blocks_pawn = king_col == col && relative_king_row > relative_row;
+
+    if defender_moves > moves_to_promote && !king_blocks_pawn {
+        let score = KNOWN_WIN + PieceType::Pawn.value() as i16 * 100 + relative_row as i16 * 10;
+        return Some(EndgameEvaluation::Exact(relative_score(score, strong)));
+    }
+    if (col == 0 || col == 7) && get_distance(weak_king, promotion) <= 1 {
+        return Some(EndgameEvaluation::Exact(0));
+    }
+    return None;
+}
+
+
+fn get_scale_factor(board: &Board, signature: &MaterialSignature) -> Option<i32> {
+    if is_opposite_bishops(board, signature) {
+        return Some(SCALE_OPPOSITE_BISHOPS);
+    }
+    let strong = match get_material(signature, Color::White) {
+        0 => return None,
+        m if m > 0 => Color::White,
+        _ => Color::Black,
+    };
+    let strong_material = signature.get_non_pawn_material(strong);
+    let weak_material = signature.get_non_pawn_material(strong.swap());
+    let bishop_value = PieceType::Bishop.value() as i16 * 100;
+    if signature.get_count(strong, PieceType::Pawn) == 0 && strong_material - weak_material <= bishop_value {
+        if strong_material < PieceType::Rook.value() as i16 * 100 {
+            return Some(SCALE_DRAW);
+        }
+        if weak_material <= bishop_value {
+            return Some(SCALE_MINOR_ADVANTAGE);
+        }
+        return Some(SCALE_EXCHANGE_ADVANTAGE);
+    }
+    return None;
+}
+
+
+fn has_mating_material(board: &Board, signature: &MaterialSignature, color: Color) -> bool {
+    if signature.get_count(color, PieceType::Queen) > 0 || signature.get_count(color, PieceType::Rook) > 0 {
+        return true;
+    }
+    let bishops = board.position.get_piece_locations(color, PieceType::Bishop);
+    if bishops & DARK_SQUARES != 0 && bishops & !DARK_SQUARES != 0 {
+        return true;
+    }
+    return bishops != 0 && signature.get_count(color, PieceType::Knight) > 0;
+}
+
+
+fn is_opposite_bishops(board: &Board, signature: &MaterialSignature) -> bool {
+    let only_bishop = Color::iter().all(|c| {
+        signature.get_count(c, PieceType::Bishop) == 1 &&
+            [PieceType::Knight, PieceType::Rook, PieceType::Queen].iter().all(|t| signature.get_count(c, *t) == 0)
+    });
+    if !only_bishop {
+        return false;
+    }
+    let white_dark = board.position.get_piece_locations(Color::White, PieceType::Bishop) & DARK_SQUARES != 0;
+    let black_dark = board.position.get_piece_locations(Color::Black, PieceType::Bishop) & DARK_SQUARES != 0;
+    return white_dark != black_dark;
+}
+
+
+fn is_stalemate(board: &Board) -> bool {
+    let mut moves = MoveList::new();
+    board.generate_legal_moves(&mut moves);
+    return moves.is_empty() && !board.in_check();
+}
+
+
+fn get_material(signature: &MaterialSignature, color: Color) -> i16 {
+    let pawn_value = PieceType::Pawn.value() as i16 * 100;
+    let strong = signature.get_non_pawn_material(color) + signature.get_count(color, PieceType::Pawn) as i16 * pawn_value;
+    let weak = signature.get_non_pawn_material(color.swap()) + signature.get_count(color.swap(), PieceType::Pawn) as i16 * pawn_value;
+    return strong - weak;
+}
+
+
+fn get_distance(from: u8, to: u8) -> i16 {
+    let [from_col, from_row] = get_col_and_row_from_square(from);
+    let [to_col, to_row] = get_col_and_row_from_square(to);
+    return (from_col as i16 - to_col as i16).abs().max((from_row as i16 - to_row as i16).abs());
+}
+
+
+fn push_to_edge(square: u8) -> i16 {
+    let [col, row] = get_col_and_row_from_square(square);
+    let col_distance = col.min(7 - col) as i16;
+    let row_distance = row.min(7 - row) as i16;
+    return 30 * (3 - col_distance) + 30 * (3 - row_distance);
+}
+
+
+fn push_close(strong_king: u8, weak_king: u8) -> i16 {
+    return 20 * (7 - get_distance(strong_king, weak_king));
+}
+
+
+fn push_to_corner(square: u8, dark_bishop: bool) -> i16 {
+    let corners: [u8; 2] = if dark_bishop { [0, 63] } else { [7, 56] };
+    let [col, row] = get_col_and_row_from_square(square);
+    let distance = corners.iter().map(|c| {
+        let [corner_col, corner_row] = get_col_and_row_from_square(*c);
+        (col as i16 - corner_col as i16).abs() + (row as i16 - corner_row as i16).abs()
+    }).min().unwrap();
+    return 40 * (14 - distance);
+}
+
+
+fn relative_score(score: i16, color: Color) -> i16 {
+    return match color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
@@ -1,14 +1,66 @@
-use crate::rules::{board::{Board, bitboards::BitboardSquares}, Color};
+use crate::{rules::board::Board, util::errors::InputError};
 
+use super::{endgame::{Endgames, EndgameEvaluation, SCALE_NORMAL}, parameters::EvaluationParameters};
 
-pub struct Evaluator {}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Evaluator {
+    Material,
+    #[default]
+    Standard,
+}
 
 
 impl Evaluator {
-    pub fn evaluate_board(board: &Board) -> i16 {
-        return BitboardSquares::from_board(board.position.get_all_piece_locations(Color::White) | 
-            board.position.get_all_piece_locations(Color::Black)).fold(0i16, |score, s| {
-                score + board.position.piece_at(&s).unwrap().material_score()
-            });
+    pub fn from_name(name: &str) -> Result<Self, InputError> {
+        return match name {
+            "material" => Ok(Self::Material),
+            "standard" => Ok(Self::Standard),
+            _ => Err(InputError::new(&format!("'{}' is not a known evaluator", name))),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        return match self {
+            Self::Material => "material",
+            Self::Standard => "standard",
+        }
+    }
+
+    pub fn evaluate_board(&self, board: &Board, parameters: &EvaluationParameters) -> i16 {
+        let score = parameters.evaluate(board, *self == Self::Material);
+        if *self == Self::Material {
+            return score;
+        }
+        return match Endgames::probe(board) {
+            Some(EndgameEvaluation::Exact(score)) => score,
+            Some(EndgameEvaluation::Scaled(factor)) => (score as i32 * factor / SCALE_NORMAL) as i16,
+            None => score,
+        };
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvaluationNoise {
+    pub amplitude: i16,
+    pub seed: u64,
+}
+
+impl EvaluationNoise {
+    pub fn new(amplitude: i16, seed: u64) -> Self {
+        return Self {
+            amplitude: amplitude.max(0),
+            seed: seed,
+        }
+    }
+
+    pub fn apply(&self, board: &Board, score: i16) -> i16 {
+        if self.amplitude == 0 {
+            return score;
+        }
+        let mixed = (board.zobrist.get_id() ^ self.seed).wrapping_mul(0x9E3779B97F4A7C15) >> 32;
+        let noise = (mixed % (2 * self.amplitude as u64 + 1)) as i16 - self.amplitude;
+        return score.saturating_add(noise);
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,13 @@
+pub mod book;
+pub mod book_builder;
+pub mod endgame;
 pub mod evaluation;
+pub mod parameters;
 pub mod scores;
 pub mod search;
+pub mod strength;
+pub mod tablebase;
+pub mod tuner;
 
 
 pub struct Engine;
@@ -0,0 +1,187 @@
+use std::fs;
+
+use tabled::Tabled;
+
+use crate::{rules::{board::{Board, bitboards::BitboardSquares}, pieces::PieceType, Color}, util::errors::InputError};
+
+
+pub const PIECE_VALUE_COUNT: usize = 5;
+pub const PARAMETER_COUNT: usize = PIECE_VALUE_COUNT + 6 * 64;
+
+static MATERIAL_SECTION: &str = "material";
+
+
+fn get_piece_value_index(piece_type: PieceType) -> Option<usize> {
+    return match piece_type {
+        PieceType::King => None,
+        p => Some(p as usize),
+    }
+}
+
+fn get_square_index(piece_type: PieceType, color: Color, square: u8) -> usize {
+    let relative = match color {
+        Color::White => square,
+        Color::Black => square ^ 56,
+    };
+    return PIECE_VALUE_COUNT + piece_type as usize * 64 + relative as usize;
+}
+
+fn get_piece_type_from_name(name: &str) -> Option<PieceType> {
+    return PieceType::iter().find(|p| p.name() == name);
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvaluationParameters {
+    values: [i16; PARAMETER_COUNT],
+}
+
+impl Default for EvaluationParameters {
+    fn default() -> Self {
+        let mut values = [0i16; PARAMETER_COUNT];
+        for piece_type in PieceType::iter() {
+            if let Some(index) = get_piece_value_index(piece_type) {
+                values[index] = piece_type.value() as i16 * 100;
+            }
+        }
+        return Self { values: values };
+    }
+}
+
+impl EvaluationParameters {
+    pub fn from_text(text: &str) -> Result<Self, InputError> {
+        let mut parameters = Self::default();
+        let mut section: Option<String> = None;
+        let mut squares: Vec<i16> = Vec::new();
+        for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                parameters.finish_section(section.as_deref(), &mut squares)?;
+                let name = name.trim().to_lowercase();
+                if name != MATERIAL_SECTION && get_piece_type_from_name(&name).is_none() {
+                    return Err(InputError::new(&format!("'{}' is not a known evaluation parameter section", name)));
+                }
+                section = Some(name);
+                continue;
+            }
+            match section.as_deref() {
+                Some(s) if s == MATERIAL_SECTION => {
+                    let (name, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                    let index = get_piece_type_from_name(&name.to_lowercase()).and_then(get_piece_value_index)
+                        .ok_or(InputError::new(&format!("'{}' is not a piece with a material value", name)))?;
+                    parameters.values[index] = value.trim().parse().map_err(|_| InputError::new(&format!("'{}' is not a valid value for the {}", value.trim(), name)))?;
+                },
+                Some(s) => for value in line.split_whitespace() {
+                    squares.push(value.parse().map_err(|_| InputError::new(&format!("'{}' is not a valid square value in section '{}'", value, s)))?);
+                },
+                None => return Err(InputError::new(&format!("Evaluation parameter line '{}' is not inside a section", line))),
+            }
+        }
+        parameters.finish_section(section.as_deref(), &mut squares)?;
+        return Ok(parameters);
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, InputError> {
+        return match fs::read_to_string(path) {
+            Ok(text) => Self::from_text(&text).map_err(|e| InputError::new(&format!("Could not load evaluation parameters from {}: {}", path, e.msg))),
+            Err(e) => Err(InputError::new(&format!("Could not read evaluation parameter file {}: {}", path, e))),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::from([format!("[{}]", MATERIAL_SECTION)]);
+        for piece_type in PieceType::iter().filter(|p| get_piece_value_index(*p).is_some()) {
+            lines.push(format!("{} {}", piece_type.name(), self.get_piece_value(piece_type)));
+        }
+        for piece_type in PieceType::iter() {
+            lines.push(String::new());
+            lines.push(format!("[{}]", piece_type.name()));
+            for row in (0..8u8).rev() {
+                lines.push((0..8u8).map(|col| format!("{:>5}", self.values[get_square_index(piece_type, Color::White, col + row * 8)])).collect::<Vec<_>>().join(""));
+            }
+        }
+        lines.push(String::new());
+        return lines.join("\n");
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), InputError> {
+        return fs::write(path, self.to_text()).map_err(|e| InputError::new(&format!("Could not write evaluation parameters to {}: {}", path, e)));
+    }
+
+    pub fn get(&self, index: usize) -> i16 {
+        return self.values[index];
+    }
+
+    pub fn set(&mut self, index: usize, value: i16) {
+        self.values[index] = value;
+    }
+
+    pub fn is_piece_value(index: usize) -> bool {
+        return index < PIECE_VALUE_COUNT;
+    }
+
+    pub fn get_piece_value(&self, piece_type: PieceType) -> i16 {
+        return get_piece_value_index(piece_type).map_or(0, |i| self.values[i]);
+    }
+
+    pub fn for_each_feature<F>(board: &Board, material_only: bool, mut visit: F) where F: FnMut(usize, i16) {
+        for color in Color::iter() {
+            let sign = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            for square in BitboardSquares::from_board(board.position.get_all_piece_locations(color)) {
+                let piece_type = board.position.piece_at(&square).unwrap().piece_type;
+                if let Some(index) = get_piece_value_index(piece_type) {
+                    visit(index, sign);
+                }
+                if !material_only {
+                    visit(get_square_index(piece_type, color, square), sign);
+                }
+            }
+        }
+    }
+
+    pub fn evaluate(&self, board: &Board, material_only: bool) -> i16 {
+        let mut score = 0i32;
+        Self::for_each_feature(board, material_only, |index, sign| score += self.values[index] as i32 * sign as i32);
+        return score.clamp(i16::MIN as i32 + 1, i16::MAX as i32) as i16;
+    }
+
+    pub fn to_printable(&self) -> Vec<PrintablePieceParameters> {
+        return PieceType::iter().map(|piece_type| {
+            let squares: Vec<i16> = (0..64u8).map(|s| self.values[get_square_index(piece_type, Color::White, s)]).collect();
+            PrintablePieceParameters {
+                piece: String::from(piece_type.name()),
+                value: get_piece_value_index(piece_type).map_or(String::from("-"), |i| self.values[i].to_string()),
+                min_square: squares.iter().min().unwrap().to_string(),
+                max_square: squares.iter().max().unwrap().to_string(),
+                average_square: format!("{:.1}", squares.iter().map(|v| *v as f64).sum::<f64>() / 64f64),
+            }
+        }).collect();
+    }
+
+    fn finish_section(&mut self, section: Option<&str>, squares: &mut Vec<i16>) -> Result<(), InputError> {
+        let piece_type = match section.and_then(get_piece_type_from_name) {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if squares.len() != 64 {
+            return Err(InputError::new(&format!("Section '{}' should have 64 square values but has {}", piece_type.name(), squares.len())));
+        }
+        for (index, value) in squares.drain(..).enumerate() {
+            let (row, col) = (7 - index as u8 / 8, index as u8 % 8);
+            self.values[get_square_index(piece_type, Color::White, col + row * 8)] = value;
+        }
+        return Ok(());
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintablePieceParameters {
+    pub piece: String,
+    pub value: String,
+    pub min_square: String,
+    pub max_square: String,
+    pub average_square: String,
+}
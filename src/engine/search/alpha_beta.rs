@@ -1,8 +1,8 @@
-use std::{sync::{Arc, atomic::{AtomicU8, AtomicI16, AtomicBool, Ordering as AtomicOrdering, AtomicU32}}, cmp::Ordering, iter::Rev, thread, time::Duration};
+use std::{sync::{Arc, atomic::{AtomicU8, AtomicI16, AtomicBool, Ordering as AtomicOrdering}}, cmp::Ordering, iter::Rev, thread, time::{Duration, Instant}};
 
 use crossbeam::{channel::{Sender, Receiver, unbounded}, atomic::AtomicCell};
 
-use crate::{engine::{evaluation::Evaluator, scores::{best_score, is_better}}, util::{zobrist::{ZobristHashMap, ZobristLockfreeMap}, concurrency::{pools::AsyncPriorityThreadPool, tasks::AsyncTask, queues::{PriorityQueueWriter, PriorityQueueBuilder}}}, rules::{pieces::movement::{Move, NullMove}, board::Board, Color}};
+use crate::{engine::{evaluation::{EvaluationNoise, Evaluator}, parameters::EvaluationParameters, scores::{best_score, is_better}, search::{MAX_SEARCH_DEPTH, PrincipalVariation, SearchOptions, SearchResult, ordering::MoveOrderingTables, statistics::{SearchStatistics, ThreadedSearchStatistics}}, tablebase::Tablebases}, util::{zobrist::{ZobristHashMap, ZobristLockfreeMap}, concurrency::{pools::AsyncPriorityThreadPool, tasks::AsyncTask, queues::{PriorityQueueWriter, PriorityQueueBuilder}}}, rules::{pieces::movement::{Move, NullMove}, board::{Board, move_list::{MoveList, MoveListIntoIter}, packed_move::PackedMove}}};
 
 
 impl PartialOrd for Move {
@@ -28,17 +28,26 @@ impl Ord for Move {
 
 
 struct MoveOrderIterator {
-    base_iter: Rev<std::vec::IntoIter<Move>>,
+    base_iter: Rev<MoveListIntoIter>,
     hash_move: Option<Move>,
     initialized: bool,
 }
 
 impl MoveOrderIterator {
-    pub fn from_moves(mut moves: Vec<Move>, hash_move: Option<Move>) -> Self {
+    pub fn from_moves(mut moves: MoveList, hash_move: Option<Move>) -> Self {
         moves.sort();
         return Self {
+            hash_move: hash_move.filter(|m| moves.contains(m)),
+            base_iter: moves.into_iter().rev(),
+            initialized: false,
+        }
+    }
+
+    pub fn from_scored_moves<F>(mut moves: MoveList, hash_move: Option<Move>, get_score: F) -> Self where F: FnMut(&Move) -> i32 {
+        moves.sort_by_score(get_score);
+        return Self {
+            hash_move: hash_move.filter(|m| moves.contains(m)),
             base_iter: moves.into_iter().rev(),
-            hash_move: hash_move,
             initialized: false,
         }
     }
@@ -103,17 +112,41 @@ impl Iterator for ThreadedMoveOrderIterator {
 struct Transposition {
     result_type: AlphaBetaResultType,
     score: i16,
-    mov: Option<Move>,
+    mov: PackedMove,
     depth: u8,
 }
 
+impl Transposition {
+    fn from_result(result: &AlphaBetaResult, depth: u8) -> Self {
+        return Self {
+            result_type: result.result_type,
+            score: result.score,
+            mov: result.mov.map_or(PackedMove::NULL, |m| PackedMove::from_move(&m)),
+            depth: depth,
+        }
+    }
+
+    fn get_move(&self, board: &Board) -> Option<Move> {
+        return self.mov.to_move(board);
+    }
+
+    fn to_result(&self, board: &Board) -> AlphaBetaResult {
+        return AlphaBetaResult {
+            result_type: self.result_type,
+            score: self.score,
+            mov: self.get_move(board),
+        }
+    }
+}
+
 enum TranspositionMatch {
     FullMatch(AlphaBetaResult),
     BestMove(Move),
     None,
 }
 
-fn process_transposition(alpha: i16, beta: i16, depth: u8, move_color: Color, t: &Transposition) -> TranspositionMatch {
+fn process_transposition(alpha: i16, beta: i16, depth: u8, board: &Board, t: &Transposition) -> TranspositionMatch {
+    let move_color = board.state.get_move_color();
     if depth >= t.depth {
         if (t.result_type == AlphaBetaResultType::BetaCutoff && is_better(beta, t.score, move_color)) ||
             (t.result_type != AlphaBetaResultType::BetaCutoff && is_better(t.score, beta, move_color))
@@ -121,34 +154,21 @@ fn process_transposition(alpha: i16, beta: i16, depth: u8, move_color: Color, t:
             return TranspositionMatch::FullMatch(AlphaBetaResult {
                 result_type: AlphaBetaResultType::BetaCutoff,
                 score: beta,
-                mov: t.mov,
-                evaluated_nodes: 0,
-                cache_hits: 1,
-                beta_cutoffs: 1,
+                mov: t.get_move(board),
             });
         }
         if t.result_type != AlphaBetaResultType::BetaCutoff && is_better(alpha, t.score, move_color) {
             return TranspositionMatch::FullMatch(AlphaBetaResult {
                 result_type: AlphaBetaResultType::AlphaFallback,
                 score: alpha,
-                mov: t.mov,
-                evaluated_nodes: 0,
-                cache_hits: 1,
-                beta_cutoffs: 0,
+                mov: t.get_move(board),
             });
         }
         if t.result_type == AlphaBetaResultType::Calculated || t.result_type == AlphaBetaResultType::Evaluated {
-            return TranspositionMatch::FullMatch(AlphaBetaResult {
-                result_type: t.result_type,
-                score: t.score,
-                mov: t.mov,
-                evaluated_nodes: 0,
-                cache_hits: 1,
-                beta_cutoffs: 0,
-            });
+            return TranspositionMatch::FullMatch(t.to_result(board));
         }
     }
-    if let Some(m) = t.mov {
+    if let Some(m) = t.get_move(board) {
         return TranspositionMatch::BestMove(m);
     }
     return TranspositionMatch::None;
@@ -186,9 +206,6 @@ pub struct AlphaBetaResult {
     pub result_type: AlphaBetaResultType,
     pub score: i16,
     pub mov: Option<Move>,
-    pub evaluated_nodes: u32,
-    pub cache_hits: u32,
-    pub beta_cutoffs: u32,
 }
 
 impl AlphaBetaResult {
@@ -197,9 +214,6 @@ impl AlphaBetaResult {
             result_type: AlphaBetaResultType::Empty,
             score: score,
             mov: None,
-            evaluated_nodes: 0,
-            cache_hits: 0,
-            beta_cutoffs: 0,
         }
     }
 
@@ -208,9 +222,6 @@ impl AlphaBetaResult {
             result_type: AlphaBetaResultType::Evaluated,
             score: score,
             mov: None,
-            evaluated_nodes: 1,
-            cache_hits: 0,
-            beta_cutoffs: 0,
         }
     }
 
@@ -219,9 +230,6 @@ impl AlphaBetaResult {
             result_type: result.result_type,
             score: result.score,
             mov: result.mov,
-            evaluated_nodes: 0,
-            cache_hits: 1,
-            beta_cutoffs: 0,
         }
     }
 }
@@ -235,35 +243,45 @@ enum AlphaBetaThreadContextParent {
 
 struct AlphaBetaThreadContext {
     transpositions: Arc<ZobristLockfreeMap<Transposition>>,
+    statistics: Arc<ThreadedSearchStatistics>,
     parent: AlphaBetaThreadContextParent,
+    evaluator: Evaluator,
+    parameters: Arc<EvaluationParameters>,
+    eval_noise: EvaluationNoise,
+    tablebases: Option<Arc<Tablebases>>,
     board: Board,
+    root_moves: Vec<Move>,
     mov: Move,
+    move_index: u8,
+    ply: u8,
     depth_remaining: u8,
     alpha: AtomicI16,
     beta: i16,
     best_move: AtomicCell<Option<Move>>,
-    evaluated: AtomicU32,
-    transposed: AtomicU32,
-    beta_cutoff: AtomicU32,
     complete: AtomicBool,
     child_count: u8,
     children_complete: AtomicU8,
 }
 
 impl AlphaBetaThreadContext {
-    pub fn initial(board: Board, channel: Sender<AlphaBetaResult>, depth: u8) -> Self {
+    pub fn initial(board: Board, channel: Sender<AlphaBetaResult>, transpositions: Arc<ZobristLockfreeMap<Transposition>>, statistics: Arc<ThreadedSearchStatistics>, options: &SearchOptions) -> Self {
         return Self {
-            transpositions: Arc::new(Default::default()),
+            transpositions: transpositions,
+            statistics: statistics,
             parent: AlphaBetaThreadContextParent::Channel(channel),
+            evaluator: options.evaluator,
+            parameters: Arc::clone(&options.parameters),
+            eval_noise: options.eval_noise,
+            tablebases: options.tablebases.clone(),
             board: board,
+            root_moves: options.root_moves.clone(),
             mov: Move::NullMove(NullMove {}),
-            depth_remaining: depth,
+            move_index: 0,
+            ply: 0,
+            depth_remaining: options.depth,
             alpha: AtomicI16::new(best_score(board.state.get_move_color().swap())),
             beta: best_score(board.state.get_move_color()),
             best_move: AtomicCell::new(None),
-            evaluated: AtomicU32::new(0),
-            transposed: AtomicU32::new(0),
-            beta_cutoff: AtomicU32::new(0),
             complete: AtomicBool::new(false),
             child_count: 0,
             children_complete: AtomicU8::new(0),
@@ -274,43 +292,63 @@ impl AlphaBetaThreadContext {
         if self.is_complete() {
             return Err(())
         }
+        self.statistics.with_current_thread(|s| s.record_node(self.ply));
         let mut hash_move: Option<Move> = None;
         {
             let transposition = self.transpositions.get(&self.board.zobrist.get_id());
+            self.statistics.with_current_thread(|s| s.record_probe(transposition.is_some()));
             if let Some(guard) = transposition {
-                match process_transposition(self.alpha.load(AtomicOrdering::Acquire), self.beta, self.depth_remaining, self.board.state.get_move_color(), guard.val()) {
+                match process_transposition(self.alpha.load(AtomicOrdering::Acquire), self.beta, self.depth_remaining, &self.board, guard.val()) {
                     TranspositionMatch::FullMatch(r) => { self.transpose(r); return Err(()); },
                     TranspositionMatch::BestMove(m) => hash_move = Some(m),
                     TranspositionMatch::None => (),
                 }
             }
         }
+        if let Some(wdl) = self.tablebases.as_deref().filter(|_| self.ply > 0 && self.board.state.halfmove_clock == 0).and_then(|tb| tb.probe_wdl(&self.board)) {
+            self.statistics.with_current_thread(|s| s.record_tablebase_hit());
+            self.finish(AlphaBetaResult {
+                result_type: AlphaBetaResultType::Calculated,
+                score: wdl.get_score(self.board.state.get_move_color(), self.ply),
+                mov: None,
+            });
+            return Err(())
+        }
         if self.depth_remaining <= 0 {
             self.evaluate();
             return Err(())
         }
-        let moves = self.board.get_legal_moves();
+        let mut moves = MoveList::new();
+        self.board.generate_legal_moves(&mut moves);
+        if !self.root_moves.is_empty() {
+            moves.retain(|m| self.root_moves.contains(m));
+        }
         if moves.len() == 0 {
             self.evaluate();
             return Err(())
         }
         self.child_count = moves.len() as u8;
         let prev_ctx = Arc::new(self);
-        let result = Ok(MoveOrderIterator::from_moves(moves, hash_move).map(|mov| {
+        let result = Ok(MoveOrderIterator::from_moves(moves, hash_move).enumerate().map(|(index, mov)| {
             let mut new_board = prev_ctx.board;
             new_board.make_move(&mov);
             Self {
                 transpositions: Arc::clone(&prev_ctx.transpositions),
+                statistics: Arc::clone(&prev_ctx.statistics),
                 parent: AlphaBetaThreadContextParent::Instance(Arc::clone(&prev_ctx)),
+                evaluator: prev_ctx.evaluator,
+                parameters: Arc::clone(&prev_ctx.parameters),
+                eval_noise: prev_ctx.eval_noise,
+                tablebases: prev_ctx.tablebases.clone(),
                 board: new_board,
+                root_moves: Vec::new(),
                 mov: mov,
+                move_index: index as u8,
+                ply: prev_ctx.ply + 1,
                 depth_remaining: prev_ctx.depth_remaining - 1,
                 alpha: AtomicI16::new(prev_ctx.beta),
                 beta: prev_ctx.alpha.load(AtomicOrdering::Acquire),
                 best_move: AtomicCell::new(None),
-                evaluated: AtomicU32::new(0),
-                transposed: AtomicU32::new(0),
-                beta_cutoff: AtomicU32::new(0),
                 complete: AtomicBool::new(false),
                 child_count: 0,
                 children_complete: AtomicU8::new(0),
@@ -327,7 +365,8 @@ impl AlphaBetaThreadContext {
     }
 
     fn evaluate(&self) {
-        self.finish(AlphaBetaResult::evaluated(Evaluator::evaluate_board(&self.board)));
+        self.statistics.with_current_thread(|s| s.record_evaluation());
+        self.finish(AlphaBetaResult::evaluated(self.eval_noise.apply(&self.board, self.evaluator.evaluate_board(&self.board, &self.parameters))));
     }
 
     fn transpose(&self, result: AlphaBetaResult) {
@@ -336,34 +375,25 @@ impl AlphaBetaThreadContext {
 
     pub fn finish(&self, result: AlphaBetaResult) {
         self.complete.store(true, AtomicOrdering::Release);
-        self.transpositions.insert(self.board.zobrist.get_id(), Transposition {
-            result_type: result.result_type,
-            score: result.score,
-            mov: result.mov,
-            depth: self.depth_remaining,
-        });
+        self.transpositions.insert(self.board.zobrist.get_id(), Transposition::from_result(&result, self.depth_remaining));
+        self.statistics.with_current_thread(|s| s.record_store());
         match &self.parent {
-            AlphaBetaThreadContextParent::Instance(p) => p.complete_child(result, self.mov),
+            AlphaBetaThreadContextParent::Instance(p) => p.complete_child(result, self.mov, self.move_index),
             AlphaBetaThreadContextParent::Channel(s) => s.send(result).expect("Error sending final result for threaded Alpha Beta Search."),
         }
     }
 
-    pub fn complete_child(&self, result: AlphaBetaResult, child_move: Move) {
+    pub fn complete_child(&self, result: AlphaBetaResult, child_move: Move, child_index: u8) {
         self.children_complete.fetch_add(1, std::sync::atomic::Ordering::Release);
         if self.is_complete() {
             return
         };
-        self.evaluated.fetch_add(result.evaluated_nodes, AtomicOrdering::Release);
-        self.transposed.fetch_add(result.cache_hits, AtomicOrdering::Release);
-        self.beta_cutoff.fetch_add(result.beta_cutoffs, AtomicOrdering::Release);
         if is_better(result.score, self.beta, self.board.state.get_move_color()) {
+            self.statistics.with_current_thread(|s| s.record_cutoff(child_index as usize));
             self.finish(AlphaBetaResult {
                 result_type: AlphaBetaResultType::BetaCutoff,
                 score: self.beta,
                 mov: Some(child_move),
-                evaluated_nodes: self.evaluated.load(AtomicOrdering::Acquire),
-                cache_hits: self.transposed.load(AtomicOrdering::Acquire),
-                beta_cutoffs: self.beta_cutoff.load(AtomicOrdering::Acquire) + 1,
             });
             return;
         }
@@ -376,22 +406,12 @@ impl AlphaBetaThreadContext {
                 result_type: AlphaBetaResultType::Calculated,
                 score: self.alpha.load(AtomicOrdering::Acquire),
                 mov: self.best_move.take(),
-                evaluated_nodes: self.evaluated.load(AtomicOrdering::Acquire),
-                cache_hits: self.transposed.load(AtomicOrdering::Acquire),
-                beta_cutoffs: self.beta_cutoff.load(AtomicOrdering::Acquire),
             });
         }
     }
 }
 
 
-struct AlphaBetaContext {
-    board: Board,
-    evaluated_nodes: u32,
-    cache_hits: u32,
-}
-
-
 pub struct AlphaBetaSearch {
     close_comms: Vec<Sender<bool>>,
     result_comm: Receiver<AlphaBetaResult>,
@@ -399,18 +419,119 @@ pub struct AlphaBetaSearch {
 
 impl AlphaBetaSearch {
 
-    pub fn do_search(mut board: Board, depth: u8) -> AlphaBetaResult {
-        let mut transpositions: ZobristHashMap<AlphaBetaResult> = Default::default();
+    pub fn do_search(board: Board, depth: u8) -> SearchResult {
+        return Self::do_search_with_options(board, &SearchOptions::new(depth));
+    }
+
+    pub fn do_search_with_options(mut board: Board, options: &SearchOptions) -> SearchResult {
+        let start = Instant::now();
+        let mut options = options.clone();
+        if let Some(moves) = options.tablebases.as_ref().and_then(|tb| tb.get_best_root_moves(&board, |m| options.allows_root_move(m))) {
+            options.root_moves = moves;
+        }
+        let deadline = options.time_limit.map(|limit| start + limit);
+        let mut statistics: SearchStatistics = Default::default();
+        let mut completed_depth = 0;
+        let mut variations: Vec<PrincipalVariation> = Vec::new();
+        let mut ordering = MoveOrderingTables::new();
+        let first_depth = match deadline.is_some() || options.node_limit.is_some() {
+            true => options.depth.min(1),
+            false => options.depth,
+        };
+        let unlimited = SearchOptions { node_limit: None, ..options.clone() };
+        for depth in first_depth..=options.depth {
+            let iteration_options = if completed_depth == 0 { &unlimited } else { &options };
+            match Self::search_lines(&mut board, iteration_options, depth, deadline, &mut ordering, &mut statistics) {
+                Some(lines) => {
+                    completed_depth = depth;
+                    variations = lines;
+                },
+                None => break,
+            }
+        }
+        statistics.duration = start.elapsed();
+        return SearchResult {
+            score: match variations.first() {
+                Some(pv) => pv.score,
+                None => options.evaluate_board(&board),
+            },
+            mov: variations.first().map(|pv| pv.moves[0]),
+            depth: completed_depth,
+            lines: variations,
+            statistics: statistics,
+        }
+    }
+
+    fn is_expired(deadline: Option<Instant>) -> bool {
+        return match deadline {
+            Some(d) => Instant::now() >= d,
+            None => false,
+        }
+    }
+
+    fn is_stopped(deadline: Option<Instant>, options: &SearchOptions, statistics: &SearchStatistics) -> bool {
+        return Self::is_expired(deadline) || options.node_limit.is_some_and(|n| statistics.nodes() >= n);
+    }
+
+    fn search_lines(board: &mut Board, options: &SearchOptions, depth: u8, deadline: Option<Instant>, ordering: &mut MoveOrderingTables, statistics: &mut SearchStatistics) -> Option<Vec<PrincipalVariation>> {
+        let mut excluded_moves: Vec<Move> = Vec::new();
+        let mut variations: Vec<PrincipalVariation> = Vec::new();
+        for _ in 0..options.multi_pv.max(1) {
+            let mut transpositions: ZobristHashMap<Transposition> = Default::default();
+            let result = Self::search_root(board, options, depth, &excluded_moves, deadline, &mut transpositions, ordering, statistics);
+            if Self::is_stopped(deadline, options, statistics) {
+                return None;
+            }
+            match result.mov {
+                Some(mov) => {
+                    excluded_moves.push(mov);
+                    variations.push(PrincipalVariation::from_hash_moves(*board, result.score, mov, depth, |b| {
+                        transpositions.get(&b.zobrist.get_id()).and_then(|t| t.get_move(b))
+                    }));
+                },
+                None => break,
+            }
+        }
+        return Some(variations);
+    }
+
+    fn search_root(board: &mut Board, options: &SearchOptions, depth: u8, excluded_moves: &[Move], deadline: Option<Instant>, transpositions: &mut ZobristHashMap<Transposition>, ordering: &mut MoveOrderingTables, statistics: &mut SearchStatistics) -> AlphaBetaResult {
         let alpha = best_score(board.state.get_move_color().swap());
         let beta = best_score(board.state.get_move_color());
-        return Self::search(&mut board, alpha, beta, depth, &mut transpositions);
+        if excluded_moves.is_empty() && options.root_moves.is_empty() {
+            return Self::search(board, alpha, beta, depth, 0, deadline, options, transpositions, ordering, statistics);
+        }
+        statistics.record_node(0);
+        let mut result = AlphaBetaResult::new(alpha);
+        let mut moves = MoveList::new();
+        board.generate_pseudo_legal_moves(&mut moves);
+        moves.retain(|m| options.allows_root_move(m) && !excluded_moves.contains(m) && board.is_legal(m));
+        if depth == 0 || moves.is_empty() {
+            return result;
+        }
+        for m in MoveOrderIterator::from_scored_moves(moves, None, |m| ordering.get_move_score(m, 0)) {
+            let change = board.make_move(&m);
+            let child_result = Self::search(board, beta, result.score, depth - 1, 1, deadline, options, transpositions, ordering, statistics);
+            board.unmake_move(change);
+            if result.mov.is_none() || is_better(child_result.score, result.score, board.state.get_move_color()) {
+                result.score = child_result.score;
+                result.mov = Some(m);
+            }
+        }
+        result.result_type = AlphaBetaResultType::Calculated;
+        return result;
     }
 
-    fn search(board: &mut Board, alpha: i16, beta: i16, depth: u8, transpositions: &mut ZobristHashMap<AlphaBetaResult>) -> AlphaBetaResult {
+    fn search(board: &mut Board, alpha: i16, beta: i16, depth: u8, ply: u8, deadline: Option<Instant>, options: &SearchOptions, transpositions: &mut ZobristHashMap<Transposition>, ordering: &mut MoveOrderingTables, statistics: &mut SearchStatistics) -> AlphaBetaResult {
         let mut result = AlphaBetaResult::new(alpha);
+        if Self::is_stopped(deadline, options, statistics) {
+            return result;
+        }
+        statistics.record_node(ply);
         let mut hash_move: Option<Move> = None;
-        if let Some(transposed_result) = transpositions.get(&board.zobrist.get_id()) {
-            result.cache_hits += 1;
+        let transposition = transpositions.get(&board.zobrist.get_id()).map(|t| t.to_result(board));
+        statistics.record_probe(transposition.is_some());
+        if let Some(transposed_result) = transposition {
             if (transposed_result.result_type == AlphaBetaResultType::BetaCutoff && is_better(beta, transposed_result.score, board.state.get_move_color())) ||
                (transposed_result.result_type != AlphaBetaResultType::BetaCutoff && is_better(transposed_result.score, beta, board.state.get_move_color()))
             {
@@ -426,29 +547,46 @@ impl AlphaBetaSearch {
                 return result;
             }
             if transposed_result.result_type == AlphaBetaResultType::Calculated || transposed_result.result_type == AlphaBetaResultType::Evaluated {
-                return AlphaBetaResult::transposed(transposed_result);
+                return AlphaBetaResult::transposed(&transposed_result);
             }
             hash_move = transposed_result.mov;
         }
 
+        if let Some(wdl) = options.tablebases.as_deref().filter(|_| ply > 0 && board.state.halfmove_clock == 0).and_then(|tb| tb.probe_wdl(board)) {
+            statistics.record_tablebase_hit();
+            let probed = AlphaBetaResult {
+                result_type: AlphaBetaResultType::Calculated,
+                score: wdl.get_score(board.state.get_move_color(), ply),
+                mov: None,
+            };
+            transpositions.insert(board.zobrist.get_id(), Transposition::from_result(&probed, MAX_SEARCH_DEPTH));
+            statistics.record_store();
+            return probed;
+        }
+
         if depth <= 0 {
-            let evaluation = AlphaBetaResult::evaluated(Evaluator::evaluate_board(&board));
-            transpositions.insert(board.zobrist.get_id(), evaluation);
+            statistics.record_evaluation();
+            let evaluation = AlphaBetaResult::evaluated(options.evaluate_board(board));
+            transpositions.insert(board.zobrist.get_id(), Transposition::from_result(&evaluation, depth));
+            statistics.record_store();
             return evaluation;
         }
 
-        for m in MoveOrderIterator::from_moves(board.get_legal_moves(), hash_move) {
+        let mut moves = MoveList::new();
+        board.generate_pseudo_legal_moves(&mut moves);
+        let mut index = 0;
+        for m in MoveOrderIterator::from_scored_moves(moves, hash_move, |m| ordering.get_move_score(m, ply)) {
+            if !board.is_legal(&m) { continue }
+            index += 1;
             let change = board.make_move(&m);
-            let child_result = Self::search(board,beta, result.score, depth - 1, transpositions);
+            let child_result = Self::search(board,beta, result.score, depth - 1, ply + 1, deadline, options, transpositions, ordering, statistics);
             board.unmake_move(change);
-            result.evaluated_nodes += child_result.evaluated_nodes;
-            result.cache_hits += child_result.cache_hits;
-            result.beta_cutoffs += child_result.beta_cutoffs;
             if is_better(child_result.score, beta, board.state.get_move_color()) {
                 result.result_type = AlphaBetaResultType::BetaCutoff;
                 result.score = beta;
                 result.mov = Some(m);
-                result.beta_cutoffs += 1;
+                statistics.record_cutoff(index - 1);
+                ordering.record_cutoff(&m, ply, depth);
                 break;
             }
             if is_better(child_result.score, result.score, board.state.get_move_color()) {
@@ -456,16 +594,21 @@ impl AlphaBetaSearch {
                 result.mov = Some(m);
             }
         }
+        if Self::is_stopped(deadline, options, statistics) {
+            return result;
+        }
         if result.result_type == AlphaBetaResultType::Empty && is_better(result.score, alpha, board.state.get_move_color()) {
             result.result_type = AlphaBetaResultType::Calculated;
         } else {
             result.result_type = AlphaBetaResultType::AlphaFallback;
         }
-        transpositions.insert(board.zobrist.get_id(), result);
+        transpositions.insert(board.zobrist.get_id(), Transposition::from_result(&result, depth));
+        statistics.record_store();
         return result;
     }
 
-    pub fn do_threaded_search(board: Board, max_depth: u8, threads: u8, initial_sleep: u64) -> AlphaBetaResult {
+    pub fn do_threaded_search(board: Board, options: &SearchOptions, threads: u8, initial_sleep: u64) -> SearchResult {
+        let start = Instant::now();
         let queue_builder = PriorityQueueBuilder::from_priorities(Vec::from([
             AlphaBetaSearchPriority::FirstMove,
             AlphaBetaSearchPriority::Remainder,
@@ -473,13 +616,33 @@ impl AlphaBetaSearch {
         let mut pool = AsyncPriorityThreadPool::from_builder(queue_builder);
         pool.start_workers(1);
         let (tx, rx) = unbounded();
-        let ctx = AlphaBetaThreadContext::initial(board, tx, max_depth);
-        Self::threaded_search(pool.clone_writer(), ctx);
+        let transpositions: Arc<ZobristLockfreeMap<Transposition>> = Arc::new(Default::default());
+        let statistics = Arc::new(ThreadedSearchStatistics::new(options.depth));
+        let ctx = AlphaBetaThreadContext::initial(board, tx, Arc::clone(&transpositions), Arc::clone(&statistics), options);
+        let root_writer = pool.clone_writer();
+        pool.enqueue(AsyncTask {
+            task: Box::new(move || {
+                Self::threaded_search(root_writer, ctx);
+            })
+        }, &AlphaBetaSearchPriority::FirstMove);
         thread::sleep(Duration::from_millis(initial_sleep));
         pool.start_workers(threads - 1);
         let result = rx.recv().expect("Error receiving result of threaded Alpha Beta search.");
+        let duration = start.elapsed();
         pool.join();
-        return result;
+        let lines = match result.mov {
+            Some(mov) => Vec::from([PrincipalVariation::from_hash_moves(board, result.score, mov, options.depth, |b| {
+                transpositions.get(&b.zobrist.get_id()).and_then(|guard| guard.val().get_move(b))
+            })]),
+            None => Vec::new(),
+        };
+        return SearchResult {
+            score: result.score,
+            mov: result.mov,
+            depth: options.depth,
+            lines: lines,
+            statistics: statistics.collect(duration),
+        }
     }
 
     fn threaded_search(pool: PriorityQueueWriter<AlphaBetaSearchPriority, AsyncTask>, ctx: AlphaBetaThreadContext) {
@@ -1,5 +1,19 @@
 pub mod alpha_beta;
 pub mod monte_carlo;
+pub mod ordering;
+pub mod statistics;
+pub mod time_manager;
+
+use std::{sync::Arc, time::Duration};
+
+use tabled::Tabled;
+
+use crate::{engine::{evaluation::{EvaluationNoise, Evaluator}, parameters::EvaluationParameters, tablebase::Tablebases}, rules::{board::Board, pieces::movement::Move}, util::notation::get_uci_notation_for_moves};
+
+use self::statistics::SearchStatistics;
+
+
+pub const MAX_SEARCH_DEPTH: u8 = 64;
 
 
 pub enum SearchType {
@@ -8,4 +22,99 @@ pub enum SearchType {
 }
 
 
-pub struct SearchResult {}
\ No newline at end of file
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub depth: u8,
+    pub multi_pv: u8,
+    pub root_moves: Vec<Move>,
+    pub time_limit: Option<Duration>,
+    pub node_limit: Option<u64>,
+    pub tablebases: Option<Arc<Tablebases>>,
+    pub evaluator: Evaluator,
+    pub parameters: Arc<EvaluationParameters>,
+    pub eval_noise: EvaluationNoise,
+}
+
+impl SearchOptions {
+    pub fn new(depth: u8) -> Self {
+        return Self {
+            depth: depth,
+            multi_pv: 1,
+            root_moves: Vec::new(),
+            time_limit: None,
+            node_limit: None,
+            tablebases: None,
+            evaluator: Evaluator::default(),
+            parameters: Arc::new(EvaluationParameters::default()),
+            eval_noise: EvaluationNoise::default(),
+        }
+    }
+
+    pub fn with_time_limit(time_limit: Duration) -> Self {
+        let mut options = Self::new(MAX_SEARCH_DEPTH);
+        options.time_limit = Some(time_limit);
+        return options;
+    }
+
+    pub fn evaluate_board(&self, board: &Board) -> i16 {
+        return self.eval_noise.apply(board, self.evaluator.evaluate_board(board, &self.parameters));
+    }
+
+    pub fn allows_root_move(&self, mov: &Move) -> bool {
+        return self.root_moves.is_empty() || self.root_moves.contains(mov);
+    }
+}
+
+
+#[derive(Clone)]
+pub struct PrincipalVariation {
+    pub score: i16,
+    pub moves: Vec<Move>,
+}
+
+impl PrincipalVariation {
+    pub fn from_hash_moves<F>(mut board: Board, score: i16, first_move: Move, depth: u8, get_hash_move: F) -> Self where F: Fn(&Board) -> Option<Move> {
+        let mut moves = Vec::from([first_move]);
+        board.make_move(&first_move);
+        while moves.len() < depth as usize {
+            let mov = match get_hash_move(&board) {
+                Some(m) => m,
+                None => break,
+            };
+            if !board.get_legal_moves().contains(&mov) {
+                break;
+            }
+            board.make_move(&mov);
+            moves.push(mov);
+        }
+        return Self {
+            score: score,
+            moves: moves,
+        }
+    }
+
+    pub fn to_printable(&self, rank: usize) -> PrintablePrincipalVariation {
+        return PrintablePrincipalVariation {
+            rank: rank.to_string(),
+            score: self.score.to_string(),
+            line: get_uci_notation_for_moves(&self.moves),
+        }
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintablePrincipalVariation {
+    pub rank: String,
+    pub score: String,
+    pub line: String,
+}
+
+
+pub struct SearchResult {
+    pub score: i16,
+    pub mov: Option<Move>,
+    pub depth: u8,
+    pub lines: Vec<PrincipalVariation>,
+    pub statistics: SearchStatistics,
+}
@@ -0,0 +1,85 @@
+use crate::rules::{board::packed_move::PackedMove, pieces::movement::Move};
+
+use super::MAX_SEARCH_DEPTH;
+
+
+static CAPTURE_SCORE: i32 = 2_000_000;
+static KILLER_SCORE: i32 = 1_000_000;
+static MAX_HISTORY_SCORE: u32 = 999_999;
+
+
+pub struct KillerMoves {
+    moves: [[PackedMove; 2]; MAX_SEARCH_DEPTH as usize + 1],
+}
+
+impl KillerMoves {
+    pub fn new() -> Self {
+        return Self { moves: [[PackedMove::NULL; 2]; MAX_SEARCH_DEPTH as usize + 1] }
+    }
+
+    pub fn store(&mut self, ply: u8, mov: PackedMove) {
+        if let Some(killers) = self.moves.get_mut(ply as usize) {
+            if killers[0] != mov {
+                killers[1] = killers[0];
+                killers[0] = mov;
+            }
+        }
+    }
+
+    pub fn get_rank(&self, ply: u8, mov: PackedMove) -> Option<usize> {
+        return self.moves.get(ply as usize).and_then(|killers| killers.iter().position(|k| *k == mov));
+    }
+}
+
+
+pub struct HistoryTable {
+    scores: [[u32; 64]; 64],
+}
+
+impl HistoryTable {
+    pub fn new() -> Self {
+        return Self { scores: [[0u32; 64]; 64] }
+    }
+
+    pub fn record(&mut self, mov: PackedMove, depth: u8) {
+        let score = &mut self.scores[mov.get_start() as usize][mov.get_end() as usize];
+        *score = score.saturating_add(depth as u32 * depth as u32).min(MAX_HISTORY_SCORE);
+    }
+
+    pub fn get_score(&self, mov: PackedMove) -> u32 {
+        return self.scores[mov.get_start() as usize][mov.get_end() as usize];
+    }
+}
+
+
+pub struct MoveOrderingTables {
+    pub killers: KillerMoves,
+    pub history: HistoryTable,
+}
+
+impl MoveOrderingTables {
+    pub fn new() -> Self {
+        return Self {
+            killers: KillerMoves::new(),
+            history: HistoryTable::new(),
+        }
+    }
+
+    pub fn record_cutoff(&mut self, mov: &Move, ply: u8, depth: u8) {
+        let packed = PackedMove::from_move(mov);
+        if packed.is_capture() || packed.is_promotion() { return }
+        self.killers.store(ply, packed);
+        self.history.record(packed, depth);
+    }
+
+    pub fn get_move_score(&self, mov: &Move, ply: u8) -> i32 {
+        if let Some(value) = mov.relative_capture_value() {
+            return CAPTURE_SCORE + value as i32;
+        }
+        let packed = PackedMove::from_move(mov);
+        return match self.killers.get_rank(ply, packed) {
+            Some(rank) => KILLER_SCORE - rank as i32,
+            None => self.history.get_score(packed) as i32,
+        }
+    }
+}
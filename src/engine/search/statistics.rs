@@ -0,0 +1,306 @@
+use std::{cell::RefCell, sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}}, time::Duration};
+
+use num_format::{ToFormattedString, Locale};
+use tabled::Tabled;
+
+
+static SEARCH_IDS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static THREAD_STATISTICS: RefCell<Option<(u64, Arc<AtomicSearchStatistics>)>> = const { RefCell::new(None) };
+}
+
+
+fn format_rate(numerator: u64, denominator: u64) -> String {
+    return match denominator {
+        0 => String::from("-"),
+        d => format!("{:.2}%", numerator as f64 * 100f64 / d as f64),
+    }
+}
+
+
+#[derive(Clone, Default)]
+pub struct SearchStatistics {
+    pub nodes_by_depth: Vec<u64>,
+    pub evaluations: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub tt_stores: u64,
+    pub tb_hits: u64,
+    pub beta_cutoffs: u64,
+    pub first_move_cutoffs: u64,
+    pub duration: Duration,
+    pub threads: Vec<SearchStatistics>,
+}
+
+impl SearchStatistics {
+    pub fn record_node(&mut self, ply: u8) {
+        while self.nodes_by_depth.len() <= ply as usize {
+            self.nodes_by_depth.push(0);
+        }
+        self.nodes_by_depth[ply as usize] += 1;
+    }
+
+    pub fn record_evaluation(&mut self) {
+        self.evaluations += 1;
+    }
+
+    pub fn record_probe(&mut self, hit: bool) {
+        self.tt_probes += 1;
+        if hit { self.tt_hits += 1 }
+    }
+
+    pub fn record_store(&mut self) {
+        self.tt_stores += 1;
+    }
+
+    pub fn record_tablebase_hit(&mut self) {
+        self.tb_hits += 1;
+    }
+
+    pub fn record_cutoff(&mut self, move_index: usize) {
+        self.beta_cutoffs += 1;
+        if move_index == 0 { self.first_move_cutoffs += 1 }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        while self.nodes_by_depth.len() < other.nodes_by_depth.len() {
+            self.nodes_by_depth.push(0);
+        }
+        other.nodes_by_depth.iter().enumerate().for_each(|(ply, nodes)| self.nodes_by_depth[ply] += nodes);
+        self.evaluations        += other.evaluations;
+        self.tt_probes          += other.tt_probes;
+        self.tt_hits            += other.tt_hits;
+        self.tt_stores          += other.tt_stores;
+        self.tb_hits            += other.tb_hits;
+        self.beta_cutoffs       += other.beta_cutoffs;
+        self.first_move_cutoffs += other.first_move_cutoffs;
+    }
+
+    pub fn nodes(&self) -> u64 {
+        return self.nodes_by_depth.iter().sum();
+    }
+
+    pub fn nodes_per_second(&self) -> u64 {
+        return match self.duration.as_secs_f64() {
+            s if s > 0f64 => (self.nodes() as f64 / s) as u64,
+            _ => 0,
+        }
+    }
+
+    pub fn branching_factor_at(&self, ply: usize) -> Option<f64> {
+        if ply == 0 || ply >= self.nodes_by_depth.len() { return None }
+        return match self.nodes_by_depth[ply - 1] {
+            0 => None,
+            prior => Some(self.nodes_by_depth[ply] as f64 / prior as f64),
+        }
+    }
+
+    pub fn effective_branching_factor(&self) -> Option<f64> {
+        let depth = self.nodes_by_depth.len();
+        if depth < 2 { return None }
+        return Some((self.nodes() as f64).powf(1f64 / (depth - 1) as f64));
+    }
+
+    pub fn get_summary(&self) -> Vec<PrintableSearchStatistic> {
+        return Vec::from([
+            PrintableSearchStatistic::new("nodes", self.nodes().to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("evaluations", self.evaluations.to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("tt_probes", self.tt_probes.to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("tt_hits", format!("{} ({})", self.tt_hits.to_formatted_string(&Locale::en), format_rate(self.tt_hits, self.tt_probes))),
+            PrintableSearchStatistic::new("tt_stores", format!("{} ({})", self.tt_stores.to_formatted_string(&Locale::en), format_rate(self.tt_stores, self.nodes()))),
+            PrintableSearchStatistic::new("tb_hits", self.tb_hits.to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("beta_cutoffs", self.beta_cutoffs.to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("first_move_cutoffs", format!("{} ({})", self.first_move_cutoffs.to_formatted_string(&Locale::en), format_rate(self.first_move_cutoffs, self.beta_cutoffs))),
+            PrintableSearchStatistic::new("branching_factor", match self.effective_branching_factor() {
+                Some(ebf) => format!("{:.2}", ebf),
+                None => String::from("-"),
+            }),
+            PrintableSearchStatistic::new("nodes_per_second", self.nodes_per_second().to_formatted_string(&Locale::en)),
+            PrintableSearchStatistic::new("time", format!("{:?}", self.duration)),
+        ]);
+    }
+
+    pub fn get_depth_analysis(&self) -> Vec<PrintableSearchDepth> {
+        return self.nodes_by_depth.iter().enumerate().map(|(ply, nodes)| {
+            PrintableSearchDepth {
+                ply: ply.to_string(),
+                nodes: nodes.to_formatted_string(&Locale::en),
+                branching_factor: match self.branching_factor_at(ply) {
+                    Some(bf) => format!("{:.2}", bf),
+                    None => String::from("-"),
+                },
+            }
+        }).collect();
+    }
+
+    pub fn get_thread_analysis(&self) -> Vec<PrintableSearchThread> {
+        let total_nodes = self.nodes();
+        return self.threads.iter().enumerate().map(|(index, thread)| {
+            PrintableSearchThread {
+                thread: (index + 1).to_string(),
+                nodes: thread.nodes().to_formatted_string(&Locale::en),
+                share: format_rate(thread.nodes(), total_nodes),
+                evaluations: thread.evaluations.to_formatted_string(&Locale::en),
+                tt_hits: format_rate(thread.tt_hits, thread.tt_probes),
+                beta_cutoffs: thread.beta_cutoffs.to_formatted_string(&Locale::en),
+                first_move_cutoffs: format_rate(thread.first_move_cutoffs, thread.beta_cutoffs),
+            }
+        }).collect();
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableSearchStatistic {
+    pub statistic: String,
+    pub value: String,
+}
+
+impl PrintableSearchStatistic {
+    fn new(statistic: &str, value: String) -> Self {
+        return Self {
+            statistic: String::from(statistic),
+            value: value,
+        }
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableSearchDepth {
+    pub ply: String,
+    pub nodes: String,
+    pub branching_factor: String,
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableSearchThread {
+    pub thread: String,
+    pub nodes: String,
+    pub share: String,
+    pub evaluations: String,
+    pub tt_hits: String,
+    pub beta_cutoffs: String,
+    pub first_move_cutoffs: String,
+}
+
+
+pub struct AtomicSearchStatistics {
+    nodes_by_depth: Vec<AtomicU64>,
+    evaluations: AtomicU64,
+    tt_probes: AtomicU64,
+    tt_hits: AtomicU64,
+    tt_stores: AtomicU64,
+    tb_hits: AtomicU64,
+    beta_cutoffs: AtomicU64,
+    first_move_cutoffs: AtomicU64,
+}
+
+impl AtomicSearchStatistics {
+    pub fn new(max_depth: u8) -> Self {
+        return Self {
+            nodes_by_depth: (0..=max_depth).map(|_| AtomicU64::new(0)).collect(),
+            evaluations: AtomicU64::new(0),
+            tt_probes: AtomicU64::new(0),
+            tt_hits: AtomicU64::new(0),
+            tt_stores: AtomicU64::new(0),
+            tb_hits: AtomicU64::new(0),
+            beta_cutoffs: AtomicU64::new(0),
+            first_move_cutoffs: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record_node(&self, ply: u8) {
+        if let Some(counter) = self.nodes_by_depth.get(ply as usize) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_evaluation(&self) {
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_probe(&self, hit: bool) {
+        self.tt_probes.fetch_add(1, Ordering::Relaxed);
+        if hit { self.tt_hits.fetch_add(1, Ordering::Relaxed); }
+    }
+
+    pub fn record_store(&self) {
+        self.tt_stores.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_tablebase_hit(&self) {
+        self.tb_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cutoff(&self, move_index: usize) {
+        self.beta_cutoffs.fetch_add(1, Ordering::Relaxed);
+        if move_index == 0 { self.first_move_cutoffs.fetch_add(1, Ordering::Relaxed); }
+    }
+
+    pub fn to_statistics(&self) -> SearchStatistics {
+        let mut nodes_by_depth: Vec<u64> = self.nodes_by_depth.iter().map(|n| n.load(Ordering::Acquire)).collect();
+        while nodes_by_depth.last() == Some(&0) {
+            nodes_by_depth.pop();
+        }
+        return SearchStatistics {
+            nodes_by_depth: nodes_by_depth,
+            evaluations: self.evaluations.load(Ordering::Acquire),
+            tt_probes: self.tt_probes.load(Ordering::Acquire),
+            tt_hits: self.tt_hits.load(Ordering::Acquire),
+            tt_stores: self.tt_stores.load(Ordering::Acquire),
+            tb_hits: self.tb_hits.load(Ordering::Acquire),
+            beta_cutoffs: self.beta_cutoffs.load(Ordering::Acquire),
+            first_move_cutoffs: self.first_move_cutoffs.load(Ordering::Acquire),
+            duration: Default::default(),
+            threads: Vec::new(),
+        }
+    }
+}
+
+
+pub struct ThreadedSearchStatistics {
+    id: u64,
+    max_depth: u8,
+    threads: Mutex<Vec<Arc<AtomicSearchStatistics>>>,
+}
+
+impl ThreadedSearchStatistics {
+    pub fn new(max_depth: u8) -> Self {
+        return Self {
+            id: SEARCH_IDS.fetch_add(1, Ordering::AcqRel),
+            max_depth: max_depth,
+            threads: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_current_thread<F>(&self, f: F) where F: FnOnce(&AtomicSearchStatistics) {
+        THREAD_STATISTICS.with(|cell| {
+            let mut local = cell.borrow_mut();
+            let registered = match local.as_ref() {
+                Some((id, _)) => *id == self.id,
+                None => false,
+            };
+            if !registered {
+                let statistics = Arc::new(AtomicSearchStatistics::new(self.max_depth));
+                self.threads.lock().expect("Error registering thread for search statistics").push(Arc::clone(&statistics));
+                *local = Some((self.id, statistics));
+            }
+            f(&local.as_ref().unwrap().1);
+        });
+    }
+
+    pub fn collect(&self, duration: Duration) -> SearchStatistics {
+        let mut result: SearchStatistics = Default::default();
+        for thread in self.threads.lock().expect("Error collecting search statistics").iter() {
+            let mut thread_statistics = thread.to_statistics();
+            thread_statistics.duration = duration;
+            result.merge(&thread_statistics);
+            result.threads.push(thread_statistics);
+        }
+        result.duration = duration;
+        return result;
+    }
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use crate::{game::clock::ChessClock, rules::Color};
+
+
+static DEFAULT_MOVES_TO_GO: u16 = 30;
+static MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+static MIN_TIME_LIMIT: Duration = Duration::from_millis(5);
+static MAX_TIME_FRACTION: f64 = 0.4;
+static INCREMENT_FRACTION: f64 = 0.75;
+
+
+#[derive(Copy, Clone, Debug)]
+pub struct TimeManager {
+    pub remaining: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u16>,
+}
+
+impl TimeManager {
+    pub fn new(remaining: Duration, increment: Duration, moves_to_go: Option<u16>) -> Self {
+        return Self {
+            remaining: remaining,
+            increment: increment,
+            moves_to_go: moves_to_go,
+        }
+    }
+
+    pub fn from_clock(clock: &ChessClock, color: Color) -> Self {
+        return Self::new(clock.get_remaining(color), clock.get_increment(color), clock.get_moves_to_go(color));
+    }
+
+    pub fn get_time_limit(&self) -> Duration {
+        let available = self.remaining.saturating_sub(MOVE_OVERHEAD);
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let target = available / moves_to_go as u32 + self.increment.mul_f64(INCREMENT_FRACTION);
+        let ceiling = match moves_to_go {
+            1 => available,
+            _ => available.mul_f64(MAX_TIME_FRACTION),
+        };
+        return target.min(ceiling).max(MIN_TIME_LIMIT);
+    }
+}
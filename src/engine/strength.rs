@@ -0,0 +1,129 @@
+use crate::{rules::{Color, pieces::{PieceType, movement::Move}}, util::{errors::InputError, random::Random}};
+
+use super::{evaluation::EvaluationNoise, search::{SearchOptions, SearchResult}};
+
+
+pub static MAX_SKILL_LEVEL: u8 = 20;
+pub static MIN_ELO: u16 = 800;
+pub static MAX_ELO: u16 = 2200;
+
+static LIMITED_MULTI_PV: u8 = 4;
+static NOISE_PER_LEVEL: i16 = 12;
+static BASE_NODE_LIMIT: u64 = 1_000;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StrengthLevel {
+    skill: u8,
+}
+
+impl Default for StrengthLevel {
+    fn default() -> Self {
+        return Self::from_skill(MAX_SKILL_LEVEL);
+    }
+}
+
+impl StrengthLevel {
+    pub fn from_skill(skill: u8) -> Self {
+        return Self { skill: skill.min(MAX_SKILL_LEVEL) };
+    }
+
+    pub fn from_elo(elo: u16) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        let skill = (elo - MIN_ELO) as u32 * (MAX_SKILL_LEVEL - 1) as u32 / (MAX_ELO - MIN_ELO) as u32;
+        return Self::from_skill(skill as u8);
+    }
+
+    pub fn from_skill_name(value: &str) -> Result<Self, InputError> {
+        return match value.parse::<u8>() {
+            Ok(s) if s <= MAX_SKILL_LEVEL => Ok(Self::from_skill(s)),
+            _ => Err(InputError::new(&format!("'{}' is not a valid skill level, use 0 to {}", value, MAX_SKILL_LEVEL))),
+        }
+    }
+
+    pub fn from_elo_name(value: &str) -> Result<Self, InputError> {
+        return match value.parse::<u16>() {
+            Ok(e) if (MIN_ELO..=MAX_ELO).contains(&e) => Ok(Self::from_elo(e)),
+            _ => Err(InputError::new(&format!("'{}' is not a valid Elo, use {} to {}", value, MIN_ELO, MAX_ELO))),
+        }
+    }
+
+    pub fn get_skill(&self) -> u8 {
+        return self.skill;
+    }
+
+    pub fn get_elo(&self) -> Option<u16> {
+        if !self.is_limited() {
+            return None;
+        }
+        return Some(MIN_ELO + (self.skill as u32 * (MAX_ELO - MIN_ELO) as u32 / (MAX_SKILL_LEVEL - 1) as u32) as u16);
+    }
+
+    pub fn is_limited(&self) -> bool {
+        return self.skill < MAX_SKILL_LEVEL;
+    }
+
+    pub fn get_depth_limit(&self) -> Option<u8> {
+        return match self.is_limited() {
+            true => Some(1 + self.skill / 3),
+            false => None,
+        }
+    }
+
+    pub fn get_node_limit(&self) -> Option<u64> {
+        if !self.is_limited() {
+            return None;
+        }
+        let nodes = BASE_NODE_LIMIT << (self.skill / 2);
+        return Some(match self.skill % 2 {
+            0 => nodes,
+            _ => nodes + nodes / 2,
+        });
+    }
+
+    pub fn get_noise_amplitude(&self) -> i16 {
+        return (MAX_SKILL_LEVEL - self.skill) as i16 * NOISE_PER_LEVEL;
+    }
+
+    pub fn name(&self) -> String {
+        return match self.get_elo() {
+            Some(elo) => format!("skill {} (~{} Elo)", self.skill, elo),
+            None => String::from("full strength"),
+        }
+    }
+
+    pub fn apply(&self, options: &mut SearchOptions, random: &mut Random) {
+        if !self.is_limited() {
+            return;
+        }
+        options.depth = options.depth.min(self.get_depth_limit().unwrap());
+        options.node_limit = match (options.node_limit, self.get_node_limit()) {
+            (Some(current), Some(limit)) => Some(current.min(limit)),
+            (current, limit) => current.or(limit),
+        };
+        options.multi_pv = options.multi_pv.max(LIMITED_MULTI_PV);
+        options.eval_noise = EvaluationNoise::new(self.get_noise_amplitude(), random.next_u64());
+    }
+
+    pub fn pick_move(&self, result: &SearchResult, color: Color, random: &mut Random) -> Option<Move> {
+        if !self.is_limited() || result.lines.len() < 2 {
+            return result.mov;
+        }
+        let perspective = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let scores: Vec<i32> = result.lines.iter().map(|pv| pv.score as i32 * perspective).collect();
+        let top = scores[0];
+        let spread = (top - scores[scores.len() - 1]).min(PieceType::Pawn.value() as i32 * 100);
+        let weakness = 120 - 2 * self.skill as i32;
+        let mut best: Option<(i32, Move)> = None;
+        for (pv, score) in result.lines.iter().zip(scores) {
+            let push = (weakness * (top - score) + spread * (random.next_u64() % weakness as u64) as i32) / 128;
+            if best.is_none_or(|(b, _)| score + push >= b) {
+                best = Some((score + push, pv.moves[0]));
+            }
+        }
+        return best.map(|(_, mov)| mov);
+    }
+}
@@ -0,0 +1,441 @@
+pub mod table;
+
+use std::{env, fs, path::Path};
+
+use fxhash::FxHashMap;
+use tabled::Tabled;
+
+use crate::{rules::{Color, board::{Board, move_list::MoveList}, pieces::{PieceType, movement::Move}}, util::{errors::InputError, notation::get_uci_notation_for_move}};
+
+use self::table::{Table, TableKind, TableProbe};
+
+
+pub static TABLEBASE_WIN_SCORE: i16 = 20_000;
+
+static WIN_RANK: i32 = 1000;
+static CURSED_WIN_RANK: i32 = 899;
+static WIN_RANK_BOUND: i32 = 900;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WdlScore {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl WdlScore {
+    pub fn from_value(value: i32) -> Self {
+        return match value {
+            i32::MIN..=-2 => Self::Loss,
+            -1 => Self::BlessedLoss,
+            0 => Self::Draw,
+            1 => Self::CursedWin,
+            _ => Self::Win,
+        }
+    }
+
+    pub fn from_rank(rank: i32) -> Self {
+        return match rank {
+            r if r >= WIN_RANK_BOUND => Self::Win,
+            r if r > 0 => Self::CursedWin,
+            0 => Self::Draw,
+            r if r > -WIN_RANK_BOUND => Self::BlessedLoss,
+            _ => Self::Loss,
+        }
+    }
+
+    pub fn negate(&self) -> Self {
+        return Self::from_value(-(*self as i32));
+    }
+
+    pub fn name(&self) -> &str {
+        return match self {
+            Self::Loss        => "loss",
+            Self::BlessedLoss => "blessed loss",
+            Self::Draw        => "draw",
+            Self::CursedWin   => "cursed win",
+            Self::Win         => "win",
+        }
+    }
+
+    pub fn get_score(&self, color: Color, ply: u8) -> i16 {
+        let score = match self {
+            Self::Loss        => ply as i16 - TABLEBASE_WIN_SCORE,
+            Self::BlessedLoss => -1,
+            Self::Draw        => 0,
+            Self::CursedWin   => 1,
+            Self::Win         => TABLEBASE_WIN_SCORE - ply as i16,
+        };
+        return match color {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ProbeState {
+    Ok,
+    Fail,
+    ChangeStm,
+    ZeroingBestMove,
+}
+
+
+fn dtz_before_zeroing(wdl: WdlScore) -> i32 {
+    return match wdl {
+        WdlScore::Win         => 1,
+        WdlScore::CursedWin   => 101,
+        WdlScore::Draw        => 0,
+        WdlScore::BlessedLoss => -101,
+        WdlScore::Loss        => -1,
+    }
+}
+
+fn is_zeroing_move(mov: &Move) -> bool {
+    return mov.get_capture().is_some() || mov.get_moved_piece().is_some_and(|p| p.piece_type == PieceType::Pawn);
+}
+
+fn is_mate(board: &Board) -> bool {
+    let mut moves = MoveList::new();
+    board.generate_legal_moves(&mut moves);
+    return moves.is_empty() && board.in_check();
+}
+
+fn get_piece_count(board: &Board) -> usize {
+    return (board.position.get_all_piece_locations(Color::White) | board.position.get_all_piece_locations(Color::Black)).count_ones() as usize;
+}
+
+fn get_board_material_key(board: &Board) -> u64 {
+    return board.position.get_material_signature().get_key();
+}
+
+fn has_castle_rights(board: &Board) -> bool {
+    let rights = &board.state.castle_rights;
+    return rights.white_kingside || rights.white_queenside || rights.black_kingside || rights.black_queenside;
+}
+
+
+#[derive(Clone)]
+pub struct RankedMove {
+    pub mov: Move,
+    pub rank: i32,
+    pub dtz: Option<i32>,
+}
+
+impl RankedMove {
+    pub fn get_wdl(&self) -> WdlScore {
+        return WdlScore::from_rank(self.rank);
+    }
+
+    pub fn to_printable(&self) -> PrintableRankedMove {
+        return PrintableRankedMove {
+            mov: get_uci_notation_for_move(&self.mov),
+            result: String::from(self.get_wdl().name()),
+            dtz: self.dtz.map_or(String::from("-"), |d| d.to_string()),
+            rank: self.rank.to_string(),
+        }
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableRankedMove {
+    #[header("move")]
+    pub mov: String,
+    pub result: String,
+    pub dtz: String,
+    pub rank: String,
+}
+
+
+pub struct Tablebases {
+    wdl: Vec<Table>,
+    dtz: Vec<Option<Table>>,
+    keys: FxHashMap<u64, usize>,
+    max_cardinality: usize,
+}
+
+impl Tablebases {
+    pub fn from_paths(paths: &str) -> Result<Self, InputError> {
+        let mut tablebases = Self {
+            wdl: Vec::new(),
+            dtz: Vec::new(),
+            keys: Default::default(),
+            max_cardinality: 0,
+        };
+        for directory in env::split_paths(paths) {
+            tablebases.add_directory(&directory)?;
+        }
+        if tablebases.wdl.is_empty() {
+            return Err(InputError::new(&format!("No Syzygy tablebase files found in {}", paths)));
+        }
+        return Ok(tablebases);
+    }
+
+    fn add_directory(&mut self, directory: &Path) -> Result<(), InputError> {
+        let entries = fs::read_dir(directory).map_err(|e| InputError::new(&format!("Could not read tablebase directory {}: {}", directory.display(), e)))?;
+        let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.extension().and_then(|e| e.to_str()) != Some(TableKind::Wdl.get_extension()) { continue }
+            let name = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(n) => n,
+                None => continue,
+            };
+            let wdl = match Table::new(TableKind::Wdl, name, path.clone()) {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            if self.keys.contains_key(&wdl.key) { continue }
+            let dtz_path = path.with_extension(TableKind::Dtz.get_extension());
+            let dtz = match dtz_path.is_file() {
+                true => Table::new(TableKind::Dtz, name, dtz_path).ok(),
+                false => None,
+            };
+            self.keys.insert(wdl.key, self.wdl.len());
+            self.keys.insert(wdl.key2, self.wdl.len());
+            self.max_cardinality = self.max_cardinality.max(wdl.piece_count);
+            self.wdl.push(wdl);
+            self.dtz.push(dtz);
+        }
+        return Ok(());
+    }
+
+    pub fn wdl_count(&self) -> usize {
+        return self.wdl.len();
+    }
+
+    pub fn dtz_count(&self) -> usize {
+        return self.dtz.iter().flatten().count();
+    }
+
+    pub fn get_max_cardinality(&self) -> usize {
+        return self.max_cardinality;
+    }
+
+    pub fn can_probe(&self, board: &Board) -> bool {
+        return get_piece_count(board) <= self.max_cardinality && !has_castle_rights(board);
+    }
+
+    pub fn probe_wdl(&self, board: &Board) -> Option<WdlScore> {
+        if !self.can_probe(board) { return None }
+        let mut state = ProbeState::Ok;
+        let wdl = self.search(board, false, &mut state);
+        return match state {
+            ProbeState::Fail => None,
+            _ => Some(wdl),
+        }
+    }
+
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) { return None }
+        let mut state = ProbeState::Ok;
+        let dtz = self.probe_dtz_with_state(board, &mut state);
+        return match state {
+            ProbeState::Fail => None,
+            _ => Some(dtz),
+        }
+    }
+
+    pub fn rank_root_moves(&self, board: &Board) -> Option<Vec<RankedMove>> {
+        if !self.can_probe(board) { return None }
+        let mut moves = self.rank_by_dtz(board).or_else(|| self.rank_by_wdl(board))?;
+        moves.sort_by_key(|m| -m.rank);
+        return Some(moves);
+    }
+
+    pub fn get_best_root_moves<F>(&self, board: &Board, allows_move: F) -> Option<Vec<Move>> where F: Fn(&Move) -> bool {
+        let ranked: Vec<RankedMove> = self.rank_root_moves(board)?.into_iter().filter(|m| allows_move(&m.mov)).collect();
+        let best = ranked.iter().map(|m| m.rank).max()?;
+        return Some(ranked.iter().filter(|m| m.rank == best).map(|m| m.mov).collect());
+    }
+
+    fn rank_by_dtz(&self, board: &Board) -> Option<Vec<RankedMove>> {
+        let halfmove_clock = board.state.halfmove_clock as i32;
+        let mut ranked: Vec<RankedMove> = Vec::new();
+        for mov in board.get_legal_moves() {
+            let mut new_board = *board;
+            new_board.make_move(&mov);
+            let mut state = ProbeState::Ok;
+            let mut dtz = match new_board.state.halfmove_clock {
+                0 => dtz_before_zeroing(self.search(&new_board, false, &mut state).negate()),
+                _ => {
+                    let dtz = -self.probe_dtz_with_state(&new_board, &mut state);
+                    dtz + dtz.signum()
+                },
+            };
+            if dtz == 2 && is_mate(&new_board) {
+                dtz = 1;
+            }
+            if state == ProbeState::Fail { return None }
+            let rank = match dtz {
+                d if d > 0 && d + halfmove_clock <= 99 => WIN_RANK,
+                d if d > 0 => WIN_RANK - (d + halfmove_clock),
+                d if d < 0 && -d * 2 + halfmove_clock < 100 => -WIN_RANK,
+                d if d < 0 => -WIN_RANK + (-d + halfmove_clock),
+                _ => 0,
+            };
+            ranked.push(RankedMove { mov: mov, rank: rank, dtz: Some(dtz) });
+        }
+        return Some(ranked);
+    }
+
+    fn rank_by_wdl(&self, board: &Board) -> Option<Vec<RankedMove>> {
+        let mut ranked: Vec<RankedMove> = Vec::new();
+        for mov in board.get_legal_moves() {
+            let mut new_board = *board;
+            new_board.make_move(&mov);
+            let mut state = ProbeState::Ok;
+            let wdl = self.search(&new_board, false, &mut state).negate();
+            if state == ProbeState::Fail { return None }
+            let rank = match wdl {
+                WdlScore::Win         => WIN_RANK,
+                WdlScore::CursedWin   => CURSED_WIN_RANK,
+                WdlScore::Draw        => 0,
+                WdlScore::BlessedLoss => -CURSED_WIN_RANK,
+                WdlScore::Loss        => -WIN_RANK,
+            };
+            ranked.push(RankedMove { mov: mov, rank: rank, dtz: None });
+        }
+        return Some(ranked);
+    }
+
+    fn probe_table(&self, board: &Board, kind: TableKind, wdl: WdlScore, state: &mut ProbeState) -> i32 {
+        if get_piece_count(board) == 2 { return 0 }
+        let key = get_board_material_key(board);
+        let table = match self.keys.get(&key) {
+            Some(index) => match kind {
+                TableKind::Wdl => Some(&self.wdl[*index]),
+                TableKind::Dtz => self.dtz[*index].as_ref(),
+            },
+            None => None,
+        };
+        let probe = match table {
+            Some(t) => t.probe(board, key, wdl),
+            None => TableProbe::Fail,
+        };
+        return match probe {
+            TableProbe::Value(v) => v,
+            TableProbe::ChangeStm => { *state = ProbeState::ChangeStm; 0 },
+            TableProbe::Fail => { *state = ProbeState::Fail; 0 },
+        }
+    }
+
+    fn search(&self, board: &Board, check_zeroing: bool, state: &mut ProbeState) -> WdlScore {
+        let mut best = WdlScore::Loss;
+        let mut moves = MoveList::new();
+        board.generate_legal_moves(&mut moves);
+        let mut move_count = 0;
+        for mov in &moves {
+            if mov.get_capture().is_none() && (!check_zeroing || !is_zeroing_move(mov)) { continue }
+            move_count += 1;
+            let mut new_board = *board;
+            new_board.make_move(mov);
+            let value = self.search(&new_board, false, state).negate();
+            if *state == ProbeState::Fail { return WdlScore::Draw }
+            if value > best {
+                best = value;
+                if value >= WdlScore::Win {
+                    *state = ProbeState::ZeroingBestMove;
+                    return value;
+                }
+            }
+        }
+        let no_more_moves = move_count > 0 && move_count == moves.len();
+        let value = match no_more_moves {
+            true => best,
+            false => {
+                let value = WdlScore::from_value(self.probe_table(board, TableKind::Wdl, WdlScore::Draw, state));
+                if *state == ProbeState::Fail { return WdlScore::Draw }
+                value
+            },
+        };
+        if best >= value {
+            *state = if best > WdlScore::Draw || no_more_moves { ProbeState::ZeroingBestMove } else { ProbeState::Ok };
+            return best;
+        }
+        *state = ProbeState::Ok;
+        return value;
+    }
+
+    fn probe_dtz_with_state(&self, board: &Board, state: &mut ProbeState) -> i32 {
+        *state = ProbeState::Ok;
+        let wdl = self.search(board, true, state);
+        if *state == ProbeState::Fail || wdl == WdlScore::Draw { return 0 }
+        if *state == ProbeState::ZeroingBestMove { return dtz_before_zeroing(wdl) }
+        let dtz = self.probe_table(board, TableKind::Dtz, wdl, state);
+        if *state == ProbeState::Fail { return 0 }
+        if *state != ProbeState::ChangeStm {
+            let cursed = if wdl == WdlScore::BlessedLoss || wdl == WdlScore::CursedWin { 100 } else { 0 };
+            return (dtz + cursed) * (wdl as i32).signum();
+        }
+        let mut min_dtz = 0xFFFF;
+        let mut moves = MoveList::new();
+        board.generate_legal_moves(&mut moves);
+        for mov in &moves {
+            let zeroing = is_zeroing_move(mov);
+            let mut new_board = *board;
+            new_board.make_move(mov);
+            let mut dtz = match zeroing {
+                true => -dtz_before_zeroing(self.search(&new_board, false, state)),
+                false => -self.probe_dtz_with_state(&new_board, state),
+            };
+            if dtz == 1 && is_mate(&new_board) {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == (wdl as i32).signum() {
+                min_dtz = dtz;
+            }
+            if *state == ProbeState::Fail { return 0 }
+        }
+        return if min_dtz == 0xFFFF { -1 } else { min_dtz };
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SYZYGY_PATH: &str = "SYZYGY_PATH";
+
+    static KNOWN_POSITIONS: [(&str, WdlScore, Option<i32>); 10] = [
+        ("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1", WdlScore::Win, Some(1)),
+        ("8/8/8/8/8/8/1k6/1Q5K b - - 0 1", WdlScore::Draw, Some(0)),
+        ("8/8/8/4k3/8/8/8/3QK3 b - - 0 1", WdlScore::Loss, None),
+        ("k7/8/1K6/8/8/8/8/7R w - - 0 1", WdlScore::Win, Some(1)),
+        ("8/8/8/8/8/8/1k6/1R5K b - - 0 1", WdlScore::Draw, Some(0)),
+        ("8/8/8/4k3/8/8/8/R3K3 b - - 0 1", WdlScore::Loss, None),
+        ("8/4P3/8/8/8/8/k7/2K5 w - - 0 1", WdlScore::Win, Some(1)),
+        ("k7/8/8/8/8/8/P7/K7 w - - 0 1", WdlScore::Draw, Some(0)),
+        ("8/8/8/8/8/8/3kP3/7K b - - 0 1", WdlScore::Draw, Some(0)),
+        ("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", WdlScore::Loss, None),
+    ];
+
+    #[test]
+    fn probes_match_known_values() {
+        let paths = match env::var(SYZYGY_PATH) {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+        let tablebases = Tablebases::from_paths(&paths).unwrap();
+        let mut failures: Vec<String> = Vec::new();
+        for (fen, wdl, dtz) in KNOWN_POSITIONS.iter() {
+            let board = Board::from_fen(fen).unwrap();
+            let probed_wdl = tablebases.probe_wdl(&board);
+            let probed_dtz = tablebases.probe_dtz(&board);
+            if probed_wdl != Some(*wdl) || probed_dtz.is_none() || dtz.is_some_and(|d| probed_dtz != Some(d)) {
+                failures.push(format!("{}: expected {} with DTZ {:?}, found {:?} with DTZ {:?}", fen, wdl.name(), dtz, probed_wdl, probed_dtz));
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
@@ -0,0 +1,735 @@
+use std::{fs::File, os::unix::fs::FileExt, path::PathBuf, sync::OnceLock};
+
+use crate::{rules::{Color, board::{Board, bitboards::BitboardSquares, material::MaterialSignature}, pieces::{Piece, PieceType}}, util::errors::InputError};
+
+use super::WdlScore;
+
+
+pub const TB_PIECES: usize = 7;
+
+static WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+static DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+static HEADER_SPLIT: u8     = 1;
+static HEADER_HAS_PAWNS: u8 = 2;
+
+static FLAG_STM: u8          = 1;
+static FLAG_MAPPED: u8       = 2;
+static FLAG_WIN_PLIES: u8    = 4;
+static FLAG_LOSS_PLIES: u8   = 8;
+static FLAG_WIDE: u8         = 16;
+static FLAG_SINGLE_VALUE: u8 = 128;
+
+static WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+static HEADER_READ_SIZE: usize = 4096;
+
+
+lazy_static! {
+    static ref ENCODING: EncodingTables = EncodingTables::new();
+}
+
+
+struct EncodingTables {
+    map_b1h1h7: [u64; 64],
+    map_a1d1d4: [u64; 64],
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; TB_PIECES],
+    map_pawns: [usize; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+impl EncodingTables {
+    fn new() -> Self {
+        let mut tables = Self {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; TB_PIECES],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                tables.map_b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal: Vec<usize> = Vec::new();
+        code = 0;
+        for square in 0..28 {
+            if off_diagonal(square) < 0 && square & 7 <= 3 {
+                tables.map_a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && square & 7 <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            tables.map_a1d1d4[square] = code;
+            code += 1;
+        }
+
+        let mut both_on_diagonal: Vec<(usize, usize)> = Vec::new();
+        code = 0;
+        for index in 0..10 {
+            for first in 0..28 {
+                if tables.map_a1d1d4[first] != index as u64 || (index == 0 && first != 1) { continue }
+                for second in 0..64 {
+                    if kings_touch(first, second) { continue }
+                    if off_diagonal(first) == 0 && off_diagonal(second) > 0 { continue }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((index, second));
+                    } else {
+                        tables.map_kk[index][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (index, square) in both_on_diagonal {
+            tables.map_kk[index][square] = code;
+            code += 1;
+        }
+
+        tables.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..TB_PIECES.min(n + 1) {
+                tables.binomial[k][n] = (if k > 0 { tables.binomial[k - 1][n - 1] } else { 0 }) + (if k < n { tables.binomial[k][n - 1] } else { 0 });
+            }
+        }
+
+        let mut available = 47i32;
+        for lead_pawns in 1..6 {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..7 {
+                    let square = file + rank * 8;
+                    if lead_pawns == 1 {
+                        tables.map_pawns[square] = available as usize;
+                        tables.map_pawns[square ^ 7] = (available - 1) as usize;
+                        available -= 2;
+                    }
+                    tables.lead_pawn_idx[lead_pawns][square] = idx;
+                    idx += tables.binomial[lead_pawns - 1][tables.map_pawns[square]];
+                }
+                tables.lead_pawns_size[lead_pawns][file] = idx;
+            }
+        }
+        return tables;
+    }
+}
+
+
+fn off_diagonal(square: usize) -> i32 {
+    return (square >> 3) as i32 - (square & 7) as i32;
+}
+
+fn kings_touch(first: usize, second: usize) -> bool {
+    return ((first >> 3) as i32 - (second >> 3) as i32).abs() <= 1 && ((first & 7) as i32 - (second & 7) as i32).abs() <= 1;
+}
+
+fn get_piece_code(piece: Piece) -> u8 {
+    let kind = match piece.piece_type {
+        PieceType::Pawn   => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook   => 4,
+        PieceType::Queen  => 5,
+        PieceType::King   => 6,
+    };
+    return match piece.color {
+        Color::White => kind,
+        Color::Black => kind | 8,
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+impl TableKind {
+    pub fn get_extension(&self) -> &str {
+        return match self {
+            Self::Wdl => "rtbw",
+            Self::Dtz => "rtbz",
+        }
+    }
+
+    fn get_magic(&self) -> &[u8; 4] {
+        return match self {
+            Self::Wdl => &WDL_MAGIC,
+            Self::Dtz => &DTZ_MAGIC,
+        }
+    }
+}
+
+
+pub enum TableProbe {
+    Value(i32),
+    ChangeStm,
+    Fail,
+}
+
+
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    num_blocks: usize,
+    block_size: usize,
+    span: usize,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; TB_PIECES],
+    group_idx: [u64; TB_PIECES + 1],
+    group_len: [usize; TB_PIECES + 1],
+    map_idx: [usize; 4],
+}
+
+
+struct TableData {
+    file: File,
+    size: usize,
+    bytes: Vec<u8>,
+    items: Vec<Vec<PairsData>>,
+    sides: usize,
+    map: usize,
+}
+
+impl TableData {
+    fn read_header(&mut self, end: usize) -> Result<(), InputError> {
+        if end <= self.bytes.len() { return Ok(()) }
+        if end > self.size {
+            return Err(InputError::new("Tablebase header is truncated"));
+        }
+        let start = self.bytes.len();
+        self.bytes.resize(end.max(start + HEADER_READ_SIZE).min(self.size), 0);
+        return self.file.read_exact_at(&mut self.bytes[start..], start as u64).map_err(|e| InputError::new(&format!("Could not read tablebase header: {}", e)));
+    }
+
+    fn read_file(&self, offset: usize, length: usize) -> Option<Vec<u8>> {
+        let mut buffer = vec![0u8; length];
+        self.file.read_exact_at(&mut buffer, offset as u64).ok()?;
+        return Some(buffer);
+    }
+
+    fn read_u8(&self, offset: usize) -> u8 {
+        return self.bytes.get(offset).copied().unwrap_or(0);
+    }
+
+    fn read_u16_le(&self, offset: usize) -> u16 {
+        return u16::from_le_bytes([self.read_u8(offset), self.read_u8(offset + 1)]);
+    }
+
+    fn read_u32_le(&self, offset: usize) -> u32 {
+        return u32::from_le_bytes([self.read_u8(offset), self.read_u8(offset + 1), self.read_u8(offset + 2), self.read_u8(offset + 3)]);
+    }
+
+    fn get_left(&self, btree: usize, sym: usize) -> usize {
+        let offset = btree + sym * 3;
+        return ((self.read_u8(offset + 1) as usize & 0xF) << 8) | self.read_u8(offset) as usize;
+    }
+
+    fn get_right(&self, btree: usize, sym: usize) -> usize {
+        let offset = btree + sym * 3;
+        return ((self.read_u8(offset + 2) as usize) << 4) | (self.read_u8(offset + 1) as usize >> 4);
+    }
+
+    fn get(&self, stm: usize, file: usize) -> &PairsData {
+        return &self.items[stm % self.sides][file];
+    }
+
+    fn set_sizes(&mut self, d: &mut PairsData, mut offset: usize) -> Result<usize, InputError> {
+        self.read_header(offset + 9)?;
+        d.flags = self.read_u8(offset);
+        offset += 1;
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            d.min_sym_len = self.read_u8(offset);
+            return Ok(offset + 1);
+        }
+        let groups = d.group_len.iter().position(|l| *l == 0).unwrap_or(TB_PIECES);
+        let tb_size = d.group_idx[groups];
+        d.block_size = 1usize << self.read_u8(offset);
+        d.span = 1usize << self.read_u8(offset + 1);
+        d.sparse_index_size = tb_size.div_ceil(d.span as u64) as usize;
+        let padding = self.read_u8(offset + 2) as usize;
+        d.num_blocks = self.read_u32_le(offset + 3) as usize;
+        d.block_length_size = d.num_blocks + padding;
+        d.max_sym_len = self.read_u8(offset + 7);
+        d.min_sym_len = self.read_u8(offset + 8);
+        offset += 9;
+        if d.max_sym_len < d.min_sym_len || d.min_sym_len == 0 {
+            return Err(InputError::new("Tablebase symbol lengths are corrupted"));
+        }
+        d.lowest_sym = offset;
+        let lengths = (d.max_sym_len - d.min_sym_len) as usize + 1;
+        self.read_header(offset + lengths * 2 + 2)?;
+        d.base64 = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            d.base64[i] = d.base64[i + 1].wrapping_add(self.read_u16_le(d.lowest_sym + i * 2) as u64).wrapping_sub(self.read_u16_le(d.lowest_sym + i * 2 + 2) as u64) / 2;
+        }
+        for (i, base) in d.base64.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - i as u32 - d.min_sym_len as u32).unwrap_or(0);
+        }
+        offset += lengths * 2;
+        let symbols = self.read_u16_le(offset) as usize;
+        offset += 2;
+        d.btree = offset;
+        self.read_header(offset + symbols * 3 + (symbols & 1))?;
+        d.symlen = vec![0u8; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                d.symlen[sym] = self.set_symlen(d, sym, &mut visited);
+            }
+        }
+        return Ok(offset + symbols * 3 + (symbols & 1));
+    }
+
+    fn set_symlen(&self, d: &mut PairsData, sym: usize, visited: &mut [bool]) -> u8 {
+        visited[sym] = true;
+        let right = self.get_right(d.btree, sym);
+        if right == 0xFFF { return 0 }
+        let left = self.get_left(d.btree, sym);
+        if left >= d.symlen.len() || right >= d.symlen.len() { return 0 }
+        if !visited[left] {
+            d.symlen[left] = self.set_symlen(d, left, visited);
+        }
+        if !visited[right] {
+            d.symlen[right] = self.set_symlen(d, right, visited);
+        }
+        return d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1);
+    }
+
+    fn set_dtz_map(&mut self, mut offset: usize, max_file: usize) -> Result<usize, InputError> {
+        self.map = offset;
+        for file in 0..=max_file {
+            let flags = self.items[0][file].flags;
+            if flags & FLAG_MAPPED == 0 { continue }
+            if flags & FLAG_WIDE != 0 {
+                offset += offset & 1;
+                for i in 0..4 {
+                    self.read_header(offset + 2)?;
+                    self.items[0][file].map_idx[i] = (offset - self.map) / 2 + 1;
+                    offset += 2 * self.read_u16_le(offset) as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.read_header(offset + 1)?;
+                    self.items[0][file].map_idx[i] = offset - self.map + 1;
+                    offset += self.read_u8(offset) as usize + 1;
+                }
+            }
+        }
+        self.read_header(offset)?;
+        return Ok(offset + (offset & 1));
+    }
+
+    fn decompress_pairs(&self, d: &PairsData, idx: u64) -> Option<i32> {
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as i32);
+        }
+        let k = (idx / d.span as u64) as usize;
+        let entry = self.read_file(d.sparse_index + k * 6, 6)?;
+        let mut block = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize;
+        let mut offset = u16::from_le_bytes([entry[4], entry[5]]) as i64;
+        offset += (idx % d.span as u64) as i64 - (d.span / 2) as i64;
+        let block_length = |block: usize| self.read_file(d.block_length + block * 2, 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as i64);
+        while offset < 0 {
+            if block == 0 { return None }
+            block -= 1;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+            if block >= d.block_length_size { return None }
+        }
+
+        let start = d.data + block * d.block_size;
+        let bytes = self.read_file(start, d.block_size.min(self.size.saturating_sub(start)))?;
+        let read_u32_be = |ptr: usize| u32::from_be_bytes([0, 1, 2, 3].map(|i| bytes.get(ptr + i).copied().unwrap_or(0)));
+        let mut buf64 = (read_u32_be(0) as u64) << 32 | read_u32_be(4) as u64;
+        let mut ptr = 8;
+        let mut buf64_size = 64;
+        let mut sym: usize;
+        loop {
+            let mut len = 0;
+            while buf64 < d.base64[len] {
+                len += 1;
+                if len >= d.base64.len() { return None }
+            }
+            sym = ((buf64 - d.base64[len]) >> (64 - len - d.min_sym_len as usize)) as usize;
+            sym = (sym + self.read_u16_le(d.lowest_sym + len * 2) as usize) & 0xFFFF;
+            let expansion = match d.symlen.get(sym) {
+                Some(l) => *l as i64 + 1,
+                None => return None,
+            };
+            if offset < expansion { break }
+            offset -= expansion;
+            len += d.min_sym_len as usize;
+            buf64 <<= len;
+            buf64_size -= len as i32;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (read_u32_be(ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        while d.symlen[sym] != 0 {
+            let left = self.get_left(d.btree, sym);
+            let left_expansion = match d.symlen.get(left) {
+                Some(l) => *l as i64 + 1,
+                None => return None,
+            };
+            if offset < left_expansion {
+                sym = left;
+            } else {
+                offset -= left_expansion;
+                sym = self.get_right(d.btree, sym);
+                if sym >= d.symlen.len() { return None }
+            }
+        }
+        return Some(self.get_left(d.btree, sym) as i32);
+    }
+}
+
+
+pub struct Table {
+    pub kind: TableKind,
+    pub key: u64,
+    pub key2: u64,
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    has_unique_pieces: bool,
+    pawn_count: [usize; 2],
+    path: PathBuf,
+    data: OnceLock<Option<TableData>>,
+}
+
+impl Table {
+    pub fn new(kind: TableKind, name: &str, path: PathBuf) -> Result<Self, InputError> {
+        let signature = MaterialSignature::from_notation(name)?;
+        let piece_count = signature.get_piece_count() as usize;
+        if piece_count > TB_PIECES {
+            return Err(InputError::new(&format!("Tablebase {} has more than {} pieces", name, TB_PIECES)));
+        }
+        let pawns = [signature.get_count(Color::White, PieceType::Pawn) as usize, signature.get_count(Color::Black, PieceType::Pawn) as usize];
+        let lead_white = pawns[1] == 0 || (pawns[0] > 0 && pawns[1] >= pawns[0]);
+        return Ok(Self {
+            kind: kind,
+            key: signature.get_key(),
+            key2: signature.swap().get_key(),
+            piece_count: piece_count,
+            has_pawns: pawns[0] + pawns[1] > 0,
+            has_unique_pieces: Color::iter().any(|c| PieceType::iter().any(|t| t != PieceType::King && signature.get_count(c, t) == 1)),
+            pawn_count: if lead_white { pawns } else { [pawns[1], pawns[0]] },
+            path: path,
+            data: OnceLock::new(),
+        })
+    }
+
+    fn get_data(&self) -> Option<&TableData> {
+        return self.data.get_or_init(|| self.load().ok()).as_ref();
+    }
+
+    fn load(&self) -> Result<TableData, InputError> {
+        let file = File::open(&self.path).map_err(|e| InputError::new(&format!("Could not read tablebase {}: {}", self.path.display(), e)))?;
+        let size = file.metadata().map_err(|e| InputError::new(&format!("Could not read tablebase {}: {}", self.path.display(), e)))?.len() as usize;
+        let mut data = TableData {
+            file: file,
+            size: size,
+            bytes: Vec::new(),
+            items: vec![vec![PairsData::default(); 4]; 2],
+            sides: if self.kind == TableKind::Wdl { 2 } else { 1 },
+            map: 0,
+        };
+        if size % 64 != 16 || data.read_header(5).is_err() || !data.bytes.starts_with(self.kind.get_magic()) {
+            return Err(InputError::new(&format!("Tablebase {} is corrupted", self.path.display())));
+        }
+        let header = data.read_u8(4);
+        if (header & HEADER_HAS_PAWNS != 0) != self.has_pawns || (header & HEADER_SPLIT != 0) != (self.key != self.key2) {
+            return Err(InputError::new(&format!("Tablebase {} does not match its material", self.path.display())));
+        }
+        let mut offset = 5;
+        let sides = if self.kind == TableKind::Wdl && self.key != self.key2 { 2 } else { 1 };
+        let max_file = if self.has_pawns { 3 } else { 0 };
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+
+        for file in 0..=max_file {
+            data.read_header(offset + 2 + self.piece_count)?;
+            let first = data.read_u8(offset);
+            let second = if both_pawns { data.read_u8(offset + 1) } else { 0xFF };
+            let order = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            offset += 1 + both_pawns as usize;
+            for k in 0..self.piece_count {
+                let byte = data.read_u8(offset);
+                for side in 0..sides {
+                    data.items[side][file].pieces[k] = if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+                offset += 1;
+            }
+            for (side, side_order) in order.iter().enumerate().take(sides) {
+                self.set_groups(&mut data.items[side][file], *side_order, file);
+            }
+        }
+        offset += offset & 1;
+
+        for file in 0..=max_file {
+            for side in 0..sides {
+                let mut d = std::mem::take(&mut data.items[side][file]);
+                offset = data.set_sizes(&mut d, offset)?;
+                data.items[side][file] = d;
+            }
+        }
+        if self.kind == TableKind::Dtz {
+            offset = data.set_dtz_map(offset, max_file)?;
+        }
+        for file in 0..=max_file {
+            for side in 0..sides {
+                let d = &mut data.items[side][file];
+                d.sparse_index = offset;
+                offset += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..=max_file {
+            for side in 0..sides {
+                let d = &mut data.items[side][file];
+                d.block_length = offset;
+                offset += d.block_length_size * 2;
+            }
+        }
+        for file in 0..=max_file {
+            for side in 0..sides {
+                offset = (offset + 0x3F) & !0x3F;
+                let d = &mut data.items[side][file];
+                d.data = offset;
+                offset += d.num_blocks * d.block_size;
+            }
+        }
+        if offset > data.size {
+            return Err(InputError::new(&format!("Tablebase {} is truncated", self.path.display())));
+        }
+        return Ok(data);
+    }
+
+    fn set_groups(&self, d: &mut PairsData, order: [u8; 2], file: usize) {
+        let mut n = 0;
+        let mut first_len: i32 = if self.has_pawns { 0 } else if self.has_unique_pieces { 3 } else { 2 };
+        d.group_len[0] = 1;
+        for i in 1..self.piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        let both_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+        let mut idx = 1u64;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                d.group_idx[0] = idx;
+                idx *= if self.has_pawns {
+                    ENCODING.lead_pawns_size[d.group_len[0]][file]
+                } else if self.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] as usize {
+                d.group_idx[1] = idx;
+                idx *= ENCODING.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= ENCODING.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+    }
+
+    fn check_dtz_stm(&self, data: &TableData, stm: usize, file: usize) -> bool {
+        if self.kind == TableKind::Wdl { return true }
+        return (data.get(0, file).flags & FLAG_STM) as usize == stm || (self.key == self.key2 && !self.has_pawns);
+    }
+
+    fn map_score(&self, data: &TableData, file: usize, value: i32, wdl: WdlScore) -> i32 {
+        if self.kind == TableKind::Wdl {
+            return value - 2;
+        }
+        let d = data.get(0, file);
+        let mut value = value;
+        if d.flags & FLAG_MAPPED != 0 {
+            let index = d.map_idx[WDL_MAP[(wdl as i32 + 2) as usize]] + value as usize;
+            value = match d.flags & FLAG_WIDE != 0 {
+                true => data.read_u16_le(data.map + index * 2) as i32,
+                false => data.read_u8(data.map + index) as i32,
+            };
+        }
+        if (wdl == WdlScore::Win && d.flags & FLAG_WIN_PLIES == 0) ||
+            (wdl == WdlScore::Loss && d.flags & FLAG_LOSS_PLIES == 0) ||
+            wdl == WdlScore::CursedWin ||
+            wdl == WdlScore::BlessedLoss
+        {
+            value *= 2;
+        }
+        return value + 1;
+    }
+
+    pub fn probe(&self, board: &Board, material_key: u64, wdl: WdlScore) -> TableProbe {
+        let data = match self.get_data() {
+            Some(d) => d,
+            None => return TableProbe::Fail,
+        };
+        let black_to_move = board.state.get_move_color() == Color::Black;
+        let flip = (self.key == self.key2 && black_to_move) || material_key != self.key;
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = (flip ^ black_to_move) as usize;
+
+        let mut squares = [0usize; TB_PIECES];
+        let mut pieces = [0u8; TB_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0u64;
+        let mut lead_pawns_count = 0;
+        let mut file = 0;
+
+        if self.has_pawns {
+            let color = match data.get(0, 0).pieces[0] ^ flip_color {
+                c if c & 8 == 0 => Color::White,
+                _ => Color::Black,
+            };
+            lead_pawns = board.position.get_piece_locations(color, PieceType::Pawn);
+            for square in BitboardSquares::from_board(lead_pawns) {
+                squares[size] = square as usize ^ flip_squares;
+                size += 1;
+            }
+            lead_pawns_count = size;
+            let lead = (0..lead_pawns_count).fold(0, |best, i| if ENCODING.map_pawns[squares[i]] > ENCODING.map_pawns[squares[best]] { i } else { best });
+            squares.swap(0, lead);
+            file = (squares[0] & 7).min(7 - (squares[0] & 7));
+        }
+
+        if !self.check_dtz_stm(data, stm, file) {
+            return TableProbe::ChangeStm;
+        }
+
+        let occupied = board.position.get_all_piece_locations(Color::White) | board.position.get_all_piece_locations(Color::Black);
+        for square in BitboardSquares::from_board(occupied ^ lead_pawns) {
+            if size >= TB_PIECES { return TableProbe::Fail }
+            let piece = match board.position.piece_at(&square) {
+                Some(p) => p,
+                None => return TableProbe::Fail,
+            };
+            squares[size] = square as usize ^ flip_squares;
+            pieces[size] = get_piece_code(piece) ^ flip_color;
+            size += 1;
+        }
+        if size != self.piece_count { return TableProbe::Fail }
+
+        let d = data.get(stm, file);
+        for i in lead_pawns_count..size - 1 {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        if squares[0] & 7 > 3 {
+            squares[..size].iter_mut().for_each(|s| *s ^= 7);
+        }
+
+        let mut idx: u64;
+        if self.has_pawns {
+            idx = ENCODING.lead_pawn_idx[lead_pawns_count][squares[0]];
+            squares[1..lead_pawns_count].sort_by_key(|s| ENCODING.map_pawns[*s]);
+            for (i, square) in squares.iter().enumerate().take(lead_pawns_count).skip(1) {
+                idx += ENCODING.binomial[i][ENCODING.map_pawns[*square]];
+            }
+        } else {
+            if squares[0] >> 3 > 3 {
+                squares[..size].iter_mut().for_each(|s| *s ^= 56);
+            }
+            let first_off_diagonal = squares[..d.group_len[0]].iter().enumerate().map(|(i, s)| (i, off_diagonal(*s))).find(|(_, o)| *o != 0);
+            if let Some((i, _)) = first_off_diagonal.filter(|(_, o)| *o > 0) {
+                squares[i..size].iter_mut().for_each(|s| *s = ((*s >> 3) | (*s << 3)) & 63);
+            }
+            idx = self.encode_leading_pieces(&squares);
+        }
+
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] > 0 {
+            let len = d.group_len[next];
+            squares[group_start..group_start + len].sort();
+            let mut n = 0u64;
+            for i in 0..len {
+                let square = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|s| square > **s).count();
+                n += ENCODING.binomial[i + 1][square - adjust - if remaining_pawns { 8 } else { 0 }];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start += len;
+            next += 1;
+        }
+
+        let value = match data.decompress_pairs(d, idx) {
+            Some(v) => v,
+            None => return TableProbe::Fail,
+        };
+        return TableProbe::Value(self.map_score(data, file, value, wdl));
+    }
+
+    fn encode_leading_pieces(&self, squares: &[usize; TB_PIECES]) -> u64 {
+        if !self.has_unique_pieces {
+            return ENCODING.map_kk[ENCODING.map_a1d1d4[squares[0]] as usize][squares[1]];
+        }
+        let adjust1 = (squares[1] > squares[0]) as usize;
+        let adjust2 = (squares[2] > squares[0]) as usize + (squares[2] > squares[1]) as usize;
+        let rank = |square: usize| (square >> 3) as u64;
+        if off_diagonal(squares[0]) != 0 {
+            return (ENCODING.map_a1d1d4[squares[0]] * 63 + (squares[1] - adjust1) as u64) * 62 + (squares[2] - adjust2) as u64;
+        }
+        if off_diagonal(squares[1]) != 0 {
+            return (6 * 63 + rank(squares[0]) * 28 + ENCODING.map_b1h1h7[squares[1]]) * 62 + (squares[2] - adjust2) as u64;
+        }
+        if off_diagonal(squares[2]) != 0 {
+            return 6 * 63 * 62 + 4 * 28 * 62 + rank(squares[0]) * 7 * 28 + (rank(squares[1]) - adjust1 as u64) * 28 + ENCODING.map_b1h1h7[squares[2]];
+        }
+        return 6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(squares[0]) * 7 * 6 + (rank(squares[1]) - adjust1 as u64) * 6 + (rank(squares[2]) - adjust2 as u64);
+    }
+}
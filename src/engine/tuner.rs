@@ -0,0 +1,347 @@
+use std::{fs, time::{Duration, Instant}};
+
+use tabled::Tabled;
+
+use crate::{game::GameResult, rules::{board::{Board, move_list::MoveList}, Color}, util::{epd::EpdRecord, errors::InputError, pgn::read_pgn_file}};
+
+use super::{endgame::{Endgames, EndgameEvaluation, SCALE_NORMAL}, parameters::{EvaluationParameters, PARAMETER_COUNT}};
+
+
+static MAX_QUIET_PLY: u8 = 8;
+static MIN_SCALING_CONSTANT: f64 = 0.05;
+static MAX_SCALING_CONSTANT: f64 = 10.0;
+static MIN_ERROR_IMPROVEMENT: f64 = 1e-12;
+static SCALING_CONSTANT_ITERATIONS: usize = 100;
+static MAX_REPORTED_ERRORS: usize = 10;
+
+
+fn parse_result(label: &str, allow_decimal: bool) -> Option<f64> {
+    let label = label.trim().trim_matches(|c| c == '"' || c == ';');
+    return match GameResult::from_notation(label) {
+        Some(GameResult::WhiteWins) => Some(1.0),
+        Some(GameResult::BlackWins) => Some(0.0),
+        Some(GameResult::Draw) => Some(0.5),
+        _ if allow_decimal => label.parse::<f64>().ok().filter(|r| (0.0..=1.0).contains(r)),
+        _ => None,
+    }
+}
+
+fn sigmoid(score: f64, k: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-k * score / 400.0));
+}
+
+fn get_relative_material(board: &Board, parameters: &EvaluationParameters) -> i32 {
+    return parameters.evaluate(board, true) as i32 * match board.state.get_move_color() {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+}
+
+fn quiescence(board: &Board, mut alpha: i32, beta: i32, parameters: &EvaluationParameters, ply: u8) -> i32 {
+    let stand_pat = get_relative_material(board, parameters);
+    if stand_pat >= beta || ply >= MAX_QUIET_PLY {
+        return stand_pat;
+    }
+    alpha = alpha.max(stand_pat);
+    let mut moves = MoveList::new();
+    board.generate_captures(&mut moves);
+    for mov in moves {
+        let mut next = *board;
+        next.make_move(&mov);
+        let score = -quiescence(&next, -beta, -alpha, parameters, ply + 1);
+        if score >= beta {
+            return score;
+        }
+        alpha = alpha.max(score);
+    }
+    return alpha;
+}
+
+
+pub struct LabelledPosition {
+    pub board: Board,
+    pub result: f64,
+}
+
+impl LabelledPosition {
+    pub fn from_line(line: &str) -> Result<Self, InputError> {
+        let line = line.trim();
+        let (position, label) = match line.rsplit_once('[') {
+            Some((p, l)) => (p, parse_result(l.trim_end().trim_end_matches(']'), true)),
+            None => match line.rsplit_once(char::is_whitespace) {
+                Some((p, l)) if parse_result(l, false).is_some() => (p, parse_result(l, false)),
+                _ => (line, None),
+            },
+        };
+        let record = EpdRecord::from_epd(position)?;
+        let result = label.or(record.operations.get("c9").and_then(|o| o.first()).and_then(|r| parse_result(r, true)));
+        return match result {
+            Some(r) => Ok(Self {
+                board: Board::from_fen(&record.fen)?,
+                result: r,
+            }),
+            None => Err(InputError::new(&format!("Position '{}' does not have a game result label", line))),
+        }
+    }
+
+    pub fn from_pgn_file(path: &str) -> Result<(Vec<Self>, Vec<String>), InputError> {
+        let mut positions: Vec<Self> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (number, game) in read_pgn_file(path)?.iter().enumerate() {
+            let result = match parse_result(game.get_result().get_notation(), false) {
+                Some(r) => r,
+                None => continue,
+            };
+            let mut boards: Vec<Board> = Vec::new();
+            match game.replay(usize::MAX, |g, _| boards.push(*g.get_board())) {
+                Ok(_) => positions.extend(boards.into_iter().map(|b| Self { board: b, result: result })),
+                Err(e) if errors.len() < MAX_REPORTED_ERRORS => errors.push(format!("Game {}: {}", number + 1, e.msg)),
+                Err(_) => (),
+            }
+        }
+        return Ok((positions, errors));
+    }
+
+    pub fn from_file(path: &str) -> Result<(Vec<Self>, Vec<String>), InputError> {
+        if path.to_lowercase().ends_with(".pgn") {
+            return Self::from_pgn_file(path);
+        }
+        let contents = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => return Err(InputError::new(&format!("Could not read position file {}: {}", path, e))),
+        };
+        let mut positions: Vec<Self> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (number, line) in contents.lines().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#')) {
+            match Self::from_line(line) {
+                Ok(p) => positions.push(p),
+                Err(e) if errors.len() < MAX_REPORTED_ERRORS => errors.push(format!("Line {}: {}", number + 1, e.msg)),
+                Err(_) => (),
+            }
+        }
+        return Ok((positions, errors));
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub struct TunerOptions {
+    pub passes: usize,
+    pub step: i16,
+    pub scaling_constant: Option<f64>,
+    pub material_only: bool,
+}
+
+impl Default for TunerOptions {
+    fn default() -> Self {
+        return Self {
+            passes: 100,
+            step: 8,
+            scaling_constant: None,
+            material_only: false,
+        }
+    }
+}
+
+
+#[derive(Clone, Default)]
+pub struct TunerSummary {
+    pub positions_read: usize,
+    pub positions_used: usize,
+    pub skipped_noisy: usize,
+    pub skipped_known: usize,
+    pub scaling_constant: f64,
+    pub initial_error: f64,
+    pub final_error: f64,
+    pub changed: usize,
+}
+
+
+pub struct TunerPass {
+    pub pass: usize,
+    pub step: i16,
+    pub error: f64,
+    pub improved: usize,
+    pub duration: Duration,
+}
+
+impl TunerPass {
+    pub fn to_printable(&self) -> PrintableTunerPass {
+        return PrintableTunerPass {
+            pass: self.pass.to_string(),
+            step: self.step.to_string(),
+            error: format!("{:.8}", self.error),
+            improved: self.improved.to_string(),
+            duration: format!("{:?}", self.duration),
+        }
+    }
+}
+
+
+struct TuningEntry {
+    result: f64,
+    evaluation: f64,
+}
+
+
+pub struct Tuner {
+    options: TunerOptions,
+    initial: EvaluationParameters,
+    parameters: EvaluationParameters,
+    entries: Vec<TuningEntry>,
+    occurrences: Vec<Vec<(u32, f64)>>,
+    summary: TunerSummary,
+}
+
+impl Tuner {
+    pub fn new(parameters: EvaluationParameters, options: TunerOptions) -> Self {
+        return Self {
+            options: options,
+            initial: parameters.clone(),
+            parameters: parameters,
+            entries: Vec::new(),
+            occurrences: vec![Vec::new(); PARAMETER_COUNT],
+            summary: Default::default(),
+        }
+    }
+
+    pub fn add_positions(&mut self, positions: &[LabelledPosition]) {
+        for position in positions {
+            self.summary.positions_read += 1;
+            if position.board.in_check() || Self::is_noisy(&position.board, &self.parameters) {
+                self.summary.skipped_noisy += 1;
+                continue;
+            }
+            let scale = match Endgames::probe(&position.board) {
+                Some(EndgameEvaluation::Exact(_)) | Some(EndgameEvaluation::Scaled(0)) => {
+                    self.summary.skipped_known += 1;
+                    continue;
+                },
+                Some(EndgameEvaluation::Scaled(factor)) => factor as f64 / SCALE_NORMAL as f64,
+                None => 1.0,
+            };
+            let mut features: Vec<(usize, f64)> = Vec::new();
+            EvaluationParameters::for_each_feature(&position.board, false, |index, sign| {
+                match features.iter_mut().find(|(i, _)| *i == index) {
+                    Some((_, coefficient)) => *coefficient += sign as f64 * scale,
+                    None => features.push((index, sign as f64 * scale)),
+                }
+            });
+            let entry = self.entries.len() as u32;
+            let mut evaluation = 0.0;
+            for (index, coefficient) in features.into_iter().filter(|(_, c)| *c != 0.0) {
+                evaluation += coefficient * self.parameters.get(index) as f64;
+                self.occurrences[index].push((entry, coefficient));
+            }
+            self.entries.push(TuningEntry {
+                result: position.result,
+                evaluation: evaluation,
+            });
+            self.summary.positions_used += 1;
+        }
+    }
+
+    pub fn get_parameters(&self) -> &EvaluationParameters {
+        return &self.parameters;
+    }
+
+    pub fn get_summary(&self) -> &TunerSummary {
+        return &self.summary;
+    }
+
+    pub fn fit_scaling_constant(&self) -> f64 {
+        let (mut low, mut high) = (0.0, MAX_SCALING_CONSTANT);
+        for _ in 0..SCALING_CONSTANT_ITERATIONS {
+            let (a, b) = (low + (high - low) / 3.0, high - (high - low) / 3.0);
+            match self.get_error(a) < self.get_error(b) {
+                true => high = b,
+                false => low = a,
+            }
+        }
+        return (low + high) / 2.0;
+    }
+
+    pub fn get_error(&self, k: f64) -> f64 {
+        let total: f64 = self.entries.iter().map(|e| (e.result - sigmoid(e.evaluation, k)).powi(2)).sum();
+        return total / self.entries.len().max(1) as f64;
+    }
+
+    pub fn run<F>(&mut self, mut on_pass: F) -> Result<TunerSummary, InputError> where F: FnMut(&TunerPass) {
+        let k = self.options.scaling_constant.unwrap_or_else(|| self.fit_scaling_constant());
+        if k < MIN_SCALING_CONSTANT {
+            return Err(InputError::new(&format!("Scaling constant K = {:.4} is below {} and would make every evaluation look equal, tune on more positions or pass a larger --k", k, MIN_SCALING_CONSTANT)));
+        }
+        self.summary.scaling_constant = k;
+        self.summary.initial_error = self.get_error(k);
+        let count = self.entries.len().max(1) as f64;
+        let mut total = self.summary.initial_error * count;
+        let mut step = self.options.step.max(1);
+        for pass in 1..=self.options.passes {
+            let start = Instant::now();
+            let mut improved = 0;
+            for index in 0..PARAMETER_COUNT {
+                if self.occurrences[index].is_empty() || (self.options.material_only && !EvaluationParameters::is_piece_value(index)) {
+                    continue;
+                }
+                for delta in [step, -step] {
+                    let value = match self.parameters.get(index).checked_add(delta) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+                    let change = self.get_error_change(index, delta as f64, k);
+                    if change < -MIN_ERROR_IMPROVEMENT * count {
+                        self.apply_change(index, delta as f64);
+                        self.parameters.set(index, value);
+                        total += change;
+                        improved += 1;
+                        break;
+                    }
+                }
+            }
+            on_pass(&TunerPass {
+                pass: pass,
+                step: step,
+                error: total / count,
+                improved: improved,
+                duration: start.elapsed(),
+            });
+            if improved == 0 {
+                if step == 1 {
+                    break;
+                }
+                step = (step / 2).max(1);
+            }
+        }
+        self.summary.final_error = self.get_error(k);
+        self.summary.changed = (0..PARAMETER_COUNT).filter(|i| self.parameters.get(*i) != self.initial.get(*i)).count();
+        return Ok(self.summary.clone());
+    }
+
+    fn get_error_change(&self, index: usize, delta: f64, k: f64) -> f64 {
+        return self.occurrences[index].iter().map(|(entry, coefficient)| {
+            let entry = &self.entries[*entry as usize];
+            (entry.result - sigmoid(entry.evaluation + coefficient * delta, k)).powi(2) - (entry.result - sigmoid(entry.evaluation, k)).powi(2)
+        }).sum();
+    }
+
+    fn apply_change(&mut self, index: usize, delta: f64) {
+        for (entry, coefficient) in &self.occurrences[index] {
+            self.entries[*entry as usize].evaluation += coefficient * delta;
+        }
+    }
+
+    fn is_noisy(board: &Board, parameters: &EvaluationParameters) -> bool {
+        let stand_pat = get_relative_material(board, parameters);
+        return quiescence(board, stand_pat, stand_pat + 1, parameters, 0) > stand_pat;
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableTunerPass {
+    pub pass: String,
+    pub step: String,
+    pub error: String,
+    pub improved: String,
+    pub duration: String,
+}
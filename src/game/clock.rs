@@ -0,0 +1,324 @@
+use std::time::{Duration, Instant};
+
+use tabled::Tabled;
+
+use crate::{rules::Color, util::errors::InputError};
+
+
+fn parse_seconds(value: &str, notation: &str) -> Result<Duration, InputError> {
+    return value.parse::<f64>().ok().filter(|s| s.is_finite() && *s >= 0.0).map(Duration::from_secs_f64)
+        .ok_or(InputError::new(&format!("'{}' is not a valid time control", notation)));
+}
+
+pub fn format_clock_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    let (hours, minutes, seconds) = (tenths / 36_000, tenths / 600 % 60, tenths % 600);
+    return match hours {
+        0 => format!("{}:{:02}.{}", minutes, seconds / 10, seconds % 10),
+        h => format!("{}:{:02}:{:02}.{}", h, minutes, seconds / 10, seconds % 10),
+    }
+}
+
+fn format_seconds(time: Duration) -> String {
+    return match time.subsec_millis() {
+        0 => time.as_secs().to_string(),
+        _ => format!("{}", time.as_secs_f64()),
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimeBonus {
+    None,
+    Increment(Duration),
+    SimpleDelay(Duration),
+    BronsteinDelay(Duration),
+}
+
+impl TimeBonus {
+    pub fn get_duration(&self) -> Duration {
+        return match self {
+            Self::None => Duration::ZERO,
+            Self::Increment(d) | Self::SimpleDelay(d) | Self::BronsteinDelay(d) => *d,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            Self::None => String::from("none"),
+            Self::Increment(d) => format!("{}s increment", format_seconds(*d)),
+            Self::SimpleDelay(d) => format!("{}s delay", format_seconds(*d)),
+            Self::BronsteinDelay(d) => format!("{}s Bronstein delay", format_seconds(*d)),
+        }
+    }
+
+    fn to_notation(&self) -> String {
+        return match self {
+            Self::None => String::new(),
+            Self::Increment(d) => format!("+{}", format_seconds(*d)),
+            Self::SimpleDelay(d) => format!("d{}", format_seconds(*d)),
+            Self::BronsteinDelay(d) => format!("b{}", format_seconds(*d)),
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeControlPeriod {
+    pub moves: Option<u16>,
+    pub time: Duration,
+    pub bonus: TimeBonus,
+}
+
+impl TimeControlPeriod {
+    fn from_notation(period: &str, notation: &str) -> Result<Self, InputError> {
+        let invalid = || InputError::new(&format!("'{}' is not a valid time control", notation));
+        let (moves, rest) = match period.split_once('/') {
+            Some((m, r)) => (Some(m.parse::<u16>().ok().filter(|m| *m > 0).ok_or_else(invalid)?), r),
+            None => (None, period),
+        };
+        let (time, bonus) = match rest.find(['+', 'd', 'b']) {
+            Some(index) => {
+                let amount = parse_seconds(&rest[index + 1..], notation)?;
+                (&rest[..index], match &rest[index..index + 1] {
+                    "+" => TimeBonus::Increment(amount),
+                    "d" => TimeBonus::SimpleDelay(amount),
+                    _ => TimeBonus::BronsteinDelay(amount),
+                })
+            },
+            None => (rest, TimeBonus::None),
+        };
+        let time = match time.strip_suffix('s') {
+            Some(seconds) => parse_seconds(seconds, notation)?,
+            None => parse_seconds(time, notation)? * 60,
+        };
+        if time.is_zero() {
+            return Err(invalid());
+        }
+        return Ok(Self {
+            moves: moves,
+            time: time,
+            bonus: bonus,
+        });
+    }
+
+    fn to_notation(&self) -> String {
+        let time = match self.time.as_secs() % 60 == 0 && self.time.subsec_millis() == 0 {
+            true => (self.time.as_secs() / 60).to_string(),
+            false => format!("{}s", format_seconds(self.time)),
+        };
+        return match self.moves {
+            Some(m) => format!("{}/{}{}", m, time, self.bonus.to_notation()),
+            None => format!("{}{}", time, self.bonus.to_notation()),
+        }
+    }
+
+    fn to_pgn_notation(&self) -> String {
+        let mut notation = match self.moves {
+            Some(m) => format!("{}/{}", m, format_seconds(self.time)),
+            None => format_seconds(self.time),
+        };
+        if let TimeBonus::Increment(d) = self.bonus {
+            notation.push_str(&format!("+{}", format_seconds(d)));
+        }
+        return notation;
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    periods: Vec<TimeControlPeriod>,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration, bonus: TimeBonus) -> Self {
+        return Self {
+            periods: Vec::from([TimeControlPeriod { moves: None, time: time, bonus: bonus }]),
+        }
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Self, InputError> {
+        let periods = notation.trim().split(':').map(|p| TimeControlPeriod::from_notation(p, notation)).collect::<Result<Vec<_>, InputError>>()?;
+        if periods.iter().rev().skip(1).any(|p| p.moves.is_none()) {
+            return Err(InputError::new(&format!("Only the last period of time control '{}' may omit a move count", notation)));
+        }
+        return Ok(Self { periods: periods });
+    }
+
+    pub fn to_notation(&self) -> String {
+        return self.periods.iter().map(|p| p.to_notation()).collect::<Vec<_>>().join(":");
+    }
+
+    pub fn to_pgn_notation(&self) -> String {
+        return self.periods.iter().map(|p| p.to_pgn_notation()).collect::<Vec<_>>().join(":");
+    }
+
+    pub fn get_period(&self, index: usize) -> &TimeControlPeriod {
+        return self.periods.get(index).unwrap_or(self.periods.last().unwrap());
+    }
+}
+
+
+#[derive(Clone, Debug)]
+pub struct ChessClock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    periods: [usize; 2],
+    period_moves: [u16; 2],
+    turn: Option<Color>,
+    started: Option<Instant>,
+    spent: Duration,
+    flagged: Option<Color>,
+}
+
+impl ChessClock {
+    pub fn new(control: TimeControl) -> Self {
+        let time = control.get_period(0).time;
+        return Self {
+            control: control,
+            remaining: [time, time],
+            periods: [0, 0],
+            period_moves: [0, 0],
+            turn: None,
+            started: None,
+            spent: Duration::ZERO,
+            flagged: None,
+        }
+    }
+
+    pub fn get_control(&self) -> &TimeControl {
+        return &self.control;
+    }
+
+    pub fn start(&mut self, color: Color) {
+        self.turn = Some(color);
+        self.spent = Duration::ZERO;
+        self.started = match self.flagged {
+            Some(_) => None,
+            None => Some(Instant::now()),
+        };
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(started) = self.started.take() {
+            self.spent += started.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.turn.is_some() && self.started.is_none() && self.flagged.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn press(&mut self) {
+        let color = match self.turn {
+            Some(c) => c,
+            None => return,
+        };
+        let elapsed = self.get_elapsed();
+        self.charge(color, elapsed);
+        self.start(color.swap());
+    }
+
+    pub fn charge(&mut self, color: Color, elapsed: Duration) {
+        let side = color as usize;
+        let period = *self.control.get_period(self.periods[side]);
+        let charged = match period.bonus {
+            TimeBonus::SimpleDelay(d) => elapsed.saturating_sub(d),
+            _ => elapsed,
+        };
+        if charged >= self.remaining[side] {
+            self.remaining[side] = Duration::ZERO;
+            self.flagged = self.flagged.or(Some(color));
+            return;
+        }
+        self.remaining[side] -= charged;
+        match period.bonus {
+            TimeBonus::Increment(d) => self.remaining[side] += d,
+            TimeBonus::BronsteinDelay(d) => self.remaining[side] += elapsed.min(d),
+            _ => (),
+        }
+        self.period_moves[side] += 1;
+        if period.moves.is_some_and(|m| self.period_moves[side] >= m) {
+            self.periods[side] += 1;
+            self.period_moves[side] = 0;
+            self.remaining[side] += self.control.get_period(self.periods[side]).time;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        return self.started.is_some();
+    }
+
+    pub fn get_turn(&self) -> Option<Color> {
+        return self.turn;
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        return self.spent + self.started.map_or(Duration::ZERO, |s| s.elapsed());
+    }
+
+    pub fn get_remaining(&self, color: Color) -> Duration {
+        let remaining = self.remaining[color as usize];
+        if self.turn != Some(color) || self.flagged.is_some() {
+            return remaining;
+        }
+        return remaining.saturating_sub(match self.get_bonus(color) {
+            TimeBonus::SimpleDelay(d) => self.get_elapsed().saturating_sub(d),
+            _ => self.get_elapsed(),
+        });
+    }
+
+    pub fn get_bonus(&self, color: Color) -> TimeBonus {
+        return self.control.get_period(self.periods[color as usize]).bonus;
+    }
+
+    pub fn get_increment(&self, color: Color) -> Duration {
+        return self.get_bonus(color).get_duration();
+    }
+
+    pub fn get_moves_to_go(&self, color: Color) -> Option<u16> {
+        let side = color as usize;
+        return self.control.get_period(self.periods[side]).moves.map(|m| m - self.period_moves[side]);
+    }
+
+    pub fn get_flagged(&self) -> Option<Color> {
+        if self.flagged.is_some() {
+            return self.flagged;
+        }
+        return self.turn.filter(|c| self.get_remaining(*c).is_zero());
+    }
+
+    pub fn has_recorded_flag(&self) -> bool {
+        return self.flagged.is_some();
+    }
+
+    pub fn to_printable(&self) -> Vec<PrintableClock> {
+        return Color::iter().map(|color| PrintableClock {
+            color: String::from(color.value()),
+            remaining: format_clock_time(self.get_remaining(color)),
+            period: (self.periods[color as usize] + 1).to_string(),
+            moves_to_go: self.get_moves_to_go(color).map_or(String::from("-"), |m| m.to_string()),
+            bonus: self.get_bonus(color).name(),
+            status: match (self.get_flagged(), self.turn) {
+                (Some(c), _) if c == color => String::from("flagged"),
+                (_, Some(c)) if c == color && self.is_running() => String::from("running"),
+                (_, Some(c)) if c == color => String::from("paused"),
+                _ => String::from("waiting"),
+            },
+        }).collect();
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableClock {
+    pub color: String,
+    pub remaining: String,
+    pub period: String,
+    pub moves_to_go: String,
+    pub bonus: String,
+    pub status: String,
+}
@@ -1,4 +1,76 @@
-use crate::{rules::{Color, pieces::{Piece, movement::Move}, board::Board}, util::zobrist::ZobristHashMap};
+pub mod clock;
+
+use crate::{rules::{Color, pieces::{Piece, movement::Move}, board::Board}, util::{errors::InputError, zobrist::ZobristHashMap}};
+
+use self::clock::{ChessClock, TimeControl};
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unfinished,
+}
+
+impl GameResult {
+    pub fn from_notation(note: &str) -> Option<Self> {
+        return match note {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            "*" => Some(Self::Unfinished),
+            _ => None,
+        }
+    }
+
+    pub fn get_notation(&self) -> &str {
+        return match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+            Self::Unfinished => "*",
+        }
+    }
+
+    pub fn get_winner(&self) -> Option<Color> {
+        return match self {
+            Self::WhiteWins => Some(Color::White),
+            Self::BlackWins => Some(Color::Black),
+            _ => None,
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameTermination {
+    Checkmate,
+    Stalemate,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    TimeForfeit,
+    TimeoutVsInsufficientMaterial,
+    Resignation,
+    DrawAgreement,
+}
+
+impl GameTermination {
+    pub fn name(&self) -> &str {
+        return match self {
+            Self::Checkmate => "checkmate",
+            Self::Stalemate => "stalemate",
+            Self::ThreefoldRepetition => "threefold repetition",
+            Self::FiftyMoveRule => "fifty move rule",
+            Self::InsufficientMaterial => "insufficient material",
+            Self::TimeForfeit => "time forfeit",
+            Self::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+            Self::Resignation => "resignation",
+            Self::DrawAgreement => "draw agreement",
+        }
+    }
+}
 
 
 #[derive(Copy, Clone)]
@@ -80,12 +152,25 @@ impl GameHistory {
     pub fn has_fivefold_repetition(&self, hash: u64) -> bool {
         return self.repeats_x_or_more(hash, 5)
     }
+
+    pub fn get_starting_board(&self) -> &Board {
+        return match self.turn_history.first() {
+            Some(turn) => &turn.board,
+            None => &self.current_board,
+        }
+    }
+
+    pub fn get_moves(&self) -> Vec<Move> {
+        return self.turn_history.iter().map(|t| t.move_played).collect();
+    }
 }
 
 
 pub struct Game {
     board: Board,
     history: GameHistory,
+    clock: Option<ChessClock>,
+    conclusion: Option<(GameResult, GameTermination)>,
 }
 
 impl Game {
@@ -93,20 +178,75 @@ impl Game {
         return Self::from_board(Board::from_starting_position());
     }
 
-    pub fn from_fen(fen: &str) -> Self {
-        return Self::from_board(Board::from_fen(fen));
+    pub fn from_fen(fen: &str) -> Result<Self, InputError> {
+        return Ok(Self::from_board(Board::from_fen(fen)?));
     }
 
-    fn from_board(board: Board) -> Self {
+    pub fn from_board(board: Board) -> Self {
         return Self {
             board: board,
             history: GameHistory::new(board),
+            clock: None,
+            conclusion: None,
         }
     }
 
     pub fn make_move(&mut self, new_move: &Move) {
         self.board.make_move(new_move);
         self.history.take_turn(new_move);
+        if self.clock.is_none() {
+            return;
+        }
+        let finished = self.get_board_outcome().is_some();
+        let color = self.get_current_turn();
+        let clock = self.clock.as_mut().unwrap();
+        match clock.get_turn() {
+            Some(_) => clock.press(),
+            None => clock.start(color),
+        }
+        if finished {
+            clock.pause();
+        }
+    }
+
+    pub fn resign(&mut self, color: Color) {
+        self.conclude(match color {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        }, GameTermination::Resignation);
+    }
+
+    pub fn agree_draw(&mut self) {
+        self.conclude(GameResult::Draw, GameTermination::DrawAgreement);
+    }
+
+    fn conclude(&mut self, result: GameResult, termination: GameTermination) {
+        if self.get_outcome().is_some() {
+            return;
+        }
+        self.conclusion = Some((result, termination));
+        if let Some(clock) = self.clock.as_mut() {
+            clock.pause();
+        }
+    }
+
+    pub fn set_time_control(&mut self, control: Option<TimeControl>) {
+        self.clock = control.map(ChessClock::new);
+    }
+
+    pub fn start_clock(&mut self) {
+        let color = self.get_current_turn();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.start(color);
+        }
+    }
+
+    pub fn get_clock(&self) -> Option<&ChessClock> {
+        return self.clock.as_ref();
+    }
+
+    pub fn get_clock_mut(&mut self) -> Option<&mut ChessClock> {
+        return self.clock.as_mut();
     }
 
     pub fn get_legal_moves(&self) -> Vec<Move> {
@@ -125,6 +265,57 @@ impl Game {
         return &self.board;
     }
 
+    pub fn get_starting_board(&self) -> &Board {
+        return self.history.get_starting_board();
+    }
+
+    pub fn get_moves(&self) -> Vec<Move> {
+        return self.history.get_moves();
+    }
+
+    pub fn get_outcome(&self) -> Option<(GameResult, GameTermination)> {
+        if self.conclusion.is_some() {
+            return self.conclusion;
+        }
+        if self.clock.as_ref().is_some_and(|c| c.has_recorded_flag()) {
+            return self.get_flag_outcome();
+        }
+        return self.get_board_outcome().or_else(|| self.get_flag_outcome());
+    }
+
+    fn get_flag_outcome(&self) -> Option<(GameResult, GameTermination)> {
+        let flagged = self.clock.as_ref()?.get_flagged()?;
+        if self.board.has_insufficient_material(flagged.swap()) {
+            return Some((GameResult::Draw, GameTermination::TimeoutVsInsufficientMaterial));
+        }
+        return Some((match flagged {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        }, GameTermination::TimeForfeit));
+    }
+
+    fn get_board_outcome(&self) -> Option<(GameResult, GameTermination)> {
+        if self.board.get_legal_moves().is_empty() {
+            return match self.board.in_check() {
+                true => Some((match self.get_current_turn() {
+                    Color::White => GameResult::BlackWins,
+                    Color::Black => GameResult::WhiteWins,
+                }, GameTermination::Checkmate)),
+                false => Some((GameResult::Draw, GameTermination::Stalemate)),
+            }
+        }
+        if self.history.repeats_x_or_more(self.board.zobrist.get_id(), 2) {
+            return Some((GameResult::Draw, GameTermination::ThreefoldRepetition));
+        }
+        if self.board.state.halfmove_clock >= 100 {
+            return Some((GameResult::Draw, GameTermination::FiftyMoveRule));
+        }
+        if self.board.is_insufficient_material() {
+            return Some((GameResult::Draw, GameTermination::InsufficientMaterial));
+        }
+        return None;
+    }
+
     pub fn serialize_board(&self) -> String {
         return self.board.to_fen();
     }
@@ -215,6 +215,7 @@ impl ArgumentParser {
                             return_args.insert(String::from(&parg.name), arg);
                         } else {
                             if self.keys.contains_key(&arg) {
+                                args.push_front(arg);
                                 break;
                             } else {
                                 return_args.insert(String::from(&parg.name), arg);
@@ -230,6 +231,7 @@ impl ArgumentParser {
                                 }
                                 arg = args.pop_front().unwrap();
                                 if self.keys.contains_key(&arg) {
+                                    args.push_front(arg);
                                     break;
                                 }
                             }
@@ -273,6 +275,7 @@ impl ArgumentParser {
                                     None => break,
                                     Some(val) => {
                                         if did_one && self.keys.contains_key(&val) {
+                                            args.push_front(val);
                                             break;
                                         } else {
                                             arg_vec.push(val);
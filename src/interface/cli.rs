@@ -1,17 +1,35 @@
-use std::{collections::HashSet, time::Instant};
+use std::{collections::HashSet, sync::Arc, time::{Duration, Instant}};
 
 use num_format::{ToFormattedString, Locale};
 use tabled::{Table, Style, Alignment, Modify, Full};
 
-use crate::{game::Game, interface::{arguments::ParsedArgs, shell::InteractiveShell}, rules::{board::{squares::{BoardSquare, get_notation_string_for_square}, fen_board_from_position, Board}, pieces::{PieceType, movement::Move, Piece}, Color}, util::{fen::{FenBoardState, get_notation_for_piece}, zobrist::ZobristId}, testing::{perft::PerftRunner, zobrist::ZobristCollisionTester}, engine::search::alpha_beta::AlphaBetaSearch};
+use crate::{game::{Game, GameTermination, clock::{TimeControl, format_clock_time}}, interface::{arguments::ParsedArgs, shell::InteractiveShell}, rules::{board::{squares::{BoardSquare, get_notation_string_for_square}, fen_board_from_position, Board}, pieces::{PieceType, movement::Move, Piece}, Color}, util::{epd::EpdRecord, errors::InputError, fen::{FenBoardState, get_chess960_starting_position, get_notation_for_piece}, notation::{get_move_from_san_notation, get_move_from_uci_notation, get_san_notation_for_move}, pgn::{PgnGame, write_pgn_file}, polyglot::get_polyglot_key, random::Random, zobrist::ZobristId}, testing::{bench::{BenchRunner, PrintableBenchResult, PrintableAllocationBenchResult}, epd::{EpdRunner, PrintableEpdResult}, matches::{MatchEngine, MatchOpening, MatchOptions, MatchRunner}, movegen::StagedGenerationTester, perft::{PerftRunner, PrintablePerftDivide}, perft_suite::{CHESS960_PERFT_SUITE, PERFT_SUITE, PerftSuitePosition, PerftSuiteRunner, PrintablePerftSuiteResult}, zobrist::ZobristCollisionTester}, engine::{book::{BookSelection, OpeningBook}, book_builder::{BookBuilder, BookBuilderOptions, BookFormat}, evaluation::Evaluator, parameters::EvaluationParameters, search::{PrincipalVariation, SearchOptions, alpha_beta::AlphaBetaSearch, statistics::SearchStatistics, time_manager::TimeManager}, strength::StrengthLevel, tablebase::Tablebases, tuner::{LabelledPosition, Tuner, TunerOptions}}};
 
-use super::arguments::{ArgumentParser, Arguments};
+use super::{arguments::{ArgumentParser, Arguments}, uci::UciInterface};
+
+
+static DEFAULT_MATCH_GAMES: usize = 10;
+static DEFAULT_PLAY_DEPTH: u8 = 4;
+static DRAW_ACCEPT_SCORE: i16 = -50;
+static PLAY_COMMANDS: &str = "Enter moves like e2e4 or Nf3, or one of: resign, draw, board, moves, clock, help, quit";
+
+
+struct PlaySettings {
+    human: Color,
+    depth: Option<u8>,
+    time: Option<Duration>,
+    strength: StrengthLevel,
+    use_book: bool,
+    random: Random,
+}
 
 
 fn build_argument_parser() -> ArgumentParser {
     let mut builder = ArgumentParser::builder();
     builder.add_subcommand("new").unwrap()
         .add_named_arg("from_fen", HashSet::from(["--from-fen"]), false, false).unwrap()
+        .add_named_arg("chess960", HashSet::from(["--chess960"]), false, false).unwrap()
+        .add_named_arg("time_control", HashSet::from(["--time-control"]), false, false).unwrap()
         .add_flag_arg("no_confirm", HashSet::from(["--no-confirm"])).unwrap();
 
     builder.add_subcommand("list").unwrap()
@@ -21,12 +39,25 @@ fn build_argument_parser() -> ArgumentParser {
         .add_positional_arg("count", false, false).unwrap();
 
     builder.add_subcommand("perft").unwrap()
-        .add_named_arg("depth", HashSet::from(["--depth"]), true, false).unwrap()
-        .add_named_arg("threads", HashSet::from(["--threads"]), false, false).unwrap();
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
+        .add_named_arg("threads", HashSet::from(["--threads"]), false, false).unwrap()
+        .add_flag_arg("divide", HashSet::from(["--divide"])).unwrap()
+        .add_flag_arg("fast", HashSet::from(["--fast"])).unwrap()
+        .add_flag_arg("hash", HashSet::from(["--hash"])).unwrap()
+        .add_flag_arg("suite", HashSet::from(["--suite"])).unwrap()
+        .add_flag_arg("chess960", HashSet::from(["--chess960"])).unwrap();
+
+    builder.add_subcommand("bench").unwrap()
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
+        .add_named_arg("rounds", HashSet::from(["--rounds"]), false, false).unwrap();
 
     builder.add_subcommand("zobrist_test").unwrap()
         .add_named_arg("depth", HashSet::from(["--depth"]), true, false).unwrap();
 
+    builder.add_subcommand("movegen_test").unwrap()
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
+        .add_flag_arg("suite", HashSet::from(["--suite"])).unwrap();
+
     builder.add_subcommand("move").unwrap();
 
     builder.add_subcommand("serialize").unwrap()
@@ -35,12 +66,98 @@ fn build_argument_parser() -> ArgumentParser {
     builder.add_subcommand("board").unwrap()
         .add_flag_arg("hide_board", HashSet::from(["--hide-board"])).unwrap()
         .add_flag_arg("as_fen", HashSet::from(["--as-fen"])).unwrap()
-        .add_flag_arg("as_zobrist", HashSet::from(["--as-zobrist"])).unwrap();
+        .add_flag_arg("as_zobrist", HashSet::from(["--as-zobrist"])).unwrap()
+        .add_flag_arg("as_polyglot", HashSet::from(["--as-polyglot"])).unwrap();
+
+    builder.add_subcommand("clock").unwrap()
+        .add_named_arg("set", HashSet::from(["--set"]), false, false).unwrap()
+        .add_flag_arg("clear", HashSet::from(["--clear"])).unwrap()
+        .add_flag_arg("start", HashSet::from(["--start"])).unwrap()
+        .add_flag_arg("pause", HashSet::from(["--pause"])).unwrap()
+        .add_flag_arg("resume", HashSet::from(["--resume"])).unwrap();
+
+    builder.add_subcommand("book").unwrap()
+        .add_named_arg("load", HashSet::from(["--load"]), false, false).unwrap()
+        .add_flag_arg("unload", HashSet::from(["--unload"])).unwrap()
+        .add_flag_arg("pick", HashSet::from(["--pick"])).unwrap()
+        .add_flag_arg("best", HashSet::from(["--best"])).unwrap();
+
+    builder.add_subcommand("build-book").unwrap()
+        .add_positional_arg("files", true, true).unwrap()
+        .add_named_arg("output", HashSet::from(["--output"]), true, false).unwrap()
+        .add_named_arg("max_ply", HashSet::from(["--max-ply"]), false, false).unwrap()
+        .add_named_arg("min_games", HashSet::from(["--min-games"]), false, false).unwrap()
+        .add_flag_arg("no_wins", HashSet::from(["--no-wins"])).unwrap()
+        .add_flag_arg("no_draws", HashSet::from(["--no-draws"])).unwrap()
+        .add_flag_arg("no_losses", HashSet::from(["--no-losses"])).unwrap()
+        .add_flag_arg("native", HashSet::from(["--native"])).unwrap();
+
+    builder.add_subcommand("tb").unwrap()
+        .add_positional_arg("action", false, false).unwrap()
+        .add_named_arg("path", HashSet::from(["--path"]), false, false).unwrap()
+        .add_flag_arg("unload", HashSet::from(["--unload"])).unwrap();
+
+    builder.add_subcommand("eval").unwrap()
+        .add_named_arg("load", HashSet::from(["--load"]), false, false).unwrap()
+        .add_named_arg("save", HashSet::from(["--save"]), false, false).unwrap()
+        .add_flag_arg("reset", HashSet::from(["--reset"])).unwrap();
+
+    builder.add_subcommand("tune").unwrap()
+        .add_positional_arg("file", true, false).unwrap()
+        .add_named_arg("output", HashSet::from(["--output"]), true, false).unwrap()
+        .add_named_arg("passes", HashSet::from(["--passes"]), false, false).unwrap()
+        .add_named_arg("step", HashSet::from(["--step"]), false, false).unwrap()
+        .add_named_arg("k", HashSet::from(["--k"]), false, false).unwrap()
+        .add_flag_arg("material_only", HashSet::from(["--material-only"])).unwrap();
 
     builder.add_subcommand("search").unwrap()
         .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
         .add_named_arg("threads", HashSet::from(["--threads"]), false, false).unwrap()
-        .add_named_arg("sleep", HashSet::from(["--sleep"]), false, false).unwrap();
+        .add_named_arg("sleep", HashSet::from(["--sleep"]), false, false).unwrap()
+        .add_named_arg("multipv", HashSet::from(["--multipv"]), false, false).unwrap()
+        .add_flag_arg("clock", HashSet::from(["--clock"])).unwrap()
+        .add_flag_arg("book", HashSet::from(["--book"])).unwrap()
+        .add_flag_arg("best_book_move", HashSet::from(["--best-book-move"])).unwrap();
+
+    builder.add_subcommand("epd").unwrap()
+        .add_positional_arg("file", true, false).unwrap()
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
+        .add_named_arg("time", HashSet::from(["--time"]), false, false).unwrap();
+
+    builder.add_subcommand("match").unwrap()
+        .add_named_arg("engine1", HashSet::from(["--engine1"]), false, false).unwrap()
+        .add_named_arg("engine2", HashSet::from(["--engine2"]), false, false).unwrap()
+        .add_named_arg("games", HashSet::from(["--games"]), false, false).unwrap()
+        .add_named_arg("openings", HashSet::from(["--openings"]), false, false).unwrap()
+        .add_named_arg("pgn", HashSet::from(["--pgn"]), false, false).unwrap()
+        .add_named_arg("time_control", HashSet::from(["--tc"]), false, false).unwrap()
+        .add_named_arg("max_moves", HashSet::from(["--max-moves"]), false, false).unwrap()
+        .add_named_arg("resign_score", HashSet::from(["--resign-score"]), false, false).unwrap()
+        .add_named_arg("resign_moves", HashSet::from(["--resign-moves"]), false, false).unwrap()
+        .add_named_arg("draw_score", HashSet::from(["--draw-score"]), false, false).unwrap()
+        .add_named_arg("draw_moves", HashSet::from(["--draw-moves"]), false, false).unwrap()
+        .add_named_arg("draw_after", HashSet::from(["--draw-after"]), false, false).unwrap()
+        .add_named_arg("elo0", HashSet::from(["--elo0"]), false, false).unwrap()
+        .add_named_arg("elo1", HashSet::from(["--elo1"]), false, false).unwrap()
+        .add_named_arg("alpha", HashSet::from(["--alpha"]), false, false).unwrap()
+        .add_named_arg("beta", HashSet::from(["--beta"]), false, false).unwrap()
+        .add_flag_arg("sprt", HashSet::from(["--sprt"])).unwrap();
+
+    builder.add_subcommand("analyze-moves").unwrap()
+        .add_positional_arg("moves", true, true).unwrap()
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap();
+
+    builder.add_subcommand("play").unwrap()
+        .add_named_arg("color", HashSet::from(["--color"]), false, false).unwrap()
+        .add_named_arg("depth", HashSet::from(["--depth"]), false, false).unwrap()
+        .add_named_arg("time", HashSet::from(["--time"]), false, false).unwrap()
+        .add_named_arg("time_control", HashSet::from(["--tc"]), false, false).unwrap()
+        .add_named_arg("skill", HashSet::from(["--skill"]), false, false).unwrap()
+        .add_named_arg("elo", HashSet::from(["--elo"]), false, false).unwrap()
+        .add_named_arg("pgn", HashSet::from(["--pgn"]), false, false).unwrap()
+        .add_flag_arg("book", HashSet::from(["--book"])).unwrap();
+
+    builder.add_subcommand("uci").unwrap();
 
     builder.add_subcommand("exit").unwrap();
 
@@ -109,6 +226,9 @@ fn format_board_for_display(board: &Board) -> Vec<String> {
 pub struct Interface {
     shell: InteractiveShell,
     game: Game,
+    book: Option<OpeningBook>,
+    tablebases: Option<Arc<Tablebases>>,
+    parameters: Arc<EvaluationParameters>,
     confirmations: HashSet<String>,
 }
 
@@ -119,6 +239,9 @@ impl Interface {
         return Interface {
             shell: InteractiveShell::new(Some(prompt), build_argument_parser()),
             game: Game::new(),
+            book: None,
+            tablebases: None,
+            parameters: Arc::new(EvaluationParameters::default()),
             confirmations: HashSet::from([String::from("y"), String::from("yes")]),
         }
     }
@@ -137,10 +260,23 @@ impl Interface {
                         "list"          => self.do_list(*s.args),
                         "move"          => self.do_move(*s.args),
                         "perft"         => self.do_perft(*s.args),
+                        "bench"         => self.do_bench(*s.args),
                         "search"        => self.do_search(*s.args),
+                        "analyze-moves" => self.do_analyze_moves(*s.args),
+                        "epd"           => self.do_epd(*s.args),
+                        "match"         => self.do_match(*s.args),
+                        "play"          => self.do_play(*s.args),
                         "serialize"     => self.do_serialize(*s.args),
                         "board"         => self.do_board(*s.args),
+                        "clock"         => self.do_clock(*s.args),
+                        "book"          => self.do_book(*s.args),
+                        "build-book"    => self.do_build_book(*s.args),
+                        "tb"            => self.do_tablebase(*s.args),
+                        "eval"          => self.do_eval(*s.args),
+                        "tune"          => self.do_tune(*s.args),
                         "zobrist_test"  => self.do_zobrist_test(*s.args),
+                        "movegen_test"  => self.do_movegen_test(*s.args),
+                        "uci"           => { UciInterface::new().init(); return; },
                         "exit"          => break,
                         x => println!("Unknown subcommand {} encountered", x)
                     },
@@ -169,11 +305,38 @@ impl Interface {
                     confirmed = self.confirmations.contains(&confirm.to_lowercase());
                 }
                 if confirmed {
-                    match a.get_arg("from_fen") {
-                        Some(fen) => self.game = Game::from_fen(&fen),
+                    let time_control = match a.get_arg("time_control").map(|tc| TimeControl::from_notation(&tc)).transpose() {
+                        Ok(tc) => tc,
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    };
+                    let fen = match a.get_arg("chess960") {
+                        Some(index) => match index.parse().map_err(|_| InputError::new(&format!("'{}' is not a valid Chess960 position index", index))).and_then(get_chess960_starting_position) {
+                            Ok(f) => Some(f),
+                            Err(e) => {
+                                self.shell.output(&e.msg);
+                                return;
+                            }
+                        },
+                        None => a.get_arg("from_fen"),
+                    };
+                    match fen {
+                        Some(fen) => match Game::from_fen(&fen) {
+                            Ok(game) => self.game = game,
+                            Err(e) => {
+                                self.shell.output(&format!("Invalid FEN: {}", e.msg));
+                                return;
+                            }
+                        },
                         None => self.game = Game::new()
                     }
+                    self.game.set_time_control(time_control);
                     self.shell.output("New game started!");
+                    if let Some(clock) = self.game.get_clock() {
+                        self.shell.output(&format!("Time control: {}, the clock starts after the first move", clock.get_control().to_notation()));
+                    }
                 } else {
                     self.shell.output("OK, aborting...");
                 }
@@ -233,7 +396,7 @@ impl Interface {
                 Move::BasicMove(b) => if b.start == start && b.end == end { chosen_move = Some(m.clone()); break; },
                 Move::EnPassant(e) => if e.basic_move.start == start && e.basic_move.end == end { chosen_move = Some(m.clone()); break; },
                 Move::TwoSquarePawnMove(t) => if t.basic_move.start == start && t.basic_move.end == end { chosen_move = Some(m.clone());  break; },
-                Move::Castle(c) => if c.king_start == start && c.king_end == end { chosen_move = Some(m.clone()); break; },
+                Move::Castle(c) => if c.king_start == start && (c.king_end == end || c.rook_start == end) { chosen_move = Some(m.clone()); break; },
                 Move::Promotion(p) => {
                     match promotion_type {
                         None => {
@@ -257,11 +420,37 @@ impl Interface {
         match args {
             ParsedArgs::SubCommand(_s) => panic!("Subcommand 'perft' should not have its own subcommands"),
             ParsedArgs::Arguments(a) => {
+                if a.get_flag("suite") {
+                    let suite: &[PerftSuitePosition] = if a.get_flag("chess960") { &CHESS960_PERFT_SUITE } else { &PERFT_SUITE };
+                    self.do_perft_suite(suite, a.get_arg("depth").map(|d| d.parse().unwrap()));
+                    return;
+                }
                 let depth: u8 = match a.get_arg("depth") {
                     Some(d) => d.parse().unwrap(),
                     None => self.shell.input("What depth should the engine search to? ").parse().unwrap()
                 };
+                if a.get_flag("fast") || a.get_flag("hash") {
+                    let result = PerftRunner::do_fast_perft(*self.game.get_board(), depth, a.get_flag("hash"));
+                    self.shell.output(&format!("Nodes: {}", result.nodes.to_formatted_string(&Locale::en)));
+                    if result.hash_probes > 0 {
+                        self.shell.output(&format!("Hash hits: {} of {} probes", result.hash_hits.to_formatted_string(&Locale::en), result.hash_probes.to_formatted_string(&Locale::en)));
+                    }
+                    self.shell.output(&format!("Nodes per second: {}", result.nodes_per_second().to_formatted_string(&Locale::en)));
+                    self.shell.output(&format!("Completed in {:?}", result.duration));
+                    return;
+                }
                 let start = Instant::now();
+                if a.get_flag("divide") {
+                    let divide = PerftRunner::do_divide(*self.game.get_board(), depth);
+                    let duration = start.elapsed();
+                    let total: u64 = divide.iter().map(|d| d.nodes).sum();
+                    let table = Table::new(divide.iter().map(PrintablePerftDivide::from_divide).collect::<Vec<_>>()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                    self.shell.output(&table.to_string());
+                    self.shell.output(&format!("Moves: {}", divide.len()));
+                    self.shell.output(&format!("Nodes: {}", total.to_formatted_string(&Locale::en)));
+                    self.shell.output(&format!("Completed in {:?}", duration));
+                    return;
+                }
                 let result = match a.get_arg("threads") {
                     Some(t) => PerftRunner::do_threaded_perft(*self.game.get_board(), depth, t.parse().unwrap()),
                     None => PerftRunner::do_perft(*self.game.get_board(), depth),
@@ -274,6 +463,84 @@ impl Interface {
         }
     }
 
+    fn do_perft_suite(&self, suite: &[PerftSuitePosition], max_depth: Option<u8>) {
+        let start = Instant::now();
+        let results = PerftSuiteRunner::run_suite(suite, max_depth, |r| {
+            self.shell.output(&format!("{} (depth {}): {}", r.name, r.depth, if r.passed() { "pass" } else { "FAIL" }));
+        });
+        let duration = start.elapsed();
+        self.shell.empty_line();
+        let table = Table::new(results.iter().map(PrintablePerftSuiteResult::from_result).collect::<Vec<_>>()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+        self.shell.output(&table.to_string());
+        self.shell.output(&format!("{} of {} perft checks passed", results.iter().filter(|r| r.passed()).count(), results.len()));
+        self.shell.output(&format!("Completed in {:?}", duration));
+    }
+
+    fn do_bench(&self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'bench' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let depth: Option<u8> = a.get_arg("depth").map(|d| d.parse().unwrap());
+                let rounds: u32 = a.get_arg("rounds").map_or(20, |r| r.parse().unwrap());
+                let results = BenchRunner::run_perft_bench(depth, |r| {
+                    self.shell.output(&format!("{} (depth {}): {} nps", r.name, r.depth, r.nodes_per_second().to_formatted_string(&Locale::en)));
+                });
+                self.shell.empty_line();
+                let table = Table::new(results.iter().map(PrintableBenchResult::from_result).collect::<Vec<_>>()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                let (nodes, duration, nps) = BenchRunner::get_total(&results);
+                self.shell.output(&format!("Total nodes: {}", nodes.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("Total time: {:?}", duration));
+                self.shell.output(&format!("Nodes per second: {}", nps.to_formatted_string(&Locale::en)));
+                self.shell.empty_line();
+                let allocations = BenchRunner::run_allocation_bench(depth.map(|d| d.saturating_sub(1)), |r| {
+                    self.shell.output(&format!("{} (depth {}): {:.3} allocations per node with Vec, {:.3} with MoveList", r.name, r.depth, r.vec_allocations.per_node(r.nodes), r.list_allocations.per_node(r.nodes)));
+                });
+                self.shell.empty_line();
+                let table = Table::new(allocations.iter().map(PrintableAllocationBenchResult::from_result).collect::<Vec<_>>()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                self.shell.empty_line();
+                let sliders = BenchRunner::run_slider_bench(rounds);
+                let method = BenchRunner::get_lookup_method();
+                self.shell.output(&format!("Slider move lookups: {}", sliders.lookups.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("Ray walk: {:?} ({:.1} ns per lookup)", sliders.ray_walk, sliders.ray_walk.as_nanos() as f64 / sliders.lookups as f64));
+                self.shell.output(&format!("{} lookup: {:?} ({:.1} ns per lookup)", method, sliders.table_lookup, sliders.table_lookup.as_nanos() as f64 / sliders.lookups as f64));
+                self.shell.output(&format!("Speedup: {:.2}x", sliders.speedup()));
+                if sliders.mismatches > 0 {
+                    self.shell.output(&format!("WARNING: {} {} lookups did not match the ray walk", sliders.mismatches, method));
+                }
+            }
+        }
+    }
+
+    fn do_movegen_test(&self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'movegen_test' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let depth: u8 = match a.get_arg("depth") {
+                    Some(d) => d.parse().unwrap(),
+                    None => self.shell.input("What depth should we test to? ").parse().unwrap()
+                };
+                let results = match a.get_flag("suite") {
+                    true => StagedGenerationTester::do_suite_test(&[&PERFT_SUITE, &CHESS960_PERFT_SUITE], depth),
+                    false => StagedGenerationTester::do_test(*self.game.get_board(), depth),
+                };
+                self.shell.empty_line();
+                self.shell.output(&format!("Positions Checked: {}", results.positions_checked.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("  Captures Staged: {}", results.captures.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("    Quiets Staged: {}", results.quiets.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("  Evasions Staged: {}", results.evasions.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("  Evaluation Time: {:?}", results.duration));
+                self.shell.output(&format!("       Mismatches: {}", results.mismatch_count.to_formatted_string(&Locale::en)));
+                for mismatch in &results.mismatches {
+                    self.shell.output(&mismatch.describe());
+                }
+                self.shell.empty_line();
+                self.shell.output(if results.passed() { "Staged generators match the full generator" } else { "Staged generators do NOT match the full generator" });
+            }
+        }
+    }
+
     fn do_zobrist_test(&self, args: ParsedArgs) {
         match args {
             ParsedArgs::SubCommand(_s) => panic!("Subcommand 'zobrist_test' should not have its own subcommands"),
@@ -303,11 +570,11 @@ impl Interface {
                     self.shell.output(&format!("             Move: {}", get_text_for_move(&collision.cause)));
                     self.shell.output(&format!("            Fen 1: {}", old));
                     self.shell.output(&format!("            Fen 2: {}", new));
-                    self.shell.output(&format!("           Hash 1: {:064b}", ZobristId::from_fen(&FenBoardState::from_fen(&old)).get_id()));
+                    self.shell.output(&format!("           Hash 1: {:064b}", ZobristId::from_fen(&FenBoardState::from_fen(&old).unwrap()).get_id()));
                     self.shell.output(&format!("    Collided Hash: {:064b}", collision.hash));
-                    self.shell.output(&format!("           Hash 2: {:064b}", ZobristId::from_fen(&FenBoardState::from_fen(&new)).get_id()));
-                    let old_board = format_board_for_display(&Board::from_fen(&old));
-                    let new_board = format_board_for_display(&Board::from_fen(&new));
+                    self.shell.output(&format!("           Hash 2: {:064b}", ZobristId::from_fen(&FenBoardState::from_fen(&new).unwrap()).get_id()));
+                    let old_board = format_board_for_display(&Board::from_fen(&old).unwrap());
+                    let new_board = format_board_for_display(&Board::from_fen(&new).unwrap());
                     self.shell.output("        Board 1:               Board 2:");
                     old_board.into_iter().zip(new_board.into_iter()).for_each(|(old_row, new_row)| {
                         self.shell.output(&format!("    {}        {}", old_row, new_row));
@@ -330,12 +597,329 @@ impl Interface {
                     self.shell.output(&format!("Zobrist Hash: {:064b}", self.game.get_board().zobrist.get_id()));
                     self.shell.empty_line();
                 }
+                if a.get_flag("as_polyglot") {
+                    self.shell.output(&format!("Polyglot Key: {:016x}", get_polyglot_key(self.game.get_board())));
+                    self.shell.empty_line();
+                }
                 if !a.get_flag("hide_board") {
-                    self.shell.output("    Board:");
-                    format_board_for_display(self.game.get_board()).iter().for_each(|row| {
-                        self.shell.output(row);
-                    });
+                    self.output_board();
+                }
+            }
+        }
+    }
+
+    fn output_board(&self) {
+        self.shell.output("    Board:");
+        format_board_for_display(self.game.get_board()).iter().for_each(|row| {
+            self.shell.output(row);
+        });
+        if let Some(clock) = self.game.get_clock() {
+            self.shell.empty_line();
+            self.shell.output(&format!("    Clock: white {} | black {}", format_clock_time(clock.get_remaining(Color::White)), format_clock_time(clock.get_remaining(Color::Black))));
+        }
+    }
+
+    fn output_clock(&self) {
+        match self.game.get_clock() {
+            Some(clock) => {
+                self.shell.output(&format!("Time control: {}", clock.get_control().to_notation()));
+                let table = Table::new(clock.to_printable()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+            },
+            None => self.shell.output("The current game has no clock, use 'clock --set <time control>' to add one"),
+        }
+    }
+
+    fn do_clock(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'clock' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                if let Some(notation) = a.get_arg("set") {
+                    match TimeControl::from_notation(&notation) {
+                        Ok(tc) => self.game.set_time_control(Some(tc)),
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                if a.get_flag("clear") {
+                    self.game.set_time_control(None);
+                    self.shell.output("Clock removed from the current game");
+                    return;
+                }
+                if a.get_flag("start") {
+                    self.game.start_clock();
+                }
+                let clock = match self.game.get_clock_mut() {
+                    Some(c) => c,
+                    None => {
+                        self.shell.output("The current game has no clock, use 'clock --set <time control>' to add one");
+                        return;
+                    }
+                };
+                if a.get_flag("pause") {
+                    clock.pause();
+                }
+                if a.get_flag("resume") {
+                    clock.resume();
+                }
+                self.output_clock();
+                if let Some((result, termination)) = self.game.get_outcome().filter(|(_, t)| matches!(t, GameTermination::TimeForfeit | GameTermination::TimeoutVsInsufficientMaterial)) {
+                    self.shell.output(&format!("Game over: {} by {}", result.get_notation(), termination.name()));
+                }
+            }
+        }
+    }
+
+    fn do_book(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'book' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                if a.get_flag("unload") {
+                    self.book = None;
+                    self.shell.output("Opening book unloaded");
+                    return;
+                }
+                if let Some(path) = a.get_arg("load") {
+                    match OpeningBook::from_file(&path) {
+                        Ok(book) => {
+                            self.shell.output(&format!("Loaded {} entries from {}", book.len().to_formatted_string(&Locale::en), path));
+                            self.book = Some(book);
+                        },
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                let board = *self.game.get_board();
+                let book = match self.book.as_mut() {
+                    Some(b) => b,
+                    None => {
+                        self.shell.output("No opening book is loaded, use 'book --load <file>' to load a Polyglot book");
+                        return;
+                    }
+                };
+                if a.get_flag("pick") {
+                    let selection = if a.get_flag("best") { BookSelection::Best } else { BookSelection::Weighted };
+                    match book.choose_move(&board, selection) {
+                        Some(mov) => self.shell.output(&format!("Book move: {}", get_text_for_move(&mov))),
+                        None => self.shell.output("The current position is not in the opening book"),
+                    }
+                    return;
+                }
+                let moves = book.get_moves(&board);
+                if moves.is_empty() {
+                    self.shell.output("The current position is not in the opening book");
+                    return;
+                }
+                let total: u64 = moves.iter().map(|m| m.weight as u64).sum();
+                let table = Table::new(moves.iter().map(|m| m.to_printable(total)).collect::<Vec<_>>())
+                    .with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+            }
+        }
+    }
+
+    fn do_build_book(&self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'build-book' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let mut options = BookBuilderOptions::default();
+                if let Some(p) = a.get_arg("max_ply") { options.max_ply = p.parse().unwrap_or(options.max_ply) }
+                if let Some(g) = a.get_arg("min_games") { options.min_games = g.parse().unwrap_or(options.min_games) }
+                options.include_wins = !a.get_flag("no_wins");
+                options.include_draws = !a.get_flag("no_draws");
+                options.include_losses = !a.get_flag("no_losses");
+                let format = if a.get_flag("native") { BookFormat::Native } else { BookFormat::Polyglot };
+                let output = a.get_arg("output").unwrap();
+                let start = Instant::now();
+                let mut builder = BookBuilder::new(options);
+                for file in a.get_narg("files").unwrap_or_default() {
+                    if let Err(e) = builder.add_pgn_file(&file) {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                }
+                let summary = match builder.write(&output, format) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                self.shell.output(&format!("   Games Read: {}", summary.games_read.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("   Games Used: {}", summary.games_used.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("Games Skipped: {}", summary.games_skipped.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("    Positions: {}", summary.positions.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("      Entries: {}", summary.entries.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("   Build Time: {:?}", start.elapsed()));
+                for error in &summary.errors {
+                    self.shell.output(error);
+                }
+                self.shell.empty_line();
+                self.shell.output(&format!("{:?} book written to {}", format, output));
+            }
+        }
+    }
+
+    fn do_tablebase(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'tb' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                if a.get_flag("unload") {
+                    self.tablebases = None;
+                    self.shell.output("Tablebases unloaded");
+                    return;
+                }
+                if let Some(path) = a.get_arg("path") {
+                    match Tablebases::from_paths(&path) {
+                        Ok(tablebases) => {
+                            self.shell.output(&format!("Loaded {} WDL and {} DTZ tables with up to {} pieces from {}", tablebases.wdl_count(), tablebases.dtz_count(), tablebases.get_max_cardinality(), path));
+                            self.tablebases = Some(Arc::new(tablebases));
+                        },
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                match a.get_arg("action").as_deref() {
+                    Some("probe") => self.do_tablebase_probe(),
+                    Some(x) => self.shell.output(&format!("Unrecognized tablebase action: '{}'", x)),
+                    None => (),
+                }
+            }
+        }
+    }
+
+    fn do_tablebase_probe(&self) {
+        let tablebases = match self.tablebases.as_ref() {
+            Some(t) => t,
+            None => {
+                self.shell.output("No tablebases are loaded, use 'tb --path <dir>' to load Syzygy tables");
+                return;
+            }
+        };
+        let board = self.game.get_board();
+        if !tablebases.can_probe(board) {
+            self.shell.output(&format!("The current position is not covered by the loaded tablebases (up to {} pieces, no castling rights)", tablebases.get_max_cardinality()));
+            return;
+        }
+        let wdl = match tablebases.probe_wdl(board) {
+            Some(w) => w,
+            None => {
+                self.shell.output("The loaded tablebases do not contain the current material");
+                return;
+            }
+        };
+        self.shell.output(&format!("WDL: {}", wdl.name()));
+        self.shell.output(&format!("DTZ: {}", tablebases.probe_dtz(board).map_or(String::from("-"), |d| d.to_string())));
+        if let Some(moves) = tablebases.rank_root_moves(board).filter(|m| !m.is_empty()) {
+            self.shell.empty_line();
+            let table = Table::new(moves.iter().map(|m| m.to_printable()).collect::<Vec<_>>())
+                .with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+            self.shell.output(&table.to_string());
+        }
+    }
+
+    fn do_eval(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'eval' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                if a.get_flag("reset") {
+                    self.parameters = Arc::new(EvaluationParameters::default());
+                    self.shell.output("Evaluation parameters reset to their defaults");
+                }
+                if let Some(path) = a.get_arg("load") {
+                    match EvaluationParameters::from_file(&path) {
+                        Ok(parameters) => {
+                            self.shell.output(&format!("Loaded evaluation parameters from {}", path));
+                            self.parameters = Arc::new(parameters);
+                        },
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                if let Some(path) = a.get_arg("save") {
+                    match self.parameters.write(&path) {
+                        Ok(_) => self.shell.output(&format!("Evaluation parameters written to {}", path)),
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                self.shell.empty_line();
+                let table = Table::new(self.parameters.to_printable()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                self.shell.output(&format!("Static evaluation: {}", Evaluator::default().evaluate_board(self.game.get_board(), &self.parameters)));
+            }
+        }
+    }
+
+    fn do_tune(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'tune' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let mut options = TunerOptions::default();
+                if let Some(p) = a.get_arg("passes") { options.passes = p.parse().unwrap_or(options.passes) }
+                if let Some(s) = a.get_arg("step") { options.step = s.parse().unwrap_or(options.step).max(1) }
+                options.scaling_constant = a.get_arg("k").and_then(|k| k.parse::<f64>().ok()).filter(|k| *k > 0.0);
+                options.material_only = a.get_flag("material_only");
+                let output = a.get_arg("output").unwrap();
+                let start = Instant::now();
+                let (positions, errors) = match LabelledPosition::from_file(&a.get_arg("file").unwrap()) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                for error in &errors {
+                    self.shell.output(error);
+                }
+                let mut tuner = Tuner::new(self.parameters.as_ref().clone(), options);
+                tuner.add_positions(&positions);
+                if tuner.get_summary().positions_used == 0 {
+                    self.shell.output(&format!("None of the {} labelled positions are quiet enough to tune on", positions.len()));
+                    return;
+                }
+                self.shell.output(&format!("Tuning on {} of {} labelled positions", tuner.get_summary().positions_used.to_formatted_string(&Locale::en),
+                    positions.len().to_formatted_string(&Locale::en)));
+                self.shell.empty_line();
+                let mut passes = Vec::new();
+                let summary = match tuner.run(|pass| {
+                    self.shell.output(&format!("Pass {}: error {:.8} (step {}, {} parameters improved, {:?})", pass.pass, pass.error, pass.step, pass.improved, pass.duration));
+                    passes.push(pass.to_printable());
+                }) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                if let Err(e) = tuner.get_parameters().write(&output) {
+                    self.shell.output(&e.msg);
+                    return;
                 }
+                self.shell.empty_line();
+                let table = Table::new(passes).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                self.shell.output(&format!("     Positions: {}", summary.positions_read.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("          Used: {}", summary.positions_used.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!(" Skipped Noisy: {}", summary.skipped_noisy.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!(" Skipped Known: {}", summary.skipped_known.to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("             K: {:.4}", summary.scaling_constant));
+                self.shell.output(&format!(" Initial Error: {:.8}", summary.initial_error));
+                self.shell.output(&format!("   Final Error: {:.8}", summary.final_error));
+                self.shell.output(&format!("Changed Values: {}", summary.changed));
+                self.shell.output(&format!("   Tuning Time: {:?}", start.elapsed()));
+                self.shell.empty_line();
+                self.shell.output(&format!("Tuned parameters written to {}, use 'eval --load {}' to apply them", output, output));
             }
         }
     }
@@ -344,36 +928,452 @@ impl Interface {
         match args {
             ParsedArgs::SubCommand(_s) => panic!("Subcommand 'search' should not have its own subcommands"),
             ParsedArgs::Arguments(a) => {
-                let depth: u8 = match a.get_arg("depth") {
-                    Some(d) => d.parse().unwrap(),
-                    None => self.shell.input("What depth should the engine search to? ").parse().unwrap()
+                if a.get_flag("book") {
+                    let board = *self.game.get_board();
+                    let selection = if a.get_flag("best_book_move") { BookSelection::Best } else { BookSelection::Weighted };
+                    match self.book.as_mut().and_then(|b| b.choose_move(&board, selection)) {
+                        Some(mov) => {
+                            self.shell.empty_line();
+                            self.shell.output(&format!("Book move: {}", get_text_for_move(&mov)));
+                            return;
+                        },
+                        None if self.book.is_none() => self.shell.output("No opening book is loaded, searching instead"),
+                        None => (),
+                    }
+                }
+                let mut options = match a.get_flag("clock") {
+                    true => match self.game.get_clock() {
+                        Some(clock) => {
+                            let limit = TimeManager::from_clock(clock, self.game.get_current_turn()).get_time_limit();
+                            self.shell.output(&format!("Allocated {:?} from the {} clock", limit, self.game.get_current_turn().value()));
+                            let mut options = SearchOptions::with_time_limit(limit);
+                            if let Some(d) = a.get_arg("depth") {
+                                options.depth = d.parse().unwrap_or(options.depth).min(options.depth);
+                            }
+                            options
+                        },
+                        None => {
+                            self.shell.output("The current game has no clock, use 'clock --set <time control>' to add one");
+                            return;
+                        }
+                    },
+                    false => {
+                        let depth: u8 = match a.get_arg("depth") {
+                            Some(d) => d.parse().unwrap(),
+                            None => self.shell.input("What depth should the engine search to? ").parse().unwrap()
+                        };
+                        SearchOptions::new(depth)
+                    },
                 };
-                let start = Instant::now();
+                options.tablebases = self.tablebases.clone();
+                options.parameters = Arc::clone(&self.parameters);
+                options.multi_pv = a.get_arg("multipv").unwrap_or("1".to_string()).parse().unwrap_or(1).max(1);
                 let result = match a.get_arg("threads") {
+                    Some(_) if options.multi_pv > 1 => {
+                        self.shell.output("Multi-PV analysis is only supported by the single-threaded search.");
+                        return;
+                    },
                     Some(t) => {
                         let threads: u8 = t.parse().unwrap_or(1);
                         let sleep = a.get_arg("sleep").unwrap_or("0".to_string()).parse().unwrap_or(0u64);
-                        AlphaBetaSearch::do_threaded_search(*self.game.get_board(), depth, threads, sleep)
+                        AlphaBetaSearch::do_threaded_search(*self.game.get_board(), &options, threads, sleep)
                     },
                     None => {
-                        AlphaBetaSearch::do_search(*self.game.get_board(), depth)
+                        AlphaBetaSearch::do_search_with_options(*self.game.get_board(), &options)
                     }
                 };
-                let duration = start.elapsed();
                 self.shell.empty_line();
                 if let Some(mov) = result.mov {
                     self.shell.output(&format!("Best move: {}", get_text_for_move(&mov)));
                 }
+                self.shell.output(&format!("Position score: {}", result.score));
+                self.shell.empty_line();
+                self.output_principal_variations(&result.lines);
+                self.output_search_statistics(&result.statistics);
+            }
+        }
+    }
+
+    fn do_analyze_moves(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'analyze-moves' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let depth: u8 = match a.get_arg("depth") {
+                    Some(d) => d.parse().unwrap(),
+                    None => self.shell.input("What depth should the engine search to? ").parse().unwrap()
+                };
+                let mut options = SearchOptions::new(depth);
+                options.tablebases = self.tablebases.clone();
+                options.parameters = Arc::clone(&self.parameters);
+                for note in a.get_narg("moves").unwrap_or_default() {
+                    match get_move_from_uci_notation(self.game.get_board(), &note) {
+                        Ok(mov) => if !options.root_moves.contains(&mov) { options.root_moves.push(mov) },
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                options.multi_pv = options.root_moves.len() as u8;
+                let result = AlphaBetaSearch::do_search_with_options(*self.game.get_board(), &options);
+                self.shell.empty_line();
+                self.output_principal_variations(&result.lines);
+                self.output_search_statistics(&result.statistics);
+            }
+        }
+    }
+
+    fn do_epd(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'epd' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let (records, errors) = match EpdRecord::from_file(&a.get_arg("file").unwrap()) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                for error in &errors {
+                    self.shell.output(error);
+                }
+                let depth = match a.get_arg("depth").or_else(|| a.get_arg("time").is_none().then(|| self.shell.input("What depth should the engine search to? "))) {
+                    Some(d) => d.trim().parse::<u8>().map(Some).map_err(|_| InputError::new(&format!("'{}' is not a valid search depth", d.trim()))),
+                    None => Ok(None),
+                };
+                let time = match a.get_arg("time") {
+                    Some(t) => t.trim().parse::<u64>().map(|m| Some(Duration::from_millis(m))).map_err(|_| InputError::new(&format!("'{}' is not a valid search time in milliseconds", t.trim()))),
+                    None => Ok(None),
+                };
+                let (depth, time) = match depth.and_then(|d| time.map(|t| (d, t))) {
+                    Ok(limits) => limits,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                let mut options = match time {
+                    Some(t) => {
+                        let mut options = SearchOptions::with_time_limit(t);
+                        if let Some(d) = depth { options.depth = d }
+                        options
+                    },
+                    None => SearchOptions::new(depth.unwrap()),
+                };
+                options.parameters = Arc::clone(&self.parameters);
+                let start = Instant::now();
+                let results = EpdRunner::run(&records, &options, |r| {
+                    self.shell.output(&format!("{}: {} ({})", r.id, r.best_move.clone().unwrap_or(String::from("-")), if r.solved { "solved" } else { "FAIL" }));
+                });
+                let duration = start.elapsed();
+                self.shell.empty_line();
+                let table = Table::new(results.iter().map(PrintableEpdResult::from_result).collect::<Vec<_>>()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                let solved = results.iter().filter(|r| r.solved).count();
+                self.shell.output(&format!("Solved: {} of {} ({:.1}%)", solved, results.len(), solved as f64 * 100f64 / results.len().max(1) as f64));
+                self.shell.output(&format!("Failed: {}", results.len() - solved));
+                if results.iter().any(|r| r.points.is_some()) {
+                    let points: u32 = results.iter().filter_map(|r| r.points).sum();
+                    let max_points: u32 = results.iter().filter_map(|r| r.max_points).sum();
+                    self.shell.output(&format!("Points: {} of {}", points, max_points));
+                }
+                self.shell.output(&format!("Total nodes: {}", results.iter().map(|r| r.nodes).sum::<u64>().to_formatted_string(&Locale::en)));
+                self.shell.output(&format!("Completed in {:?}", duration));
+            }
+        }
+    }
+
+    fn do_match(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'match' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let mut engines: Vec<MatchEngine> = Vec::new();
+                for name in ["engine1", "engine2"] {
+                    match MatchEngine::from_spec(&a.get_arg(name).unwrap_or_default(), name) {
+                        Ok(e) => engines.push(e),
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                let engines = [engines.remove(0), engines.remove(0)];
+                let mut options = MatchOptions::new(a.get_arg("games").and_then(|g| g.parse().ok()).unwrap_or(DEFAULT_MATCH_GAMES));
+                if let Some(path) = a.get_arg("openings") {
+                    match MatchOpening::from_file(&path) {
+                        Ok(o) => options.openings = o,
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                if let Some(notation) = a.get_arg("time_control") {
+                    match TimeControl::from_notation(&notation) {
+                        Ok(tc) => options.time_control = Some(tc),
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                let adjudication = &mut options.adjudication;
+                if let Some(m) = a.get_arg("max_moves") { adjudication.max_moves = m.parse().unwrap_or(adjudication.max_moves) }
+                if let Some(s) = a.get_arg("resign_score") { adjudication.resign_score = s.parse().unwrap_or(adjudication.resign_score) }
+                if let Some(m) = a.get_arg("resign_moves") { adjudication.resign_moves = m.parse().unwrap_or(adjudication.resign_moves) }
+                if let Some(s) = a.get_arg("draw_score") { adjudication.draw_score = s.parse().unwrap_or(adjudication.draw_score) }
+                if let Some(m) = a.get_arg("draw_moves") { adjudication.draw_moves = m.parse().unwrap_or(adjudication.draw_moves) }
+                if let Some(m) = a.get_arg("draw_after") { adjudication.draw_after = m.parse().unwrap_or(adjudication.draw_after) }
+                let sprt = &mut options.sprt;
+                if let Some(e) = a.get_arg("elo0") { sprt.elo0 = e.parse().unwrap_or(sprt.elo0) }
+                if let Some(e) = a.get_arg("elo1") { sprt.elo1 = e.parse().unwrap_or(sprt.elo1) }
+                if let Some(p) = a.get_arg("alpha") { sprt.alpha = p.parse().unwrap_or(sprt.alpha) }
+                if let Some(p) = a.get_arg("beta") { sprt.beta = p.parse().unwrap_or(sprt.beta) }
+                options.stop_on_sprt = a.get_flag("sprt");
+                options.pgn_path = a.get_arg("pgn");
+                options.tablebases = self.tablebases.clone();
+                for engine in &engines {
+                    self.shell.output(&format!("{}: {}", engine.name, engine.describe()));
+                }
+                if let Some(tc) = &options.time_control {
+                    self.shell.output(&format!("Time control: {}", tc.to_notation()));
+                }
+                self.shell.output(&format!("Playing {} games from {} openings", options.games, options.openings.len()));
+                self.shell.empty_line();
+                let start = Instant::now();
+                let result = MatchRunner::run(&engines, &options, |game, statistics| {
+                    self.shell.output(&format!("Game {}: {} vs {} ({}): {} by {} in {} plies, {:?} [+{} ={} -{}]",
+                        game.round, game.white, game.black, game.opening, game.result.get_notation(), game.termination.name(),
+                        game.plies, game.duration, statistics.wins, statistics.draws, statistics.losses));
+                });
+                let statistics = match result {
+                    Ok(s) => s,
+                    Err(e) => {
+                        self.shell.output(&e.msg);
+                        return;
+                    }
+                };
+                self.shell.empty_line();
+                self.shell.output(&format!("Results for {} against {}:", engines[0].name, engines[1].name));
+                let table = Table::new(statistics.get_summary(&options.sprt)).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+                self.shell.output(&table.to_string());
+                if let Some(path) = &options.pgn_path {
+                    self.shell.output(&format!("Games written to {}", path));
+                }
+                self.shell.output(&format!("Completed in {:?}", start.elapsed()));
+            }
+        }
+    }
+
+    fn do_play(&mut self, args: ParsedArgs) {
+        match args {
+            ParsedArgs::SubCommand(_s) => panic!("Subcommand 'play' should not have its own subcommands"),
+            ParsedArgs::Arguments(a) => {
+                let human = match a.get_arg("color").as_deref() {
+                    None | Some("white") => Color::White,
+                    Some("black") => Color::Black,
+                    Some("random") => match Random::new().next_u64() % 2 {
+                        0 => Color::White,
+                        _ => Color::Black,
+                    },
+                    Some(c) => {
+                        self.shell.output(&format!("'{}' is not a valid color, use white, black or random", c));
+                        return;
+                    }
+                };
+                if let Some(notation) = a.get_arg("time_control") {
+                    match TimeControl::from_notation(&notation) {
+                        Ok(tc) => self.game.set_time_control(Some(tc)),
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    }
+                }
+                if let Some((result, termination)) = self.game.get_outcome() {
+                    self.shell.output(&format!("The current game is already over ({} by {}), use 'new' to start another", result.get_notation(), termination.name()));
+                    return;
+                }
+                let strength = match (a.get_arg("skill"), a.get_arg("elo")) {
+                    (Some(_), Some(_)) => Err(InputError::new("Choose either a skill level or an Elo, not both")),
+                    (Some(skill), None) => StrengthLevel::from_skill_name(&skill),
+                    (None, Some(elo)) => StrengthLevel::from_elo_name(&elo),
+                    (None, None) => Ok(StrengthLevel::default()),
+                };
+                let mut settings = PlaySettings {
+                    human: human,
+                    depth: a.get_arg("depth").and_then(|d| d.parse().ok()),
+                    time: a.get_arg("time").and_then(|t| t.parse().ok()).map(Duration::from_millis),
+                    strength: match strength {
+                        Ok(s) => s,
+                        Err(e) => {
+                            self.shell.output(&e.msg);
+                            return;
+                        }
+                    },
+                    use_book: a.get_flag("book"),
+                    random: Random::new(),
+                };
+                if let Some(clock) = self.game.get_clock_mut() {
+                    match clock.get_turn() {
+                        Some(_) => clock.resume(),
+                        None => self.game.start_clock(),
+                    }
+                }
+                self.shell.output(&format!("You are playing {} against the engine at {}. {}.", human.value(), settings.strength.name(), PLAY_COMMANDS));
                 self.shell.empty_line();
-                self.shell.output(&format!("Position score:             {}", result.score));
-                self.shell.output(&format!("Evaluated positions:        {}", result.evaluated_nodes.to_formatted_string(&Locale::en)));
-                self.shell.output(&format!("Cached transpositions used: {}", result.cache_hits.to_formatted_string(&Locale::en)));
-                self.shell.output(&format!("Beta cutoffs applied:       {}", result.beta_cutoffs.to_formatted_string(&Locale::en)));
-                self.shell.output(&format!("Completed in:               {:?}", duration));
+                self.output_board();
+                while self.game.get_outcome().is_none() {
+                    self.shell.empty_line();
+                    if self.game.get_current_turn() != human {
+                        self.play_engine_move(&mut settings);
+                        self.shell.empty_line();
+                        self.output_board();
+                        continue;
+                    }
+                    let input = self.shell.input(&format!("{} to move > ", human.value()));
+                    if self.game.get_outcome().is_some() {
+                        continue;
+                    }
+                    match input.trim() {
+                        "" => (),
+                        "resign" => {
+                            let confirm = self.shell.input("Are you sure you want to resign? (y/N) ");
+                            if self.confirmations.contains(&confirm.to_lowercase()) {
+                                self.game.resign(human);
+                            }
+                        },
+                        "draw" => self.play_draw_offer(&mut settings),
+                        "board" => self.output_board(),
+                        "moves" => {
+                            let board = *self.game.get_board();
+                            self.shell.output(&self.game.get_legal_moves().iter().map(|m| get_san_notation_for_move(&board, m)).collect::<Vec<_>>().join(" "));
+                        },
+                        "clock" => self.output_clock(),
+                        "help" => self.shell.output(PLAY_COMMANDS),
+                        "quit" | "exit" => {
+                            if let Some(clock) = self.game.get_clock_mut() {
+                                clock.pause();
+                            }
+                            self.shell.output("Leaving play mode, use 'play' to resume the game");
+                            return;
+                        },
+                        note => {
+                            let board = *self.game.get_board();
+                            match get_move_from_uci_notation(&board, note).or_else(|_| get_move_from_san_notation(&board, note)) {
+                                Ok(mov) if self.game.get_legal_moves().contains(&mov) => self.game.make_move(&mov),
+                                _ => self.shell.output(&format!("'{}' is not a legal move, type 'moves' to list them", note)),
+                            }
+                        },
+                    }
+                }
+                let (result, termination) = self.game.get_outcome().unwrap();
+                self.shell.empty_line();
+                self.shell.output(&format!("Game over: {} by {}", result.get_notation(), termination.name()));
+                self.shell.output(match result.get_winner() {
+                    Some(c) if c == human => "Congratulations, you won!",
+                    Some(_) => "The engine won this time.",
+                    None => "The game is drawn.",
+                });
+                if let Some(path) = a.get_arg("pgn") {
+                    let (white, black) = match human {
+                        Color::White => ("Human", "rust_chess"),
+                        Color::Black => ("rust_chess", "Human"),
+                    };
+                    let mut pgn = PgnGame::from_game(&self.game, Vec::from([
+                        (String::from("Event"), String::from("rust_chess play")),
+                        (String::from("Site"), String::from("local")),
+                        (String::from("White"), String::from(white)),
+                        (String::from("Black"), String::from(black)),
+                    ]), result);
+                    pgn.set_tag("Termination", termination.name());
+                    match write_pgn_file(&path, &[pgn], true) {
+                        Ok(_) => self.shell.output(&format!("Game written to {}", path)),
+                        Err(e) => self.shell.output(&e.msg),
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_play_search_options(&self, settings: &mut PlaySettings) -> SearchOptions {
+        let clock_limit = self.game.get_clock().map(|c| TimeManager::from_clock(c, self.game.get_current_turn()).get_time_limit());
+        let time_limit = match (settings.time, clock_limit) {
+            (Some(time), Some(limit)) => Some(time.min(limit)),
+            (time, limit) => time.or(limit),
+        };
+        let mut options = match time_limit {
+            Some(t) => SearchOptions::with_time_limit(t),
+            None => SearchOptions::new(settings.depth.unwrap_or(DEFAULT_PLAY_DEPTH)),
+        };
+        if let (Some(_), Some(d)) = (time_limit, settings.depth) {
+            options.depth = d;
+        }
+        options.tablebases = self.tablebases.clone();
+        options.parameters = Arc::clone(&self.parameters);
+        settings.strength.apply(&mut options, &mut settings.random);
+        return options;
+    }
+
+    fn play_engine_move(&mut self, settings: &mut PlaySettings) {
+        let board = *self.game.get_board();
+        if settings.use_book {
+            if let Some(mov) = self.book.as_mut().and_then(|b| b.choose_move(&board, BookSelection::Weighted)).filter(|m| self.game.get_legal_moves().contains(m)) {
+                self.shell.output(&format!("Engine plays {} (book)", get_san_notation_for_move(&board, &mov)));
+                self.game.make_move(&mov);
+                return;
+            }
+        }
+        self.shell.output("Engine is thinking...");
+        let result = AlphaBetaSearch::do_search_with_options(board, &self.get_play_search_options(settings));
+        match settings.strength.pick_move(&result, board.state.get_move_color(), &mut settings.random).filter(|m| self.game.get_legal_moves().contains(m)) {
+            Some(mov) => {
+                self.shell.output(&format!("Engine plays {} (score {}, depth {}, {:?})", get_san_notation_for_move(&board, &mov), result.score, result.depth, result.statistics.duration));
+                self.game.make_move(&mov);
+            },
+            None => {
+                self.shell.output("The engine could not find a move and resigns");
+                self.game.resign(settings.human.swap());
             }
         }
     }
 
+    fn play_draw_offer(&mut self, settings: &mut PlaySettings) {
+        self.shell.output("Engine is considering your draw offer...");
+        let result = AlphaBetaSearch::do_search_with_options(*self.game.get_board(), &self.get_play_search_options(settings));
+        let engine_score = match settings.human {
+            Color::White => -result.score,
+            Color::Black => result.score,
+        };
+        match engine_score <= DRAW_ACCEPT_SCORE {
+            true => {
+                self.shell.output("The engine accepts your draw offer");
+                self.game.agree_draw();
+            },
+            false => self.shell.output("The engine declines your draw offer"),
+        }
+    }
+
+    fn output_principal_variations(&self, lines: &[PrincipalVariation]) {
+        if lines.is_empty() {
+            return;
+        }
+        let table = Table::new(lines.iter().enumerate().map(|(index, pv)| pv.to_printable(index + 1)).collect::<Vec<_>>())
+            .with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::left()));
+        self.shell.output(&table.to_string());
+    }
+
+    fn output_search_statistics(&self, statistics: &SearchStatistics) {
+        let summary = Table::new(statistics.get_summary()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+        self.shell.output(&summary.to_string());
+        let depths = Table::new(statistics.get_depth_analysis()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+        self.shell.output(&depths.to_string());
+        if !statistics.threads.is_empty() {
+            let threads = Table::new(statistics.get_thread_analysis()).with(Style::pseudo_clean()).with(Modify::new(Full).with(Alignment::right()));
+            self.shell.output(&threads.to_string());
+        }
+    }
+
     fn do_serialize(&self, args: ParsedArgs) {
         match args {
             ParsedArgs::SubCommand(_s) => panic!("Subcommand 'serialize' should not have its own subcommands"),
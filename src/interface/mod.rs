@@ -1,4 +1,6 @@
 pub mod cli;
+pub mod uci;
+pub mod uci_client;
 
 mod arguments;
 mod shell;
\ No newline at end of file
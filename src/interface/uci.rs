@@ -0,0 +1,274 @@
+use std::{io::{self, BufRead, Write}, sync::Arc, time::Duration};
+
+use crate::{game::Game, engine::{book::{BookSelection, OpeningBook}, parameters::EvaluationParameters, search::{SearchOptions, SearchResult, alpha_beta::AlphaBetaSearch, time_manager::TimeManager}, strength::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO, StrengthLevel}, tablebase::Tablebases}, rules::{Color, pieces::movement::Move}, util::{errors::InputError, notation::{get_chess960_uci_notation_for_move, get_chess960_uci_notation_for_moves, get_move_from_uci_notation, get_uci_notation_for_move, get_uci_notation_for_moves}, random::Random}};
+
+
+static ENGINE_NAME: &str = "rust_chess";
+static ENGINE_AUTHOR: &str = "bendemeyer";
+static DEFAULT_DEPTH: u8 = 5;
+static MAX_MULTI_PV: u8 = 64;
+static DEFAULT_UCI_ELO: u16 = 1500;
+static GO_KEYWORDS: [&str; 12] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite"];
+
+
+pub struct UciInterface {
+    game: Game,
+    multi_pv: u8,
+    chess960: bool,
+    own_book: bool,
+    best_book_move: bool,
+    book: Option<OpeningBook>,
+    tablebases: Option<Arc<Tablebases>>,
+    parameters: Arc<EvaluationParameters>,
+    skill_level: u8,
+    limit_strength: bool,
+    elo: u16,
+    random: Random,
+}
+
+impl UciInterface {
+    pub fn new() -> UciInterface {
+        return UciInterface {
+            game: Game::new(),
+            multi_pv: 1,
+            chess960: false,
+            own_book: false,
+            best_book_move: false,
+            book: None,
+            tablebases: None,
+            parameters: Arc::new(EvaluationParameters::default()),
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: DEFAULT_UCI_ELO,
+            random: Random::new(),
+        }
+    }
+
+    pub fn init(&mut self) {
+        self.do_uci();
+        for line in io::stdin().lock().lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_) => break,
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.first() {
+                Some(&"uci")        => self.do_uci(),
+                Some(&"isready")    => self.do_isready(),
+                Some(&"setoption")  => self.do_setoption(&tokens[1..]),
+                Some(&"ucinewgame") => self.game = Game::new(),
+                Some(&"position")   => {
+                    if let Err(e) = self.do_position(&tokens[1..]) {
+                        self.output(&format!("info string {}", e.msg));
+                    }
+                },
+                Some(&"go")         => self.do_go(&tokens[1..]),
+                Some(&"quit")       => break,
+                _ => (),
+            }
+        }
+    }
+
+    fn output(&self, line: &str) {
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{}", line).expect("Error writing to stdout");
+        stdout.flush().expect("Error flushing stdout");
+    }
+
+    fn do_uci(&self) {
+        self.output(&format!("id name {}", ENGINE_NAME));
+        self.output(&format!("id author {}", ENGINE_AUTHOR));
+        self.output(&format!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV));
+        self.output("option name UCI_Chess960 type check default false");
+        self.output("option name OwnBook type check default false");
+        self.output("option name BookFile type string default <empty>");
+        self.output("option name Best Book Move type check default false");
+        self.output("option name SyzygyPath type string default <empty>");
+        self.output("option name EvalFile type string default <empty>");
+        self.output(&format!("option name Skill Level type spin default {} min 0 max {}", MAX_SKILL_LEVEL, MAX_SKILL_LEVEL));
+        self.output("option name UCI_LimitStrength type check default false");
+        self.output(&format!("option name UCI_Elo type spin default {} min {} max {}", DEFAULT_UCI_ELO, MIN_ELO, MAX_ELO));
+        self.output("uciok");
+    }
+
+    fn do_isready(&self) {
+        self.game.get_legal_moves();
+        self.output("readyok");
+    }
+
+    fn do_setoption(&mut self, tokens: &[&str]) {
+        let name_start = tokens.iter().position(|t| *t == "name").map(|i| i + 1).unwrap_or(tokens.len());
+        let value_start = tokens.iter().position(|t| *t == "value").unwrap_or(tokens.len());
+        let name = tokens[name_start..value_start.max(name_start)].join(" ");
+        let value = tokens[(value_start + 1).min(tokens.len())..].join(" ");
+        match name.to_lowercase().as_str() {
+            "multipv" => match value.parse::<u8>() {
+                Ok(v) => self.multi_pv = v.clamp(1, MAX_MULTI_PV),
+                Err(_) => self.output(&format!("info string Invalid MultiPV value '{}'", value)),
+            },
+            "uci_chess960" => match value.parse::<bool>() {
+                Ok(v) => self.chess960 = v,
+                Err(_) => self.output(&format!("info string Invalid UCI_Chess960 value '{}'", value)),
+            },
+            "ownbook" => match value.parse::<bool>() {
+                Ok(v) => self.own_book = v,
+                Err(_) => self.output(&format!("info string Invalid OwnBook value '{}'", value)),
+            },
+            "best book move" => match value.parse::<bool>() {
+                Ok(v) => self.best_book_move = v,
+                Err(_) => self.output(&format!("info string Invalid Best Book Move value '{}'", value)),
+            },
+            "bookfile" => match value.as_str() {
+                "" | "<empty>" => self.book = None,
+                path => match OpeningBook::from_file(path) {
+                    Ok(book) => self.book = Some(book),
+                    Err(e) => {
+                        self.book = None;
+                        self.output(&format!("info string {}", e.msg));
+                    },
+                },
+            },
+            "skill level" => match value.parse::<u8>() {
+                Ok(v) => self.skill_level = v.min(MAX_SKILL_LEVEL),
+                Err(_) => self.output(&format!("info string Invalid Skill Level value '{}'", value)),
+            },
+            "uci_limitstrength" => match value.parse::<bool>() {
+                Ok(v) => self.limit_strength = v,
+                Err(_) => self.output(&format!("info string Invalid UCI_LimitStrength value '{}'", value)),
+            },
+            "uci_elo" => match value.parse::<u16>() {
+                Ok(v) => self.elo = v.clamp(MIN_ELO, MAX_ELO),
+                Err(_) => self.output(&format!("info string Invalid UCI_Elo value '{}'", value)),
+            },
+            "syzygypath" => match value.as_str() {
+                "" | "<empty>" => self.tablebases = None,
+                path => match Tablebases::from_paths(path) {
+                    Ok(tablebases) => {
+                        self.output(&format!("info string Found {} tablebases with up to {} pieces", tablebases.wdl_count(), tablebases.get_max_cardinality()));
+                        self.tablebases = Some(Arc::new(tablebases));
+                    },
+                    Err(e) => {
+                        self.tablebases = None;
+                        self.output(&format!("info string {}", e.msg));
+                    },
+                },
+            },
+            "evalfile" => match value.as_str() {
+                "" | "<empty>" => self.parameters = Arc::new(EvaluationParameters::default()),
+                path => match EvaluationParameters::from_file(path) {
+                    Ok(parameters) => self.parameters = Arc::new(parameters),
+                    Err(e) => {
+                        self.parameters = Arc::new(EvaluationParameters::default());
+                        self.output(&format!("info string {}", e.msg));
+                    },
+                },
+            },
+            _ => self.output(&format!("info string Unknown option '{}'", name)),
+        }
+    }
+
+    fn do_position(&mut self, tokens: &[&str]) -> Result<(), InputError> {
+        let moves_start = tokens.iter().position(|t| *t == "moves").unwrap_or(tokens.len());
+        let mut game = match tokens.first() {
+            Some(&"startpos") => Game::new(),
+            Some(&"fen") => Game::from_fen(&tokens[1..moves_start].join(" "))?,
+            _ => return Err(InputError::new("Expected 'startpos' or 'fen' in position command")),
+        };
+        for note in tokens.iter().skip(moves_start + 1) {
+            let mov = get_move_from_uci_notation(game.get_board(), note)?;
+            game.make_move(&mov);
+        }
+        self.game = game;
+        return Ok(());
+    }
+
+    fn do_go(&mut self, tokens: &[&str]) {
+        let get_value = |keyword: &str| tokens.iter().position(|t| *t == keyword).and_then(|i| tokens.get(i + 1)).and_then(|v| v.parse::<u64>().ok());
+        let depth: Option<u8> = get_value("depth").map(|d| d.min(u8::MAX as u64) as u8);
+        let (time, increment) = match self.game.get_current_turn() {
+            Color::White => (get_value("wtime"), get_value("winc")),
+            Color::Black => (get_value("btime"), get_value("binc")),
+        };
+        let clock_limit = time.map(|t| {
+            let moves_to_go = get_value("movestogo").map(|m| m.min(u16::MAX as u64) as u16);
+            TimeManager::new(Duration::from_millis(t), Duration::from_millis(increment.unwrap_or(0)), moves_to_go).get_time_limit()
+        });
+        let time_limit = match (get_value("movetime").map(Duration::from_millis), clock_limit) {
+            (Some(movetime), Some(limit)) => Some(movetime.min(limit)),
+            (movetime, limit) => movetime.or(limit),
+        };
+        let mut options = match time_limit {
+            Some(t) => SearchOptions::with_time_limit(t),
+            None => SearchOptions::new(depth.unwrap_or(DEFAULT_DEPTH)),
+        };
+        if let (Some(_), Some(d)) = (time_limit, depth) {
+            options.depth = d;
+        }
+        options.node_limit = get_value("nodes");
+        options.multi_pv = self.multi_pv;
+        options.tablebases = self.tablebases.clone();
+        options.parameters = Arc::clone(&self.parameters);
+        if let Some(i) = tokens.iter().position(|t| *t == "searchmoves") {
+            for note in tokens[i + 1..].iter().take_while(|t| !GO_KEYWORDS.contains(t)) {
+                match get_move_from_uci_notation(self.game.get_board(), note) {
+                    Ok(mov) => options.root_moves.push(mov),
+                    Err(e) => self.output(&format!("info string {}", e.msg)),
+                }
+            }
+        }
+        if let Some(mov) = self.get_book_move(&options) {
+            self.output(&format!("bestmove {}", self.get_notation_for_move(&mov)));
+            return;
+        }
+        let strength = self.get_strength();
+        strength.apply(&mut options, &mut self.random);
+        let result = AlphaBetaSearch::do_search_with_options(*self.game.get_board(), &options);
+        let best = strength.pick_move(&result, self.game.get_current_turn(), &mut self.random);
+        self.output_search_result(&result, best);
+    }
+
+    fn get_strength(&self) -> StrengthLevel {
+        return match self.limit_strength {
+            true => StrengthLevel::from_elo(self.elo),
+            false => StrengthLevel::from_skill(self.skill_level),
+        }
+    }
+
+    fn get_book_move(&mut self, options: &SearchOptions) -> Option<Move> {
+        if !self.own_book { return None }
+        let selection = if self.best_book_move { BookSelection::Best } else { BookSelection::Weighted };
+        return self.book.as_mut()?.choose_move(self.game.get_board(), selection).filter(|m| options.allows_root_move(m));
+    }
+
+    fn get_notation_for_move(&self, mov: &Move) -> String {
+        return match self.chess960 {
+            true => get_chess960_uci_notation_for_move(mov),
+            false => get_uci_notation_for_move(mov),
+        }
+    }
+
+    fn output_search_result(&self, result: &SearchResult, best: Option<Move>) {
+        let perspective = match self.game.get_current_turn() {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        for (index, pv) in result.lines.iter().take(self.multi_pv as usize).enumerate() {
+            self.output(&format!("info depth {} multipv {} score cp {} nodes {} nps {} time {} pv {}",
+                result.depth,
+                index + 1,
+                pv.score as i32 * perspective,
+                result.statistics.nodes(),
+                result.statistics.nodes_per_second(),
+                result.statistics.duration.as_millis(),
+                match self.chess960 {
+                    true => get_chess960_uci_notation_for_moves(&pv.moves),
+                    false => get_uci_notation_for_moves(&pv.moves),
+                },
+            ));
+        }
+        self.output(&format!("bestmove {}", match best {
+            Some(mov) => self.get_notation_for_move(&mov),
+            None => String::from("0000"),
+        }));
+    }
+}
@@ -0,0 +1,245 @@
+use std::{io::{BufRead, BufReader, Write}, process::{Child, ChildStdin, Command, Stdio}, thread, time::{Duration, Instant}};
+
+use crossbeam::channel::{Receiver, RecvTimeoutError, unbounded};
+
+use crate::{game::Game, rules::{Color, pieces::movement::Move}, util::{errors::InputError, fen::STARTING_POSITION, notation::{get_chess960_uci_notation_for_moves, get_move_from_uci_notation, get_uci_notation_for_moves}}};
+
+
+static HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+static STOP_TIMEOUT: Duration = Duration::from_secs(1);
+static MATE_SCORE: i32 = 30_000;
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UciEngineFailure {
+    Crash,
+    TimeForfeit,
+    IllegalMove(String),
+}
+
+impl UciEngineFailure {
+    pub fn name(&self) -> String {
+        return match self {
+            Self::Crash => String::from("engine crashed"),
+            Self::TimeForfeit => String::from("engine exceeded its time"),
+            Self::IllegalMove(m) => format!("engine played illegal move '{}'", m),
+        }
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub struct UciClockState {
+    pub remaining: [Duration; 2],
+    pub increment: [Duration; 2],
+    pub moves_to_go: Option<u16>,
+}
+
+
+#[derive(Copy, Clone)]
+pub struct UciSearchLimits {
+    pub depth: Option<u8>,
+    pub movetime: Option<Duration>,
+    pub clock: Option<UciClockState>,
+    pub timeout: Duration,
+}
+
+
+pub struct UciSearchResult {
+    pub mov: Move,
+    pub score: Option<i16>,
+    pub depth: Option<u8>,
+    pub duration: Duration,
+}
+
+
+fn parse_info_score(tokens: &[&str]) -> Option<i32> {
+    let index = tokens.iter().position(|t| *t == "score")?;
+    let value: i32 = tokens.get(index + 2)?.parse().ok()?;
+    return match *tokens.get(index + 1)? {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - value),
+        "mate" => Some(-MATE_SCORE - value),
+        _ => None,
+    }
+}
+
+fn parse_info_depth(tokens: &[&str]) -> Option<u8> {
+    let index = tokens.iter().position(|t| *t == "depth")?;
+    return tokens.get(index + 1)?.parse().ok();
+}
+
+
+pub struct UciEngineProcess {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    chess960: bool,
+}
+
+impl UciEngineProcess {
+    pub fn spawn(command: &str) -> Result<Self, InputError> {
+        let words = shell_words::split(command).map_err(|e| InputError::new(&format!("Could not parse engine command '{}': {}", command, e)))?;
+        let (program, args) = match words.split_first() {
+            Some(parts) => parts,
+            None => return Err(InputError::new("No engine command was specified")),
+        };
+        let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn()
+            .map_err(|e| InputError::new(&format!("Could not start engine '{}': {}", command, e)))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, rx) = unbounded();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(l) => if tx.send(l).is_err() { break },
+                    Err(_) => break,
+                }
+            }
+        });
+        let mut engine = Self {
+            name: String::from(program.as_str()),
+            child: child,
+            stdin: stdin,
+            lines: rx,
+            chess960: false,
+        };
+        let handshake = engine.send("uci").and_then(|_| engine.wait_for("uciok", HANDSHAKE_TIMEOUT));
+        match handshake {
+            Ok(lines) => {
+                if let Some(name) = lines.iter().find_map(|l| l.strip_prefix("id name ")) {
+                    engine.name = String::from(name.trim());
+                }
+            },
+            Err(e) => return Err(InputError::new(&format!("Engine '{}' did not complete the UCI handshake: {}", command, e.name()))),
+        }
+        return Ok(engine);
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciEngineFailure> {
+        return self.send(&format!("setoption name {} value {}", name, value));
+    }
+
+    pub fn new_game(&mut self) -> Result<(), UciEngineFailure> {
+        self.send("ucinewgame")?;
+        return self.wait_until_ready(HANDSHAKE_TIMEOUT);
+    }
+
+    pub fn is_alive(&mut self) -> bool {
+        return matches!(self.child.try_wait(), Ok(None));
+    }
+
+    pub fn search(&mut self, game: &Game, limits: &UciSearchLimits) -> Result<UciSearchResult, UciEngineFailure> {
+        let chess960 = game.get_starting_board().is_chess960();
+        if chess960 != self.chess960 {
+            self.set_option("UCI_Chess960", &chess960.to_string())?;
+            self.wait_until_ready(HANDSHAKE_TIMEOUT)?;
+            self.chess960 = chess960;
+        }
+        let start_fen = game.get_starting_board().to_fen();
+        let position = match start_fen == STARTING_POSITION {
+            true => String::from("position startpos"),
+            false => format!("position fen {}", start_fen),
+        };
+        let moves = game.get_moves();
+        let notation = match chess960 {
+            true => get_chess960_uci_notation_for_moves(&moves),
+            false => get_uci_notation_for_moves(&moves),
+        };
+        match moves.is_empty() {
+            true => self.send(&position)?,
+            false => self.send(&format!("{} moves {}", position, notation))?,
+        }
+        let mut go = String::from("go");
+        if let Some(depth) = limits.depth {
+            go.push_str(&format!(" depth {}", depth));
+        }
+        if let Some(movetime) = limits.movetime {
+            go.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        if let Some(clock) = limits.clock {
+            go.push_str(&format!(" wtime {} btime {} winc {} binc {}", clock.remaining[0].as_millis(), clock.remaining[1].as_millis(),
+                clock.increment[0].as_millis(), clock.increment[1].as_millis()));
+            if let Some(moves) = clock.moves_to_go {
+                go.push_str(&format!(" movestogo {}", moves));
+            }
+        }
+        let start = Instant::now();
+        self.send(&go)?;
+        let lines = match self.wait_for("bestmove", limits.timeout) {
+            Ok(l) => l,
+            Err(UciEngineFailure::TimeForfeit) => {
+                if self.send("stop").is_err() || self.wait_for("bestmove", STOP_TIMEOUT).is_err() {
+                    let _ = self.child.kill();
+                }
+                return Err(UciEngineFailure::TimeForfeit);
+            },
+            Err(e) => return Err(e),
+        };
+        let duration = start.elapsed();
+        let note = lines.last().and_then(|l| l.split_whitespace().nth(1)).unwrap_or("(none)");
+        let mov = match get_move_from_uci_notation(game.get_board(), note) {
+            Ok(m) if game.get_legal_moves().contains(&m) => m,
+            _ => return Err(UciEngineFailure::IllegalMove(String::from(note))),
+        };
+        let info: Vec<Vec<&str>> = lines.iter().filter(|l| l.starts_with("info ")).map(|l| l.split_whitespace().collect()).collect();
+        let perspective = match game.get_current_turn() {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        return Ok(UciSearchResult {
+            mov: mov,
+            score: info.iter().rev().find_map(|t| parse_info_score(t)).map(|s| (s * perspective).clamp(i16::MIN as i32 + 1, i16::MAX as i32) as i16),
+            depth: info.iter().rev().find_map(|t| parse_info_depth(t)),
+            duration: duration,
+        });
+    }
+
+    pub fn quit(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while Instant::now() < deadline {
+            if !self.is_alive() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), UciEngineFailure> {
+        return writeln!(self.stdin, "{}", line).and_then(|_| self.stdin.flush()).map_err(|_| UciEngineFailure::Crash);
+    }
+
+    fn wait_until_ready(&mut self, timeout: Duration) -> Result<(), UciEngineFailure> {
+        self.send("isready")?;
+        return self.wait_for("readyok", timeout).map(|_| ());
+    }
+
+    fn wait_for(&mut self, keyword: &str, timeout: Duration) -> Result<Vec<String>, UciEngineFailure> {
+        let deadline = Instant::now() + timeout;
+        let mut lines: Vec<String> = Vec::new();
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(l) => l,
+                Err(RecvTimeoutError::Timeout) => return Err(UciEngineFailure::TimeForfeit),
+                Err(RecvTimeoutError::Disconnected) => return Err(UciEngineFailure::Crash),
+            };
+            let found = line.split_whitespace().next() == Some(keyword);
+            lines.push(line);
+            if found {
+                return Ok(lines);
+            }
+        }
+    }
+}
+
+impl Drop for UciEngineProcess {
+    fn drop(&mut self) {
+        if self.is_alive() {
+            self.quit();
+        }
+        let _ = self.child.wait();
+    }
+}
@@ -1,18 +1,19 @@
-use fxhash::FxHashMap;
-
 use crate::rules::{pieces::{movement::{SlideDirection, PawnMovement}, Piece, PieceType}, Color};
 
+use super::magics::{get_bishop_attacks, get_queen_attacks, get_rook_attacks};
 use super::squares::BoardSquare;
 
 
+pub static DARK_SQUARES: u64 = 0xAA55AA55AA55AA55;
+
 lazy_static! {
-    static ref RAY_BITBOARDS: FxHashMap<u16, u64> = prepare_ray_bitboards();
-    static ref DIAGONAL_BITBOARDS: FxHashMap<u8, u64> = prepare_diagonal_bitboards();
-    static ref ORTHAGONAL_BITBOARDS: FxHashMap<u8, u64> = prepare_orthagonal_bitboards();
+    static ref RAY_BITBOARDS: [[u64; 64]; 8] = prepare_ray_bitboards();
+    static ref DIAGONAL_BITBOARDS: [u64; 64] = prepare_diagonal_bitboards();
+    static ref ORTHAGONAL_BITBOARDS: [u64; 64] = prepare_orthagonal_bitboards();
 
-    static ref KNIGHT_BITBOARDS: FxHashMap<u8, u64> = prepare_knight_bitboards();
-    static ref PAWN_BITBOARDS: FxHashMap<u8, u64> = prepare_pawn_bitboards();
-    static ref KING_BITBOARDS: FxHashMap<u8, u64> = prepare_king_bitboards();
+    static ref KNIGHT_BITBOARDS: [u64; 64] = prepare_knight_bitboards();
+    static ref PAWN_BITBOARDS: [[u64; 64]; 4] = prepare_pawn_bitboards();
+    static ref KING_BITBOARDS: [u64; 64] = prepare_king_bitboards();
 }
 
 
@@ -148,17 +149,17 @@ impl<I> Iterator for BitboardPieceLocations<I> where I: Iterator<Item=PieceBoard
 
 
 pub fn get_bit_for_square(square: u8) -> u64 {
-    return 2u64.pow(square as u32)
+    return 1u64 << square
 }
 
 
 pub fn set_bit_at_square(board: u64, square: u8) -> u64 {
-    return board | 2u64.pow(square as u32)
+    return board | (1u64 << square)
 }
 
 
 pub fn unset_bit_at_square(board: u64, square: u8) -> u64 {
-    return board & !(2u64.pow(square as u32))
+    return board & !(1u64 << square)
 }
 
 
@@ -179,36 +180,36 @@ fn generate_sliding_bitboard(square: u8, direction: SlideDirection) -> u64 {
 }
 
 
-fn get_slide_direction_key(square: u8, dir: SlideDirection) -> u16 {
-    return square as u16 + dir.get_hash_offset()
-}
-
-
-fn prepare_ray_bitboards() -> FxHashMap<u16, u64> {
-    SlideDirection::all_directions().iter().fold(Default::default(),|mut map, dir| {
-        (0u8..=63u8).for_each(|s| {
-            map.insert(get_slide_direction_key(s, *dir), generate_sliding_bitboard(s, *dir));
-        });
-        map
-    })
+fn prepare_ray_bitboards() -> [[u64; 64]; 8] {
+    let mut boards = [[0u64; 64]; 8];
+    for dir in SlideDirection::all_directions() {
+        for s in 0u8..=63u8 {
+            boards[dir.get_table_index()][s as usize] = generate_sliding_bitboard(s, dir);
+        }
+    }
+    return boards;
 }
 
 
-fn prepare_diagonal_bitboards() -> FxHashMap<u8, u64> {
-    (0u8..=63u8).map(|s| {
-        (s, SlideDirection::diagonals().into_iter().fold(0u64, |board, dir| {
+fn prepare_diagonal_bitboards() -> [u64; 64] {
+    let mut boards = [0u64; 64];
+    for s in 0u8..=63u8 {
+        boards[s as usize] = SlideDirection::diagonals().into_iter().fold(0u64, |board, dir| {
             board | generate_sliding_bitboard(s, dir)
-        }))
-    }).collect()
+        });
+    }
+    return boards;
 }
 
 
-fn prepare_orthagonal_bitboards() -> FxHashMap<u8, u64> {
-    (0u8..=63u8).map(|s| {
-        (s, SlideDirection::orthagonals().into_iter().fold(0u64, |board, dir| {
+fn prepare_orthagonal_bitboards() -> [u64; 64] {
+    let mut boards = [0u64; 64];
+    for s in 0u8..=63u8 {
+        boards[s as usize] = SlideDirection::orthagonals().into_iter().fold(0u64, |board, dir| {
             board | generate_sliding_bitboard(s, dir)
-        }))
-    }).collect()
+        });
+    }
+    return boards;
 }
 
 
@@ -230,23 +231,14 @@ fn generate_pawn_bitboard(square: u8, movement: PawnMovement) -> u64 {
 }
 
 
-fn get_pawn_movement_key(square: u8, m: PawnMovement) -> u8 {
-    return square + m.get_hash_offset()
-}
-
-
-fn prepare_pawn_bitboards() -> FxHashMap<u8, u64> {
-    [
-        PawnMovement::WhiteAdvance,
-        PawnMovement::WhiteAttack,
-        PawnMovement::BlackAdvance,
-        PawnMovement::BlackAttack,
-    ].iter().fold(Default::default(), |mut map, mov| {
-        (0u8..=63u8).for_each(|s| {
-            map.insert(get_pawn_movement_key(s, *mov), generate_pawn_bitboard(s, *mov));
-        });
-        map
-    })
+fn prepare_pawn_bitboards() -> [[u64; 64]; 4] {
+    let mut boards = [[0u64; 64]; 4];
+    for mov in [PawnMovement::WhiteAdvance, PawnMovement::WhiteAttack, PawnMovement::BlackAdvance, PawnMovement::BlackAttack] {
+        for s in 0u8..=63u8 {
+            boards[mov.get_table_index()][s as usize] = generate_pawn_bitboard(s, mov);
+        }
+    }
+    return boards;
 }
 
 
@@ -276,11 +268,12 @@ fn generate_knight_bitboard(square: u8) -> u64 {
 }
 
 
-fn prepare_knight_bitboards() -> FxHashMap<u8, u64> {
-    return (0u8..=63u8).fold(Default::default(), |mut map, s| {
-        map.insert(s, generate_knight_bitboard(s));
-        map
-    })
+fn prepare_knight_bitboards() -> [u64; 64] {
+    let mut boards = [0u64; 64];
+    for s in 0u8..=63u8 {
+        boards[s as usize] = generate_knight_bitboard(s);
+    }
+    return boards;
 }
 
 
@@ -299,36 +292,37 @@ fn generate_king_bitboard(square: u8) -> u64 {
 }
 
 
-fn prepare_king_bitboards() -> FxHashMap<u8, u64> {
-    return (0u8..=63u8).fold(Default::default(), |mut map, s| {
-        map.insert(s, generate_king_bitboard(s));
-        map
-    })
+fn prepare_king_bitboards() -> [u64; 64] {
+    let mut boards = [0u64; 64];
+    for s in 0u8..=63u8 {
+        boards[s as usize] = generate_king_bitboard(s);
+    }
+    return boards;
 }
 
 
 pub fn get_ray_bitboard(square: u8, dir: SlideDirection) -> u64 {
-    return *RAY_BITBOARDS.get(&get_slide_direction_key(square, dir)).unwrap();
+    return RAY_BITBOARDS[dir.get_table_index()][square as usize];
 }
 
 pub fn get_diagonal_bitboard(square: u8) -> u64 {
-    return *DIAGONAL_BITBOARDS.get(&square).unwrap();
+    return DIAGONAL_BITBOARDS[square as usize];
 }
 
 pub fn get_orthagonal_bitboard(square: u8) -> u64 {
-    return *ORTHAGONAL_BITBOARDS.get(&square).unwrap();
+    return ORTHAGONAL_BITBOARDS[square as usize];
 }
 
 pub fn get_pawn_bitboard(square: u8, mov: PawnMovement) -> u64 {
-    return *PAWN_BITBOARDS.get(&get_pawn_movement_key(square, mov)).unwrap();
+    return PAWN_BITBOARDS[mov.get_table_index()][square as usize];
 }
 
 pub fn get_knight_bitboard(square: u8) -> u64 {
-    return *KNIGHT_BITBOARDS.get(&square).unwrap();
+    return KNIGHT_BITBOARDS[square as usize];
 }
 
 pub fn get_king_bitboard(square: u8) -> u64 {
-    return *KING_BITBOARDS.get(&square).unwrap();
+    return KING_BITBOARDS[square as usize];
 }
 
 fn get_moves_for_slide_direction(square: u8, friendlies: u64, enemies: u64, dir: SlideDirection) -> u64 {
@@ -360,6 +354,15 @@ fn get_moves_for_slide_directions<'a, I>(square: u8, friendlies: u64, enemies: u
 }
 
 
+pub fn get_ray_walk_moves_for_slider(square: u8, piece_type: PieceType, friendlies: u64, enemies: u64) -> u64 {
+    return match piece_type {
+        PieceType::Bishop => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::diagonals().iter()),
+        PieceType::Rook   => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::orthagonals().iter()),
+        _                 => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::all_directions().iter()),
+    }
+}
+
+
 fn get_moves_for_pawn_attacks(square: u8, enemies: u64, mov: PawnMovement, en_passant_target: u64) -> u64 {
     return get_pawn_bitboard(square, mov) & (enemies | en_passant_target);
 }
@@ -407,9 +410,9 @@ pub fn get_moves_for_piece(square: u8, piece: Piece, friendlies: u64, enemies: u
     match piece.piece_type {
         PieceType::Pawn   => get_moves_for_pawn(square, friendlies, enemies, piece.color, en_passant_target),
         PieceType::Knight => get_moves_for_knight(square, friendlies),
-        PieceType::Bishop => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::diagonals().iter()),
-        PieceType::Rook   => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::orthagonals().iter()),
-        PieceType::Queen  => get_moves_for_slide_directions(square, friendlies, enemies, SlideDirection::all_directions().iter()),
+        PieceType::Bishop => get_bishop_attacks(square, friendlies | enemies) & !friendlies,
+        PieceType::Rook   => get_rook_attacks(square, friendlies | enemies) & !friendlies,
+        PieceType::Queen  => get_queen_attacks(square, friendlies | enemies) & !friendlies,
         PieceType::King   => get_moves_for_king(square, friendlies),
     }
 }
@@ -0,0 +1,183 @@
+use crate::rules::pieces::movement::SlideDirection;
+
+use super::bitboards::get_bit_for_square;
+use super::squares::{get_col_and_row_from_square, get_square_from_col_and_row};
+
+
+#[cfg(all(feature = "pext", not(all(target_arch = "x86_64", target_feature = "bmi2"))))]
+compile_error!("The 'pext' feature requires an x86_64 target with BMI2 enabled, e.g. RUSTFLAGS=\"-C target-cpu=native\"");
+
+
+static MAGIC_SEED: u64 = 0x2545F4914F6CDD1D;
+
+
+lazy_static! {
+    static ref BISHOP_MAGICS: SliderTable = SliderTable::generate(&SlideDirection::diagonals());
+    static ref ROOK_MAGICS: SliderTable = SliderTable::generate(&SlideDirection::orthagonals());
+}
+
+
+struct MagicRandom {
+    state: u64,
+}
+
+impl MagicRandom {
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        return self.state.wrapping_mul(0x2545F4914F6CDD1D);
+    }
+
+    fn next_sparse(&mut self) -> u64 {
+        return self.next() & self.next() & self.next();
+    }
+}
+
+
+#[derive(Copy, Clone, Default)]
+struct MagicEntry {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl MagicEntry {
+    #[cfg(not(feature = "pext"))]
+    fn get_index(&self, occupancy: u64) -> usize {
+        return self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+    }
+
+    #[cfg(feature = "pext")]
+    fn get_index(&self, occupancy: u64) -> usize {
+        return self.offset + unsafe { core::arch::x86_64::_pext_u64(occupancy, self.mask) } as usize;
+    }
+}
+
+
+struct SliderTable {
+    entries: [MagicEntry; 64],
+    attacks: Vec<u64>,
+}
+
+impl SliderTable {
+    fn generate(directions: &[SlideDirection]) -> Self {
+        let mut random = MagicRandom { state: MAGIC_SEED };
+        let mut entries = [MagicEntry::default(); 64];
+        let mut attacks: Vec<u64> = Vec::new();
+        for square in 0u8..=63u8 {
+            let mask = generate_relevant_occupancy_mask(square, directions);
+            let occupancies = get_occupancy_subsets(mask);
+            let references: Vec<u64> = occupancies.iter().map(|o| generate_sliding_attacks(square, directions, *o)).collect();
+            let mut entry = MagicEntry { mask: mask, magic: 0, shift: 64 - mask.count_ones(), offset: attacks.len() };
+            let table = find_magic_table(&mut entry, &occupancies, &references, &mut random);
+            attacks.extend(table);
+            entries[square as usize] = entry;
+        }
+        return Self { entries: entries, attacks: attacks };
+    }
+
+    fn get_attacks(&self, square: u8, occupancy: u64) -> u64 {
+        return self.attacks[self.entries[square as usize].get_index(occupancy)];
+    }
+}
+
+
+fn walk_direction(square: u8, dir: SlideDirection) -> impl Iterator<Item = (i8, i8)> {
+    let [col, row] = get_col_and_row_from_square(square);
+    let (col_shift, row_shift) = dir.get_direction();
+    return (1i8..8i8)
+        .map(move |step| (col as i8 + col_shift * step, row as i8 + row_shift * step))
+        .take_while(|(c, r)| (0..8).contains(c) && (0..8).contains(r));
+}
+
+
+fn generate_sliding_attacks(square: u8, directions: &[SlideDirection], occupancy: u64) -> u64 {
+    let mut board = 0u64;
+    for dir in directions {
+        for (col, row) in walk_direction(square, *dir) {
+            let bit = get_bit_for_square(get_square_from_col_and_row(col as u8, row as u8));
+            board |= bit;
+            if occupancy & bit != 0 { break }
+        }
+    }
+    return board;
+}
+
+
+fn generate_relevant_occupancy_mask(square: u8, directions: &[SlideDirection]) -> u64 {
+    let mut board = 0u64;
+    for dir in directions {
+        let (col_shift, row_shift) = dir.get_direction();
+        for (col, row) in walk_direction(square, *dir) {
+            if !(0..8).contains(&(col + col_shift)) || !(0..8).contains(&(row + row_shift)) { break }
+            board |= get_bit_for_square(get_square_from_col_and_row(col as u8, row as u8));
+        }
+    }
+    return board;
+}
+
+
+fn get_occupancy_subsets(mask: u64) -> Vec<u64> {
+    let mut subsets = Vec::with_capacity(1 << mask.count_ones());
+    let mut subset = 0u64;
+    loop {
+        subsets.push(subset);
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 { break }
+    }
+    return subsets;
+}
+
+
+#[cfg(not(feature = "pext"))]
+fn find_magic_table(entry: &mut MagicEntry, occupancies: &[u64], references: &[u64], random: &mut MagicRandom) -> Vec<u64> {
+    let size = occupancies.len();
+    let mut table = vec![0u64; size];
+    let mut epochs = vec![0u32; size];
+    let mut attempt = 0u32;
+    loop {
+        let magic = random.next_sparse();
+        if (entry.mask.wrapping_mul(magic) >> 56).count_ones() < 6 { continue }
+        attempt += 1;
+        let collision = occupancies.iter().zip(references.iter()).any(|(occupancy, attacks)| {
+            let index = (occupancy.wrapping_mul(magic) >> entry.shift) as usize;
+            if epochs[index] != attempt {
+                epochs[index] = attempt;
+                table[index] = *attacks;
+                return false;
+            }
+            return table[index] != *attacks;
+        });
+        if !collision {
+            entry.magic = magic;
+            return table;
+        }
+    }
+}
+
+
+#[cfg(feature = "pext")]
+fn find_magic_table(entry: &mut MagicEntry, occupancies: &[u64], references: &[u64], _random: &mut MagicRandom) -> Vec<u64> {
+    let mut table = vec![0u64; occupancies.len()];
+    for (occupancy, attacks) in occupancies.iter().zip(references.iter()) {
+        table[entry.get_index(*occupancy) - entry.offset] = *attacks;
+    }
+    return table;
+}
+
+
+pub fn get_bishop_attacks(square: u8, occupancy: u64) -> u64 {
+    return BISHOP_MAGICS.get_attacks(square, occupancy);
+}
+
+
+pub fn get_rook_attacks(square: u8, occupancy: u64) -> u64 {
+    return ROOK_MAGICS.get_attacks(square, occupancy);
+}
+
+
+pub fn get_queen_attacks(square: u8, occupancy: u64) -> u64 {
+    return get_bishop_attacks(square, occupancy) | get_rook_attacks(square, occupancy);
+}
@@ -0,0 +1,72 @@
+use crate::rules::Color;
+use crate::rules::pieces::PieceType;
+use crate::util::errors::InputError;
+
+
+static NON_PAWN_TYPES: [PieceType; 4] = [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen];
+
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialSignature {
+    counts: [[u8; 6]; 2],
+}
+
+impl MaterialSignature {
+    pub fn from_counts(counts: [[u8; 6]; 2]) -> Self {
+        return Self { counts: counts };
+    }
+
+    pub fn from_notation(notation: &str) -> Result<Self, InputError> {
+        let sides: Vec<&str> = notation.split('v').collect();
+        if sides.len() != 2 {
+            return Err(InputError::new(&format!("'{}' is not a valid material signature", notation)));
+        }
+        let mut counts = [[0u8; 6]; 2];
+        for (side, pieces) in sides.iter().enumerate() {
+            for c in pieces.chars() {
+                let piece_type = match c {
+                    'P' => PieceType::Pawn,
+                    'N' => PieceType::Knight,
+                    'B' => PieceType::Bishop,
+                    'R' => PieceType::Rook,
+                    'Q' => PieceType::Queen,
+                    'K' => PieceType::King,
+                    _ => return Err(InputError::new(&format!("'{}' is not a valid material signature", notation))),
+                };
+                counts[side][piece_type as usize] += 1;
+            }
+            if counts[side][PieceType::King as usize] != 1 {
+                return Err(InputError::new(&format!("'{}' is not a valid material signature", notation)));
+            }
+        }
+        return Ok(Self::from_counts(counts));
+    }
+
+    pub fn get_count(&self, color: Color, piece_type: PieceType) -> u8 {
+        return self.counts[color as usize][piece_type as usize];
+    }
+
+    pub fn get_piece_count(&self) -> u8 {
+        return self.counts.iter().flatten().sum();
+    }
+
+    pub fn get_non_pawn_material(&self, color: Color) -> i16 {
+        return NON_PAWN_TYPES.iter().fold(0i16, |total, t| total + self.get_count(color, *t) as i16 * t.value() as i16 * 100);
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        return self.get_count(Color::White, PieceType::Pawn) + self.get_count(Color::Black, PieceType::Pawn) > 0;
+    }
+
+    pub fn is_lone_king(&self, color: Color) -> bool {
+        return self.counts[color as usize][..5].iter().all(|c| *c == 0);
+    }
+
+    pub fn swap(&self) -> Self {
+        return Self::from_counts([self.counts[1], self.counts[0]]);
+    }
+
+    pub fn get_key(&self) -> u64 {
+        return self.counts.iter().flatten().enumerate().fold(0u64, |key, (index, count)| key | (*count as u64) << (index * 4));
+    }
+}
@@ -1,4 +1,8 @@
 pub mod bitboards;
+pub mod magics;
+pub mod material;
+pub mod move_list;
+pub mod packed_move;
 pub mod positions;
 pub mod squares;
 pub mod state;
@@ -8,51 +12,41 @@ use fxhash::FxHashMap;
 use crate::rules::board::positions::CastlingSquares;
 use crate::util::fen::{FenBoardState, Castling, STARTING_POSITION};
 use crate::util::zobrist::ZobristId;
+use crate::util::errors::InputError;
 
-use self::bitboards::{BitboardSquares, get_bit_for_square, get_moves_for_piece};
+use self::bitboards::{DARK_SQUARES, BitboardSquares, get_bit_for_square, get_diagonal_bitboard, get_moves_for_piece, get_orthagonal_bitboard};
+use self::move_list::{GenerationStage, MoveList};
 use self::positions::{BoardPosition, Pin, AttacksAndPins, Attack, PieceLocation};
 use self::squares::{BoardSquare, get_col_and_row_from_square, get_square_from_col_and_row, is_fourth_rank, is_eighth_rank, is_second_rank};
-use self::state::{CastleRight, BoardState, BoardCastles, ReversibleBoardChange, ApplyableBoardChange};
+use self::state::{CastleRight, BoardState, BoardCastles, CastleFiles, ReversibleBoardChange, ApplyableBoardChange};
 
 use super::Color;
 use super::pieces::{Piece, PieceType};
 use super::pieces::movement::{BasicMove, Castle, CastleType, EnPassant, Move, Promotion, TwoSquarePawnMove};
 
 
-lazy_static! {
-    static ref CASTLING_MOVES: FxHashMap<Color, FxHashMap<CastleType, CastlingSquares>> = FxHashMap::from_iter([
-        (Color::White, FxHashMap::from_iter([
-            (CastleType::Kingside, CastlingSquares::from_color_and_type(Color::White, CastleType::Kingside)),
-            (CastleType::Queenside, CastlingSquares::from_color_and_type(Color::White, CastleType::Queenside))
-        ].into_iter())),
-        (Color::Black, FxHashMap::from_iter([
-            (CastleType::Kingside, CastlingSquares::from_color_and_type(Color::Black, CastleType::Kingside)),
-            (CastleType::Queenside, CastlingSquares::from_color_and_type(Color::Black, CastleType::Queenside))
-        ].into_iter())),
-    ].into_iter());
+impl CastleRight {
+    fn is_lost_by_move(&self, mov: &Move, files: &CastleFiles) -> bool {
+        let row = match self.color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let squares = get_bit_for_square(get_square_from_col_and_row(files.get_king_file(self.color), row)) |
+            get_bit_for_square(get_square_from_col_and_row(files.get_file(self), row));
+        return mov.iter_piece_movements().any(|m| {
+            (get_bit_for_square(m.start_square) | get_bit_for_square(m.end_square)) & squares != 0
+        });
+    }
 }
 
 
-impl CastleRight {
-    fn associated_rights_by_square(square: u8) -> Vec<Self> {
-        let mut rights = Vec::new();
-        if square == BoardSquare::A1.value() || square == BoardSquare::E1.value() {
-            rights.push(Self { color: Color::White, side: CastleType::Queenside });
-        }
-        if square == BoardSquare::H1.value() || square == BoardSquare::E1.value() {
-            rights.push(Self { color: Color::White, side: CastleType::Kingside });
-        }
-        if square == BoardSquare::A8.value() || square == BoardSquare::E8.value() {
-            rights.push(Self { color: Color::Black, side: CastleType::Queenside });
-        }
-        if square == BoardSquare::H8.value() || square == BoardSquare::E8.value() {
-            rights.push(Self { color: Color::Black, side: CastleType::Kingside });
-        }
-        return rights;
+fn get_promotion_rank(color: Color) -> u64 {
+    return match color {
+        Color::White => 0xFF00000000000000,
+        Color::Black => 0x00000000000000FF,
     }
 }
 
-
 fn get_capture_square_for_ep_target(ep_target: u8) -> u8 {
     return if ep_target > 31 { ep_target - 8 } else { ep_target + 8 };
 }
@@ -92,10 +86,19 @@ pub fn fen_board_from_position(position: &BoardPosition) -> [[Option<Piece>; 8];
 }
 
 
+fn get_castling_king_file(position: &BoardPosition, castling: &Castling, color: Color) -> u8 {
+    let has_rights = Castling::RIGHTS.iter().any(|r| r.color == color && castling.get_file(r).is_some());
+    return match has_rights {
+        true => get_col_and_row_from_square(position.find_king(color))[0],
+        false => 4,
+    }
+}
+
 fn board_from_fen_state(state: FenBoardState) -> Board {
     let piece_map = piece_map_from_fen_board(state.board);
+    let position = BoardPosition::from_piece_map(piece_map);
     return Board {
-        position: BoardPosition::from_piece_map(piece_map.clone()),
+        position: position,
         state: BoardState {
             to_move: state.to_move,
             en_passant_target: match state.en_passant {
@@ -105,11 +108,19 @@ fn board_from_fen_state(state: FenBoardState) -> Board {
             halfmove_clock: state.halfmove_timer,
             move_number: state.move_number,
             castle_rights: BoardCastles {
-                white_kingside: state.castling.white_kingside,
-                white_queenside: state.castling.white_queenside,
-                black_kingside: state.castling.black_kingside,
-                black_queenside: state.castling.black_queenside,
-            }
+                white_kingside: state.castling.white_kingside.is_some(),
+                white_queenside: state.castling.white_queenside.is_some(),
+                black_kingside: state.castling.black_kingside.is_some(),
+                black_queenside: state.castling.black_queenside.is_some(),
+            },
+            castle_files: CastleFiles {
+                white_king: get_castling_king_file(&position, &state.castling, Color::White),
+                black_king: get_castling_king_file(&position, &state.castling, Color::Black),
+                white_kingside: state.castling.white_kingside.unwrap_or(7),
+                white_queenside: state.castling.white_queenside.unwrap_or(0),
+                black_kingside: state.castling.black_kingside.unwrap_or(7),
+                black_queenside: state.castling.black_queenside.unwrap_or(0),
+            },
         },
         zobrist: ZobristId::from_fen(&state),
     }
@@ -120,10 +131,10 @@ fn fen_state_from_board(board: &Board) -> FenBoardState {
         board: fen_board_from_position(&board.position),
         to_move: board.state.to_move,
         castling: Castling {
-            white_kingside: board.state.castle_rights.white_kingside,
-            white_queenside: board.state.castle_rights.white_queenside,
-            black_kingside: board.state.castle_rights.black_kingside,
-            black_queenside: board.state.castle_rights.black_queenside,
+            white_kingside: board.state.castle_rights.white_kingside.then_some(board.state.castle_files.white_kingside),
+            white_queenside: board.state.castle_rights.white_queenside.then_some(board.state.castle_files.white_queenside),
+            black_kingside: board.state.castle_rights.black_kingside.then_some(board.state.castle_files.black_kingside),
+            black_queenside: board.state.castle_rights.black_queenside.then_some(board.state.castle_files.black_queenside),
         },
         en_passant: match board.state.en_passant_target {
             0 => None,
@@ -135,18 +146,18 @@ fn fen_state_from_board(board: &Board) -> FenBoardState {
 }
 
 
-fn get_castle_details(color: Color, castle_type: CastleType) -> &'static CastlingSquares {
-    return CASTLING_MOVES.get(&color).unwrap().get(&castle_type).unwrap()
+fn get_castle_details(right: &CastleRight, files: &CastleFiles) -> CastlingSquares {
+    return CastlingSquares::from_files(right.color, right.side, files.get_king_file(right.color), files.get_file(right));
 }
 
 
-fn get_legal_king_moves(position: &BoardPosition, color: Color) -> Vec<Move> {
+fn get_legal_king_moves(position: &BoardPosition, color: Color, moves: &mut MoveList) {
     let king_square = position.find_king(color);
-    return get_moves_for_piece_location(position, &PieceLocation {square: king_square, piece: Piece { color: color, piece_type: PieceType::King }}, 0u64);
+    get_moves_for_piece_location(position, &PieceLocation {square: king_square, piece: Piece { color: color, piece_type: PieceType::King }}, 0u64, moves);
 }
 
-fn get_legal_moves_from_check(position: &BoardPosition, color: Color, check: &Attack, pinned: u64, ep_target: u64) -> Vec<Move> {
-    let mut moves = get_legal_king_moves(position, color);
+fn get_legal_moves_from_check(position: &BoardPosition, color: Color, check: &Attack, pinned: u64, ep_target: u64, moves: &mut MoveList) {
+    get_legal_king_moves(position, color, moves);
     for loc in position.get_all_masked_piece_squares_for_color(color, !pinned) {
         if loc.piece.piece_type == PieceType::King { continue };
         let move_board = get_moves_for_piece(
@@ -157,20 +168,19 @@ fn get_legal_moves_from_check(position: &BoardPosition, color: Color, check: &At
             ep_target);
         let legal_moves = move_board & (check.attack_path | get_bit_for_square(check.attacking_square));
         for end_square in BitboardSquares::from_board(legal_moves) {
-            moves.extend(build_move(position, loc.square, end_square, &loc.piece, ep_target));
+            build_move(position, loc.square, end_square, &loc.piece, ep_target, moves);
         }
         if loc.piece.piece_type == PieceType::Pawn && move_board & ep_target != 0 {
             let end = ep_target.trailing_zeros() as u8;
             let capture_square = get_capture_square_for_ep_target(end);
             if capture_square == check.attacking_square && move_board & get_bit_for_square(end) != 0 {
-                moves.extend(build_move(position, loc.square, end, &loc.piece, ep_target))
+                build_move(position, loc.square, end, &loc.piece, ep_target, moves);
             }
         }
     }
-    return moves;
 }
 
-fn get_legal_moves_for_pinned_piece(position: &BoardPosition, pin: &Pin, ep_target: u64) -> Vec<Move> {
+fn get_legal_moves_for_pinned_piece(position: &BoardPosition, pin: &Pin, ep_target: u64, moves: &mut MoveList) {
     let pinned_piece = position.piece_at(&pin.pinned_square).unwrap();
     let move_board = get_moves_for_piece(
         pin.pinned_square,
@@ -179,63 +189,58 @@ fn get_legal_moves_for_pinned_piece(position: &BoardPosition, pin: &Pin, ep_targ
         position.get_all_piece_locations(pinned_piece.color.swap()),
         ep_target);
     let legal_moves = move_board & (pin.pin_path | get_bit_for_square(pin.pinning_square));
-    BitboardSquares::from_board(legal_moves).fold(Vec::new(), |mut moves, s| {
-        moves.extend(build_move(position, pin.pinned_square, s, &pinned_piece, ep_target));
-        moves
-    })
+    for end_square in BitboardSquares::from_board(legal_moves) {
+        build_move(position, pin.pinned_square, end_square, &pinned_piece, ep_target, moves);
+    }
 }
 
-fn get_moves_for_piece_location(position: &BoardPosition, loc: &PieceLocation, ep_target: u64) -> Vec<Move> {
+fn get_moves_for_piece_location(position: &BoardPosition, loc: &PieceLocation, ep_target: u64, moves: &mut MoveList) {
     let move_board = get_moves_for_piece(
         loc.square,
         loc.piece,
         position.get_all_piece_locations(loc.piece.color),
         position.get_all_piece_locations(loc.piece.color.swap()),
         ep_target);
-    BitboardSquares::from_board(move_board).fold(Vec::new(), |mut moves, s| {
-        moves.extend(build_move(position, loc.square, s, &loc.piece, ep_target));
-        moves
-    })
+    for end_square in BitboardSquares::from_board(move_board) {
+        build_move(position, loc.square, end_square, &loc.piece, ep_target, moves);
+    }
 }
 
-fn build_move(position: &BoardPosition, start: u8, end: u8, piece: &Piece, ep_target: u64) -> Vec<Move> {
+fn build_move(position: &BoardPosition, start: u8, end: u8, piece: &Piece, ep_target: u64, moves: &mut MoveList) {
     let capture = position.piece_at(&end).map(|p| p);
     let basic_move = BasicMove { piece: *piece, start: start, end: end, capture: capture };
     if piece.piece_type == PieceType::King && position.is_check(end, piece.color) {
-        return Vec::new();
+        return;
     }
     if piece.piece_type == PieceType::Pawn && end == ep_target.trailing_zeros() as u8 {
         let capture_square = get_capture_square_for_ep_target(end);
         if position.en_passant_is_illegal(piece.color, start, end, capture_square) {
-            return Vec::new();
+            return;
         } else {
             let ep_capture = position.piece_at(&capture_square).map(|p| p);
             let mut ep_basic = basic_move.clone();
             ep_basic.capture = ep_capture;
             match ep_capture {
-                Some(_) => return Vec::from([ Move::EnPassant(EnPassant::from_basic_move(&ep_basic, capture_square)) ]),
+                Some(_) => moves.push(Move::EnPassant(EnPassant::from_basic_move(&ep_basic, capture_square))),
                 None => panic!("Invalid Move: Cannot create an en passant move without a capture!"),
             }
         }
     } else if piece.piece_type == PieceType::Pawn && is_eighth_rank(end, piece.color) {
-        return Promotion::get_all_from_basic_move(&basic_move).into_iter().map(|p| { Move::Promotion(p) }).collect()
+        for promotion in Promotion::get_all_from_basic_move(&basic_move) {
+            moves.push(Move::Promotion(promotion));
+        }
     } else if piece.piece_type == PieceType::Pawn && is_second_rank(start, piece.color) && is_fourth_rank(end, piece.color) {
         if capture.is_some() { panic!("Invalid Move: Cannot create a two square pawn move with a captured piece!") }
         let en_passant_target = get_en_passant_target_for_two_square_first_move(piece.color, end);
-        return Vec::from([ Move::TwoSquarePawnMove(TwoSquarePawnMove::from_basic_move(&basic_move, en_passant_target)) ]);
+        moves.push(Move::TwoSquarePawnMove(TwoSquarePawnMove::from_basic_move(&basic_move, en_passant_target)));
     } else {
-        return Vec::from([ Move::BasicMove(basic_move) ]);
+        moves.push(Move::BasicMove(basic_move));
     }
 }
 
 
 fn predict_lost_castle_rights(mov: &Move, state: &BoardState) -> Vec<CastleRight> {
-    let mut rights = Vec::new();
-    for movement in mov.get_piece_movements() {
-        rights.extend(CastleRight::associated_rights_by_square(movement.start_square));
-        rights.extend(CastleRight::associated_rights_by_square(movement.end_square));
-    }
-    return rights.into_iter().filter(|r| state.can_castle(r)).collect();
+    return Castling::RIGHTS.into_iter().filter(|r| state.can_castle(r) && r.is_lost_by_move(mov, &state.castle_files)).collect();
 }
 
 
@@ -275,7 +280,7 @@ fn prepare_change(mov: Move, position: &BoardPosition, state: &BoardState, board
     updated_state.increment_halfmove_clock();
     if let Some(_capture) = mov.get_capture() {
         updated_state.reset_halfmove_clock();
-    } else if mov.get_piece_movements()[0].get_piece().piece_type == PieceType::Pawn {
+    } else if mov.get_moved_piece().is_some_and(|p| p.piece_type == PieceType::Pawn) {
         updated_state.reset_halfmove_clock();
     }
     if updated_state.get_move_color() == Color::Black { updated_state.increment_move_number(); }
@@ -283,18 +288,21 @@ fn prepare_change(mov: Move, position: &BoardPosition, state: &BoardState, board
 
     let updated_zobrist_id = predict_zobrist_update(&board_id, &mov, &revoked_castle_rights, &updated_state);
 
-    let responses: Vec<ApplyableBoardChange> = (if checks_and_pins.attacks.len() > 1 {
-        get_legal_king_moves(&updated_position, updated_state.get_move_color())
+    let mut response_moves = MoveList::new();
+    if checks_and_pins.attacks.len() > 1 {
+        get_legal_king_moves(&updated_position, updated_state.get_move_color(), &mut response_moves);
     } else if !checks_and_pins.attacks.is_empty() {
         get_legal_moves_from_check(
             &updated_position,
             updated_state.get_move_color(),
             &checks_and_pins.attacks[0],
             checks_and_pins.pinned,
-            updated_state.en_passant_target)
-    } else {
-        Vec::new()
-    }).into_iter().map(|m| { prepare_change(m, &updated_position, &updated_state, updated_zobrist_id) }).collect();
+            updated_state.en_passant_target,
+            &mut response_moves);
+    }
+    let responses: Vec<ApplyableBoardChange> = response_moves.into_iter().map(|m| {
+        prepare_change(m, &updated_position, &updated_state, updated_zobrist_id)
+    }).collect();
 
     return ApplyableBoardChange {
         new_move: mov,
@@ -314,7 +322,9 @@ fn get_pinned_moves_closure(pin: &Pin, position: &BoardPosition, state: &BoardSt
     let owned_position = *position;
     let owned_state = *state;
     let closure = move || {
-        get_legal_moves_for_pinned_piece(&owned_position, &owned_pin, owned_state.en_passant_target).into_iter().map(|m| {
+        let mut moves = MoveList::new();
+        get_legal_moves_for_pinned_piece(&owned_position, &owned_pin, owned_state.en_passant_target, &mut moves);
+        moves.into_iter().map(|m| {
             prepare_change(m, &owned_position, &owned_state, id)
         }).collect()
     };
@@ -327,7 +337,9 @@ fn get_piece_moves_closure(piece_square: &PieceLocation, position: &BoardPositio
     let owned_position = *position;
     let owned_state = *state;
     let closure = move || {
-        get_moves_for_piece_location(&owned_position, &owned_piece_square, owned_state.en_passant_target).into_iter().map(|m| {
+        let mut moves = MoveList::new();
+        get_moves_for_piece_location(&owned_position, &owned_piece_square, owned_state.en_passant_target, &mut moves);
+        moves.into_iter().map(|m| {
             prepare_change(m, &owned_position, &owned_state, id)
         }).collect()
     };
@@ -344,12 +356,16 @@ pub struct Board {
 
 impl Board {
     pub fn from_starting_position() -> Board {
-        return Self::from_fen(STARTING_POSITION);
+        return Self::from_fen(STARTING_POSITION).unwrap();
     }
 
-    pub fn from_fen(fen: &str) -> Board {
-        let parsed_fen = FenBoardState::from_fen(fen);
-        return board_from_fen_state(parsed_fen);
+    pub fn from_fen(fen: &str) -> Result<Board, InputError> {
+        let board = board_from_fen_state(FenBoardState::from_fen(fen)?);
+        let waiting_color = board.state.get_move_color().swap();
+        if board.position.is_check(board.position.find_king(waiting_color), waiting_color) {
+            return Err(InputError::new(&format!("FEN position has the {} king in check while it is not {} to move", waiting_color.value(), waiting_color.value())));
+        }
+        return Ok(board);
     }
 
     pub fn to_fen(&self) -> String {
@@ -363,23 +379,96 @@ impl Board {
             self.state.castle_rights == other.state.castle_rights;
     }
 
+    pub fn is_chess960(&self) -> bool {
+        return self.state.castle_files != CastleFiles::default();
+    }
+
     pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        self.generate_legal_moves(&mut moves);
+        return moves.to_vec();
+    }
+
+    pub fn generate_legal_moves(&self, moves: &mut MoveList) {
+        self.generate_staged_moves(GenerationStage::All, moves);
+    }
+
+    pub fn generate_captures(&self, moves: &mut MoveList) {
+        self.generate_staged_moves(GenerationStage::Captures, moves);
+    }
+
+    pub fn generate_quiet_moves(&self, moves: &mut MoveList) {
+        self.generate_staged_moves(GenerationStage::Quiets, moves);
+    }
+
+    pub fn generate_check_evasions(&self, moves: &mut MoveList) {
+        moves.clear();
+        if self.in_check() {
+            self.generate_staged_moves(GenerationStage::Evasions, moves);
+        }
+    }
+
+    fn generate_staged_moves(&self, stage: GenerationStage, moves: &mut MoveList) {
+        moves.clear();
         let king_square = self.find_king(self.state.to_move);
-        let mut moves: Vec<Move> = Vec::new();
         let checks_and_pins = self.get_checks_and_pins(&king_square, self.state.to_move);
         let checks = checks_and_pins.attacks;
         let pins = checks_and_pins.pins;
         let pinned_squares = checks_and_pins.pinned;
-        if checks.len() > 1 { return self.get_legal_king_moves() }
-        if !checks.is_empty() { return self.get_legal_moves_from_check(checks.first().unwrap(), pinned_squares) }
+        if checks.len() > 1 { return self.get_legal_king_moves(stage, moves) }
+        if !checks.is_empty() { return self.get_legal_moves_from_check(checks.first().unwrap(), pinned_squares, stage, moves) }
         for pin in pins {
-            moves.extend(self.get_legal_moves_for_pinned_piece(&pin))
+            self.get_legal_moves_for_pinned_piece(&pin, stage, moves);
         }
-        self.position.get_all_masked_piece_squares_for_color(self.state.to_move, !pinned_squares).for_each(|loc| {
-            moves.extend(self.get_moves_for_piece_location(loc))
-        });
-        moves.extend(self.get_castle_moves(self.state.to_move));
-        return moves;
+        for loc in self.position.get_all_masked_piece_squares_for_color(self.state.to_move, !pinned_squares) {
+            self.get_moves_for_piece_location(loc, stage, moves);
+        }
+        if stage != GenerationStage::Captures {
+            self.get_castle_moves(self.state.to_move, moves);
+        }
+    }
+
+    pub fn generate_pseudo_legal_moves(&self, moves: &mut MoveList) {
+        moves.clear();
+        for loc in self.position.get_all_masked_piece_squares_for_color(self.state.to_move, u64::MAX) {
+            let move_board = get_moves_for_piece(
+                loc.square,
+                loc.piece,
+                self.position.get_all_piece_locations(loc.piece.color),
+                self.position.get_all_piece_locations(loc.piece.color.swap()),
+                self.state.en_passant_target);
+            for end_square in BitboardSquares::from_board(move_board) {
+                self.build_pseudo_legal_move(loc.square, end_square, loc.piece, moves);
+            }
+        }
+        self.get_castle_moves(self.state.to_move, moves);
+    }
+
+    pub fn is_legal(&self, mov: &Move) -> bool {
+        let color = self.state.get_move_color();
+        let movement = match mov {
+            Move::NullMove(_m) => return false,
+            Move::Castle(c) => return !self.in_check() && self.get_castle(color, c.side) == Some(*mov),
+            _ => mov.get_primary_movement().unwrap(),
+        };
+        if movement.piece_type == PieceType::King {
+            return !self.position.is_check(movement.end_square, color);
+        }
+        let king_square = self.position.find_king(color);
+        let king_lines = get_diagonal_bitboard(king_square) | get_orthagonal_bitboard(king_square);
+        if !matches!(mov, Move::EnPassant(_)) && king_lines & get_bit_for_square(movement.start_square) == 0 && !self.in_check() {
+            return true;
+        }
+        let mut position = self.position;
+        position.apply_move(mov);
+        return !position.is_check(king_square, color);
+    }
+
+    pub fn gives_check(&self, mov: &Move) -> bool {
+        let color = self.state.get_move_color().swap();
+        let mut position = self.position;
+        position.apply_move(mov);
+        return position.is_check(position.find_king(color), color);
     }
 
     pub fn make_move(&mut self, new_move: &Move) -> ReversibleBoardChange {
@@ -389,9 +478,7 @@ impl Board {
             prior_state: self.state,
         };
         self.zobrist.update_move(new_move);
-        for castle in self.revoke_castle_rights(new_move) {
-            self.zobrist.update_castle_right(castle);
-        }
+        self.revoke_castle_rights(new_move);
         match self.state.clear_en_passant_target() {
             Some(square) => self.zobrist.update_en_passant(square),
             None => (),
@@ -399,7 +486,7 @@ impl Board {
         self.state.increment_halfmove_clock();
         if let Some(_capture) = new_move.get_capture() {
             self.state.reset_halfmove_clock();
-        } else if new_move.get_piece_movements()[0].get_piece().piece_type == PieceType::Pawn {
+        } else if new_move.get_moved_piece().is_some_and(|p| p.piece_type == PieceType::Pawn) {
             self.state.reset_halfmove_clock();
         }
         self.position.apply_move(new_move);
@@ -424,59 +511,60 @@ impl Board {
         self.state = change.prior_state;
     }
 
-    fn revoke_castle_rights(&mut self, new_move: &Move) -> Vec<CastleRight> {
-        let mut rights = Vec::new();
-        for movement in new_move.get_piece_movements() {
-            rights.extend(CastleRight::associated_rights_by_square(movement.start_square));
-            rights.extend(CastleRight::associated_rights_by_square(movement.end_square));
-        }
-        return rights.into_iter().filter_map(|r| {
-            match self.state.can_castle(&r) {
-                true => {
-                    self.state.revoke_castle_right(&r);
-                    Some(r)
-                },
-                false => None,
+    fn revoke_castle_rights(&mut self, new_move: &Move) {
+        for right in Castling::RIGHTS {
+            if self.state.can_castle(&right) && right.is_lost_by_move(new_move, &self.state.castle_files) {
+                self.state.revoke_castle_right(&right);
+                self.zobrist.update_castle_right(right);
             }
-        }).collect();
+        }
+    }
+
+    fn get_stage_mask(&self, piece: Piece, stage: GenerationStage) -> u64 {
+        let targets = match piece.piece_type {
+            PieceType::Pawn => self.position.get_all_piece_locations(piece.color.swap()) | self.state.en_passant_target | get_promotion_rank(piece.color),
+            _ => self.position.get_all_piece_locations(piece.color.swap()),
+        };
+        return match stage {
+            GenerationStage::All | GenerationStage::Evasions => u64::MAX,
+            GenerationStage::Captures => targets,
+            GenerationStage::Quiets => !targets,
+        }
     }
 
-    fn get_moves_for_piece(&self, square: u8) -> Vec<Move> {
+    fn get_moves_for_piece(&self, square: u8, stage: GenerationStage, moves: &mut MoveList) {
         let piece = self.position.piece_at(&square).unwrap();
-        return self.get_moves_for_piece_location( PieceLocation { square: square, piece: piece } )
+        self.get_moves_for_piece_location(PieceLocation { square: square, piece: piece }, stage, moves);
     }
 
-    fn get_moves_for_piece_location(&self, loc: PieceLocation) -> Vec<Move> {
-        let mut moves: Vec<Move> = Vec::new();
+    fn get_moves_for_piece_location(&self, loc: PieceLocation, stage: GenerationStage, moves: &mut MoveList) {
         let move_board = get_moves_for_piece(
             loc.square,
             loc.piece,
             self.position.get_all_piece_locations(loc.piece.color),
             self.position.get_all_piece_locations(loc.piece.color.swap()),
-            self.state.en_passant_target);
-        BitboardSquares::from_board(move_board).for_each(|end_square| {
-            moves.extend(self.build_move(loc.square, end_square, loc.piece))
-        });
-        return moves;
+            self.state.en_passant_target) & self.get_stage_mask(loc.piece, stage);
+        for end_square in BitboardSquares::from_board(move_board) {
+            self.build_move(loc.square, end_square, loc.piece, moves);
+        }
     }
 
-    fn get_legal_moves_for_pinned_piece(&self, pin: &Pin) -> Vec<Move> {
+    fn get_legal_moves_for_pinned_piece(&self, pin: &Pin, stage: GenerationStage, moves: &mut MoveList) {
         let pinned_piece = self.position.piece_at(&pin.pinned_square).unwrap();
         let move_board = get_moves_for_piece(
             pin.pinned_square,
             pinned_piece,
             self.position.get_all_piece_locations(pinned_piece.color),
             self.position.get_all_piece_locations(pinned_piece.color.swap()),
-            self.state.en_passant_target);
+            self.state.en_passant_target) & self.get_stage_mask(pinned_piece, stage);
         let legal_moves = move_board & (pin.pin_path | get_bit_for_square(pin.pinning_square));
-        BitboardSquares::from_board(legal_moves).fold(Vec::new(), |mut moves, s| {
-            moves.extend(self.build_move(pin.pinned_square, s, pinned_piece));
-            moves
-        })
+        for end_square in BitboardSquares::from_board(legal_moves) {
+            self.build_move(pin.pinned_square, end_square, pinned_piece, moves);
+        }
     }
 
-    fn get_legal_moves_from_check(&self, check: &Attack, pinned_squares: u64) -> Vec<Move> {
-        let mut moves = self.get_legal_king_moves();
+    fn get_legal_moves_from_check(&self, check: &Attack, pinned_squares: u64, stage: GenerationStage, moves: &mut MoveList) {
+        self.get_legal_king_moves(stage, moves);
         let pieces = self.get_pieces_to_move() ^ pinned_squares;
         for start_square in BitboardSquares::from_board(pieces) {
             let piece = self.position.piece_at(&start_square).unwrap();
@@ -486,49 +574,54 @@ impl Board {
                 piece,
                 self.position.get_all_piece_locations(piece.color),
                 self.position.get_all_piece_locations(piece.color.swap()),
-                self.state.en_passant_target);
+                self.state.en_passant_target) & self.get_stage_mask(piece, stage);
             let legal_moves = move_board & (check.attack_path | get_bit_for_square(check.attacking_square));
             for end_square in BitboardSquares::from_board(legal_moves) {
-                moves.extend(self.build_move(start_square, end_square, piece));
+                self.build_move(start_square, end_square, piece, moves);
             }
             if piece.piece_type == PieceType::Pawn && move_board & self.state.en_passant_target != 0 {
                 let end = self.state.get_en_passant_target().unwrap();
                 let capture_square = get_capture_square_for_ep_target(end);
                 if capture_square == check.attacking_square && move_board & get_bit_for_square(end) != 0 {
-                    moves.extend(self.build_move(start_square, end, piece))
+                    self.build_move(start_square, end, piece, moves);
                 }
             }
         }
-        return moves;
     }
 
-    fn get_legal_king_moves(&self) -> Vec<Move> {
-        return self.get_moves_for_piece(self.position.find_king(self.state.get_move_color()));
+    fn get_legal_king_moves(&self, stage: GenerationStage, moves: &mut MoveList) {
+        self.get_moves_for_piece(self.position.find_king(self.state.get_move_color()), stage, moves);
     }
 
-    fn get_castle_moves(&self, color: Color) -> Vec<Move> {
-        [self.get_castle(color, CastleType::Kingside), self.get_castle(color, CastleType::Queenside)].into_iter().filter_map(|opt| {
-            match opt { Some(m) => Some(m), None => None }
-        }).collect()
+    fn get_castle_moves(&self, color: Color, moves: &mut MoveList) {
+        for side in [CastleType::Kingside, CastleType::Queenside] {
+            if let Some(castle) = self.get_castle(color, side) {
+                moves.push(castle);
+            }
+        }
     }
 
     fn get_castle(&self, color: Color, side: CastleType) -> Option<Move> {
-        if !self.state.can_castle(&CastleRight{ color: color, side: side }) { return None };
-        let detail = get_castle_details(color, side);
+        let right = CastleRight{ color: color, side: side };
+        if !self.state.can_castle(&right) { return None };
+        let detail = get_castle_details(&right, &self.state.castle_files);
         let all_pieces = self.position.get_all_piece_locations(Color::White) | self.position.get_all_piece_locations(Color::Black);
         if detail.transit_squares & all_pieces != 0 { return None };
         for square in BitboardSquares::from_board(detail.king_transit_squares) {
             if self.position.is_check(square, color) { return None }
         }
-        if self.position.is_check(detail.king_end, color) { return None };
-        return Some(Move::Castle(Castle {
+        let castle = Move::Castle(Castle {
             color: color,
             side: side,
             king_start: detail.king_start,
             king_end: detail.king_end,
             rook_start: detail.rook_start,
             rook_end: detail.rook_end,
-        }))
+        });
+        let mut castled_position = self.position;
+        castled_position.apply_move(&castle);
+        if castled_position.is_check(detail.king_end, color) { return None };
+        return Some(castle);
     }
 
     fn find_king(&self, color: Color) -> u8 {
@@ -549,37 +642,74 @@ impl Board {
         return self.position.is_check(self.position.find_king(self.state.get_move_color()), self.state.get_move_color())
     }
 
+    pub fn is_insufficient_material(&self) -> bool {
+        return Color::iter().all(|c| self.has_insufficient_material(c));
+    }
+
+    pub fn has_insufficient_material(&self, color: Color) -> bool {
+        let signature = self.position.get_material_signature();
+        let opponent = color.swap();
+        let blocked = [PieceType::Pawn, PieceType::Rook, PieceType::Queen].iter().any(|t| signature.get_count(color, *t) > 0);
+        if blocked {
+            return false;
+        }
+        if signature.get_count(color, PieceType::Knight) > 0 {
+            return signature.get_count(color, PieceType::Knight) == 1 && signature.get_count(color, PieceType::Bishop) == 0 &&
+                [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook].iter().all(|t| signature.get_count(opponent, *t) == 0);
+        }
+        if signature.get_count(color, PieceType::Bishop) == 0 {
+            return true;
+        }
+        if signature.get_count(opponent, PieceType::Pawn) > 0 || signature.get_count(opponent, PieceType::Knight) > 0 {
+            return false;
+        }
+        let bishops = self.position.get_piece_locations(Color::White, PieceType::Bishop) | self.position.get_piece_locations(Color::Black, PieceType::Bishop);
+        return bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0;
+    }
+
+    pub fn get_checks(&self) -> AttacksAndPins {
+        let king_color = self.state.get_move_color();
+        return self.get_checks_and_pins(&self.position.find_king(king_color), king_color);
+    }
+
     fn get_checks_and_pins(&self, king_square: &u8, king_color: Color) -> AttacksAndPins {
         return self.position.get_attacks_and_pins(*king_square, king_color);
     }
 
-    fn build_move(&self, start: u8, end: u8, piece: Piece) -> Vec<Move> {
-        let capture = self.position.piece_at(&end).map(|p| p);
-        let basic_move = BasicMove { piece: piece, start: start, end: end, capture: capture };
+    fn build_move(&self, start: u8, end: u8, piece: Piece, moves: &mut MoveList) {
         if piece.piece_type == PieceType::King && self.position.is_check(end, piece.color) {
-            return Vec::new();
+            return;
         }
+        if piece.piece_type == PieceType::Pawn && end == self.state.get_en_passant_target().unwrap_or(255) {
+            if self.position.en_passant_is_illegal(piece.color, start, end, get_capture_square_for_ep_target(end)) {
+                return;
+            }
+        }
+        self.build_pseudo_legal_move(start, end, piece, moves);
+    }
+
+    fn build_pseudo_legal_move(&self, start: u8, end: u8, piece: Piece, moves: &mut MoveList) {
+        let capture = self.position.piece_at(&end).map(|p| p);
+        let basic_move = BasicMove { piece: piece, start: start, end: end, capture: capture };
         if piece.piece_type == PieceType::Pawn && end == self.state.get_en_passant_target().unwrap_or(255) {
             let capture_square = get_capture_square_for_ep_target(end);
-            if self.position.en_passant_is_illegal(piece.color, start, end, capture_square) {
-                return Vec::new();
-            } else {
-                let ep_capture = self.position.piece_at(&capture_square).map(|p| p);
-                let mut ep_basic = basic_move.clone();
-                ep_basic.capture = ep_capture;
-                match ep_capture {
-                    Some(_) => return Vec::from([ Move::EnPassant(EnPassant::from_basic_move(&ep_basic, capture_square)) ]),
-                    None => panic!("Invalid Move: Cannot create an en passant move without a capture!"),
-                }
+            let ep_capture = self.position.piece_at(&capture_square).map(|p| p);
+            let mut ep_basic = basic_move.clone();
+            ep_basic.capture = ep_capture;
+            match ep_capture {
+                Some(_) => moves.push(Move::EnPassant(EnPassant::from_basic_move(&ep_basic, capture_square))),
+                None => panic!("Invalid Move: Cannot create an en passant move without a capture!"),
             }
         } else if piece.piece_type == PieceType::Pawn && is_eighth_rank(end, piece.color) {
-            return Promotion::get_all_from_basic_move(&basic_move).into_iter().map(|p| { Move::Promotion(p) }).collect()
+            for promotion in Promotion::get_all_from_basic_move(&basic_move) {
+                moves.push(Move::Promotion(promotion));
+            }
         } else if piece.piece_type == PieceType::Pawn && is_second_rank(start, piece.color) && is_fourth_rank(end, piece.color) {
             if capture.is_some() { panic!("Invalid Move: Cannot create a two square pawn move with a captured piece!") }
             let en_passant_target = get_en_passant_target_for_two_square_first_move(piece.color, end);
-            return Vec::from([ Move::TwoSquarePawnMove(TwoSquarePawnMove::from_basic_move(&basic_move, en_passant_target)) ]);
+            moves.push(Move::TwoSquarePawnMove(TwoSquarePawnMove::from_basic_move(&basic_move, en_passant_target)));
         } else {
-            return Vec::from([ Move::BasicMove(basic_move) ]);
+            moves.push(Move::BasicMove(basic_move));
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,143 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::rules::pieces::movement::{Move, NullMove};
+
+
+pub const MAX_MOVES: usize = 256;
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GenerationStage {
+    All,
+    Captures,
+    Quiets,
+    Evasions,
+}
+
+
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        return Self {
+            moves: [Move::NullMove(NullMove {}); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mov: Move) {
+        self.moves[self.len] = mov;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn retain<F>(&mut self, mut keep: F) where F: FnMut(&Move) -> bool {
+        let mut kept = 0;
+        for index in 0..self.len {
+            if keep(&self.moves[index]) {
+                self.moves[kept] = self.moves[index];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+
+    pub fn sort(&mut self) {
+        for index in 1..self.len {
+            let mov = self.moves[index];
+            let mut insert_at = index;
+            while insert_at > 0 && self.moves[insert_at - 1] > mov {
+                self.moves[insert_at] = self.moves[insert_at - 1];
+                insert_at -= 1;
+            }
+            self.moves[insert_at] = mov;
+        }
+    }
+
+    pub fn sort_by_score<F>(&mut self, mut get_score: F) where F: FnMut(&Move) -> i32 {
+        let mut scores = [0i32; MAX_MOVES];
+        for (score, mov) in scores.iter_mut().zip(&self.moves[..self.len]) {
+            *score = get_score(mov);
+        }
+        for index in 1..self.len {
+            let (mov, score) = (self.moves[index], scores[index]);
+            let mut insert_at = index;
+            while insert_at > 0 && scores[insert_at - 1] > score {
+                self.moves[insert_at] = self.moves[insert_at - 1];
+                scores[insert_at] = scores[insert_at - 1];
+                insert_at -= 1;
+            }
+            self.moves[insert_at] = mov;
+            scores[insert_at] = score;
+        }
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        return &self.moves[..self.len];
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.moves[..self.len];
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.moves[..self.len].iter();
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = MoveListIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return MoveListIntoIter { list: self, index: 0 };
+    }
+}
+
+
+pub struct MoveListIntoIter {
+    list: MoveList,
+    index: usize,
+}
+
+impl Iterator for MoveListIntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.list.len { return None }
+        self.index += 1;
+        return Some(self.list.moves[self.index - 1]);
+    }
+}
+
+impl DoubleEndedIterator for MoveListIntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.index >= self.list.len { return None }
+        self.list.len -= 1;
+        return Some(self.list.moves[self.list.len]);
+    }
+}
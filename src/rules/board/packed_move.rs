@@ -0,0 +1,159 @@
+use crate::rules::pieces::{Piece, PieceType};
+use crate::rules::pieces::movement::{BasicMove, Castle, CastleType, EnPassant, Move, Promotion, TwoSquarePawnMove};
+
+use super::Board;
+use super::positions::CastlingSquares;
+use super::squares::get_col_and_row_from_square;
+use super::{get_capture_square_for_ep_target, get_en_passant_target_for_two_square_first_move};
+
+
+const QUIET: u16                  = 0;
+const DOUBLE_PAWN_PUSH: u16       = 1;
+const KINGSIDE_CASTLE: u16        = 2;
+const QUEENSIDE_CASTLE: u16       = 3;
+const CAPTURE: u16                = 4;
+const EN_PASSANT: u16             = 5;
+const PROMOTION: u16              = 8;
+const PROMOTION_CAPTURE: u16      = 12;
+
+
+fn get_promotion_index(piece_type: PieceType) -> u16 {
+    return match piece_type {
+        PieceType::Knight => 0,
+        PieceType::Bishop => 1,
+        PieceType::Rook   => 2,
+        PieceType::Queen  => 3,
+        _ => panic!("Invalid Move: Cannot promote to a {}", piece_type.name()),
+    }
+}
+
+fn get_promotion_type(index: u16) -> PieceType {
+    return match index & 3 {
+        0 => PieceType::Knight,
+        1 => PieceType::Bishop,
+        2 => PieceType::Rook,
+        _ => PieceType::Queen,
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PackedMove(u16);
+
+impl PackedMove {
+    pub const NULL: PackedMove = PackedMove(0);
+
+    fn new(start: u8, end: u8, flags: u16) -> Self {
+        return Self(start as u16 | (end as u16) << 6 | flags << 12);
+    }
+
+    pub fn from_move(mov: &Move) -> Self {
+        return match mov {
+            Move::NullMove(_m) => Self::NULL,
+            Move::BasicMove(m) => Self::new(m.start, m.end, if m.capture.is_some() { CAPTURE } else { QUIET }),
+            Move::TwoSquarePawnMove(m) => Self::new(m.basic_move.start, m.basic_move.end, DOUBLE_PAWN_PUSH),
+            Move::EnPassant(m) => Self::new(m.basic_move.start, m.basic_move.end, EN_PASSANT),
+            Move::Castle(m) => Self::new(m.king_start, m.rook_start, match m.side {
+                CastleType::Kingside => KINGSIDE_CASTLE,
+                CastleType::Queenside => QUEENSIDE_CASTLE,
+            }),
+            Move::Promotion(m) => Self::new(m.basic_move.start, m.basic_move.end, get_promotion_index(m.promote_to) | match m.basic_move.capture {
+                Some(_) => PROMOTION_CAPTURE,
+                None => PROMOTION,
+            }),
+        }
+    }
+
+    pub fn from_value(value: u16) -> Self {
+        return Self(value);
+    }
+
+    pub fn from_bytes(bytes: [u8; 2]) -> Self {
+        return Self(u16::from_be_bytes(bytes));
+    }
+
+    pub fn value(&self) -> u16 {
+        return self.0;
+    }
+
+    pub fn to_bytes(&self) -> [u8; 2] {
+        return self.0.to_be_bytes();
+    }
+
+    pub fn is_null(&self) -> bool {
+        return *self == Self::NULL;
+    }
+
+    pub fn get_start(&self) -> u8 {
+        return (self.0 & 0x3F) as u8;
+    }
+
+    pub fn get_end(&self) -> u8 {
+        return ((self.0 >> 6) & 0x3F) as u8;
+    }
+
+    pub fn get_flags(&self) -> u16 {
+        return self.0 >> 12;
+    }
+
+    pub fn is_capture(&self) -> bool {
+        return self.get_flags() & CAPTURE != 0;
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        return self.get_flags() & PROMOTION != 0;
+    }
+
+    pub fn get_promotion(&self) -> Option<PieceType> {
+        return match self.is_promotion() {
+            true => Some(get_promotion_type(self.get_flags())),
+            false => None,
+        }
+    }
+
+    pub fn to_move(&self, board: &Board) -> Option<Move> {
+        if self.is_null() { return None }
+        let color = board.state.get_move_color();
+        let (start, end, flags) = (self.get_start(), self.get_end(), self.get_flags());
+        let piece = board.position.piece_at(&start).filter(|p| p.color == color)?;
+        let target = board.position.piece_at(&end);
+        return match flags {
+            KINGSIDE_CASTLE | QUEENSIDE_CASTLE => {
+                if piece.piece_type != PieceType::King || target != Some(Piece { color: color, piece_type: PieceType::Rook }) { return None }
+                let side = if flags == KINGSIDE_CASTLE { CastleType::Kingside } else { CastleType::Queenside };
+                let squares = CastlingSquares::from_files(color, side, get_col_and_row_from_square(start)[0], get_col_and_row_from_square(end)[0]);
+                Some(Move::Castle(Castle {
+                    color: color,
+                    side: side,
+                    king_start: squares.king_start,
+                    king_end: squares.king_end,
+                    rook_start: squares.rook_start,
+                    rook_end: squares.rook_end,
+                }))
+            },
+            EN_PASSANT => {
+                let capture_square = get_capture_square_for_ep_target(end);
+                let capture = board.position.piece_at(&capture_square).filter(|p| p.color != color);
+                if piece.piece_type != PieceType::Pawn || target.is_some() || capture.is_none() { return None }
+                let basic_move = BasicMove { piece: piece, start: start, end: end, capture: capture };
+                Some(Move::EnPassant(EnPassant::from_basic_move(&basic_move, capture_square)))
+            },
+            DOUBLE_PAWN_PUSH => {
+                if piece.piece_type != PieceType::Pawn || target.is_some() { return None }
+                let basic_move = BasicMove { piece: piece, start: start, end: end, capture: None };
+                Some(Move::TwoSquarePawnMove(TwoSquarePawnMove::from_basic_move(&basic_move, get_en_passant_target_for_two_square_first_move(color, end))))
+            },
+            QUIET | CAPTURE | PROMOTION..=15 => {
+                let capture = target.filter(|p| p.color != color);
+                if target.is_some() != self.is_capture() || capture.is_some() != self.is_capture() { return None }
+                let basic_move = BasicMove { piece: piece, start: start, end: end, capture: capture };
+                match self.is_promotion() {
+                    true if piece.piece_type == PieceType::Pawn => Some(Move::Promotion(Promotion { basic_move: basic_move, promote_to: get_promotion_type(flags) })),
+                    true => None,
+                    false => Some(Move::BasicMove(basic_move)),
+                }
+            },
+            _ => None,
+        }
+    }
+}
@@ -5,8 +5,10 @@ use crate::rules::pieces::PieceType;
 use crate::rules::pieces::movement::{Move, SlideDirection, PawnMovement};
 use crate::rules::pieces::{Piece, movement::CastleType};
 
-use super::bitboards::{get_bit_for_square, set_bit_at_square, unset_bit_at_square, get_diagonal_bitboard, get_ray_bitboard, BitboardSquares, get_knight_bitboard, get_pawn_bitboard, get_orthagonal_bitboard, ColorBoard, PieceTypeBoard, PieceBoard};
-use super::squares::BoardSquare;
+use super::bitboards::{get_bit_for_square, set_bit_at_square, unset_bit_at_square, get_diagonal_bitboard, get_ray_bitboard, BitboardSquares, get_knight_bitboard, get_king_bitboard, get_pawn_bitboard, get_orthagonal_bitboard, ColorBoard, PieceTypeBoard, PieceBoard};
+use super::material::MaterialSignature;
+use super::magics::{get_bishop_attacks, get_rook_attacks};
+use super::squares::get_square_from_col_and_row;
 
 
 #[derive(Clone, Default)]
@@ -67,41 +69,34 @@ pub struct CastlingSquares {
     pub king_transit_squares: u64
 }
 
+fn get_back_rank_squares_between(row: u8, first_file: u8, second_file: u8) -> u64 {
+    return (first_file.min(second_file)..=first_file.max(second_file)).fold(0u64, |board, file| {
+        board | get_bit_for_square(get_square_from_col_and_row(file, row))
+    });
+}
+
 impl CastlingSquares {
-    pub fn from_color_and_type(color: Color, ctype: CastleType) -> CastlingSquares {
-        return match (color, ctype) {
-            (Color::White, CastleType::Kingside) => {
-                 CastlingSquares {
-                     king_start: BoardSquare::E1.value(), king_end: BoardSquare::G1.value(),
-                     rook_start: BoardSquare::H1.value(), rook_end: BoardSquare::F1.value(),
-                     transit_squares: get_bit_for_square(BoardSquare::F1.value()) | get_bit_for_square(BoardSquare::G1.value()),
-                     king_transit_squares: get_bit_for_square(BoardSquare::F1.value()),
-                 }
-            },
-            (Color::White, CastleType::Queenside) => {
-                CastlingSquares {
-                    king_start: BoardSquare::E1.value(), king_end: BoardSquare::C1.value(),
-                    rook_start: BoardSquare::A1.value(), rook_end: BoardSquare::D1.value(),
-                    transit_squares: get_bit_for_square(BoardSquare::D1.value()) | get_bit_for_square(BoardSquare::C1.value()) | get_bit_for_square(BoardSquare::B1.value()),
-                    king_transit_squares: get_bit_for_square(BoardSquare::D1.value()),
-                }
-            },
-            (Color::Black, CastleType::Kingside) => {
-                CastlingSquares {
-                    king_start: BoardSquare::E8.value(), king_end: BoardSquare::G8.value(),
-                    rook_start: BoardSquare::H8.value(), rook_end: BoardSquare::F8.value(),
-                    transit_squares: get_bit_for_square(BoardSquare::F8.value()) | get_bit_for_square(BoardSquare::G8.value()),
-                    king_transit_squares: get_bit_for_square(BoardSquare::F8.value()),
-                }
-            },
-            (Color::Black, CastleType::Queenside) => {
-                CastlingSquares {
-                    king_start: BoardSquare::E8.value(), king_end: BoardSquare::C8.value(),
-                    rook_start: BoardSquare::A8.value(), rook_end: BoardSquare::D8.value(),
-                    transit_squares: get_bit_for_square(BoardSquare::D8.value()) | get_bit_for_square(BoardSquare::C8.value()) | get_bit_for_square(BoardSquare::B8.value()),
-                    king_transit_squares: get_bit_for_square(BoardSquare::D8.value()),
-                }
-            }
+    pub fn from_files(color: Color, ctype: CastleType, king_file: u8, rook_file: u8) -> CastlingSquares {
+        let row = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let (king_end_file, rook_end_file) = match ctype {
+            CastleType::Kingside  => (6, 5),
+            CastleType::Queenside => (2, 3),
+        };
+        let king_start = get_square_from_col_and_row(king_file, row);
+        let king_end = get_square_from_col_and_row(king_end_file, row);
+        let rook_start = get_square_from_col_and_row(rook_file, row);
+        let king_path = get_back_rank_squares_between(row, king_file, king_end_file);
+        let rook_path = get_back_rank_squares_between(row, rook_file, rook_end_file);
+        return CastlingSquares {
+            king_start: king_start,
+            king_end: king_end,
+            rook_start: rook_start,
+            rook_end: get_square_from_col_and_row(rook_end_file, row),
+            transit_squares: (king_path | rook_path) & !get_bit_for_square(king_start) & !get_bit_for_square(rook_start),
+            king_transit_squares: king_path & !get_bit_for_square(king_start) & !get_bit_for_square(king_end),
         }
     }
 }
@@ -154,19 +149,24 @@ impl<I> Iterator for BitboardPieceLocations<I> where I: Iterator<Item=PieceBoard
 }
 
 
-pub struct PieceBoardGenerator {
+pub struct PieceBoardGenerator<const N: usize> {
     position: BoardPosition,
-    pieces: Vec<Piece>,
+    pieces: [Piece; N],
+    remaining: usize,
     mask: u64,
 }
 
-impl Iterator for PieceBoardGenerator {
+impl<const N: usize> Iterator for PieceBoardGenerator<N> {
     type Item = PieceBoard;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.pieces.pop() {
-            None => None,
-            Some(p) => Some(self.position.get_masked_piece_board(p.color, p.piece_type, self.mask))
+        match self.remaining {
+            0 => None,
+            _ => {
+                self.remaining -= 1;
+                let p = self.pieces[self.remaining];
+                Some(self.position.get_masked_piece_board(p.color, p.piece_type, self.mask))
+            }
         }
     }
 }
@@ -225,23 +225,24 @@ impl BoardPosition {
         return Some(Piece { color: color, piece_type: piece_type });
     }
 
-    pub fn get_masked_piece_squares(&self, pieces: Vec<Piece>, mask: u64) -> BitboardPieceLocations<PieceBoardGenerator> {
+    pub fn get_masked_piece_squares<const N: usize>(&self, pieces: [Piece; N], mask: u64) -> BitboardPieceLocations<PieceBoardGenerator<N>> {
         return BitboardPieceLocations::from_iter(PieceBoardGenerator {
             position: *self,
             mask: mask,
             pieces: pieces,
+            remaining: N,
         });
     }
 
-    pub fn get_all_masked_piece_squares_for_color(&self, color: Color, mask: u64) -> BitboardPieceLocations<PieceBoardGenerator> {
-        return self.get_masked_piece_squares(Vec::from([
+    pub fn get_all_masked_piece_squares_for_color(&self, color: Color, mask: u64) -> BitboardPieceLocations<PieceBoardGenerator<6>> {
+        return self.get_masked_piece_squares([
             Piece { color: color, piece_type: PieceType::Pawn   },
             Piece { color: color, piece_type: PieceType::Knight },
             Piece { color: color, piece_type: PieceType::Bishop },
             Piece { color: color, piece_type: PieceType::Rook   },
             Piece { color: color, piece_type: PieceType::Queen  },
             Piece { color: color, piece_type: PieceType::King   },
-        ]), mask);
+        ], mask);
     }
 
     pub fn get_masked_piece_board(&self, color: Color, piece_type: PieceType, mask: u64) -> PieceBoard {
@@ -277,6 +278,16 @@ impl BoardPosition {
         return match color { Color::White => self.white_pieces, Color::Black => self.black_pieces };
     }
 
+    pub fn get_material_signature(&self) -> MaterialSignature {
+        let mut counts = [[0u8; 6]; 2];
+        for color in Color::iter() {
+            for piece_type in PieceType::iter() {
+                counts[color as usize][piece_type as usize] = self.get_piece_locations(color, piece_type).count_ones() as u8;
+            }
+        }
+        return MaterialSignature::from_counts(counts);
+    }
+
     fn insert_piece(&mut self, square: u8, piece: Piece) {
         self.insert_piece_into_boards(square, piece);
     }
@@ -315,11 +326,6 @@ impl BoardPosition {
         }
     }
 
-    fn move_piece(&mut self, start: u8, end: u8, piece: Piece) {
-        self.remove_piece(start, piece);
-        self.insert_piece(end, piece);
-    }
-
     pub fn apply_move(&mut self, new_move: &Move) {
         if let Some(capture) = new_move.get_capture() {
             self.remove_piece(capture.square, capture.get_piece());
@@ -328,8 +334,11 @@ impl BoardPosition {
             self.remove_piece(p.basic_move.start, p.basic_move.piece);
             self.insert_piece(p.basic_move.end, Piece { color: p.basic_move.piece.color, piece_type: p.promote_to });
         } else {
-            for movement in new_move.get_piece_movements() {
-                self.move_piece(movement.start_square, movement.end_square, movement.get_piece());
+            for movement in new_move.iter_piece_movements() {
+                self.remove_piece(movement.start_square, movement.get_piece());
+            }
+            for movement in new_move.iter_piece_movements() {
+                self.insert_piece(movement.end_square, movement.get_piece());
             }
         }
     }
@@ -342,8 +351,11 @@ impl BoardPosition {
             self.remove_piece(p.basic_move.end, Piece { color: p.basic_move.piece.color, piece_type: p.promote_to });
             self.insert_piece(p.basic_move.start, p.basic_move.piece);
         } else {
-            for movement in old_move.get_piece_movements() {
-                self.move_piece(movement.end_square, movement.start_square, movement.get_piece());
+            for movement in old_move.iter_piece_movements() {
+                self.remove_piece(movement.end_square, movement.get_piece());
+            }
+            for movement in old_move.iter_piece_movements() {
+                self.insert_piece(movement.start_square, movement.get_piece());
             }
         }
     }
@@ -491,33 +503,23 @@ impl BoardPosition {
             return true;
         }
 
+        if self.get_piece_locations(attacking_color, PieceType::King) & get_king_bitboard(king_square) != 0 {
+            return true;
+        }
+
         let pawn_attacks = match king_color { Color::White => PawnMovement::WhiteAttack, Color::Black => PawnMovement::BlackAttack };
         if  self.get_piece_locations(attacking_color, PieceType::Pawn) & get_pawn_bitboard(king_square, pawn_attacks) != 0 {
             return true;
         }
 
         let diagonal_attackers = self.get_diagonal_slider_locations(attacking_color);
-        let diagonal_bitboard = get_diagonal_bitboard(king_square);
-        if diagonal_attackers & diagonal_bitboard != 0 {
-            for dir in SlideDirection::diagonals() {
-                let ray = get_ray_bitboard(king_square, dir);
-                let blocks = ray & all_pieces;
-                if blocks == 0 { continue };
-                let potential_attacker = get_bit_for_square(if dir.is_positive() { blocks.trailing_zeros() as u8 } else { 63 - blocks.leading_zeros() as u8 });
-                if potential_attacker & diagonal_attackers != 0 { return true };
-            }
+        if diagonal_attackers & get_diagonal_bitboard(king_square) != 0 && get_bishop_attacks(king_square, all_pieces) & diagonal_attackers != 0 {
+            return true;
         }
 
         let orthagonal_attackers = self.get_orthagonal_slider_locations(attacking_color);
-        let orthagonal_bitboard = get_orthagonal_bitboard(king_square);
-        if orthagonal_attackers & orthagonal_bitboard != 0 {
-            for dir in SlideDirection::orthagonals() {
-                let ray = get_ray_bitboard(king_square, dir);
-                let blocks = ray & all_pieces;
-                if blocks == 0 { continue };
-                let potential_attacker = get_bit_for_square(if dir.is_positive() { blocks.trailing_zeros() as u8 } else { 63 - blocks.leading_zeros() as u8 });
-                if potential_attacker & orthagonal_attackers != 0 { return true };
-            }
+        if orthagonal_attackers & get_orthagonal_bitboard(king_square) != 0 && get_rook_attacks(king_square, all_pieces) & orthagonal_attackers != 0 {
+            return true;
         }
 
         return false;
@@ -81,12 +81,55 @@ impl Default for BoardCastles {
 }
 
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CastleFiles {
+    pub white_king: u8,
+    pub black_king: u8,
+    pub white_kingside: u8,
+    pub white_queenside: u8,
+    pub black_kingside: u8,
+    pub black_queenside: u8,
+}
+
+impl CastleFiles {
+    pub fn get_king_file(&self, color: Color) -> u8 {
+        match color {
+            Color::White => self.white_king,
+            Color::Black => self.black_king,
+        }
+    }
+
+    pub fn get_file(&self, right: &CastleRight) -> u8 {
+        match (right.color, right.side) {
+            (Color::White, CastleType::Kingside)  => self.white_kingside,
+            (Color::White, CastleType::Queenside) => self.white_queenside,
+            (Color::Black, CastleType::Kingside)  => self.black_kingside,
+            (Color::Black, CastleType::Queenside) => self.black_queenside,
+        }
+    }
+}
+
+impl Default for CastleFiles {
+    fn default() -> Self {
+        Self {
+            white_king: 4,
+            black_king: 4,
+            white_kingside: 7,
+            white_queenside: 0,
+            black_kingside: 7,
+            black_queenside: 0,
+        }
+    }
+}
+
+
 #[derive(Copy, Clone, Default, PartialEq, Eq)]
 pub struct BoardState {
     pub to_move: Color,
     pub castle_rights: BoardCastles,
+    pub castle_files: CastleFiles,
     pub en_passant_target: u64,
-    pub move_number: u8,
+    pub move_number: u16,
     pub halfmove_clock: u8,
 }
 
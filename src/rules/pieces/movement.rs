@@ -62,16 +62,16 @@ impl SlideDirection {
         }
     }
 
-    pub fn get_hash_offset(&self) -> u16 {
+    pub fn get_table_index(&self) -> usize {
         return match self {
-            SlideDirection::North     => 64 * 0,
-            SlideDirection::NorthEast => 64 * 1,
-            SlideDirection::East      => 64 * 2,
-            SlideDirection::SouthEast => 64 * 3,
-            SlideDirection::South     => 64 * 4,
-            SlideDirection::SouthWest => 64 * 5,
-            SlideDirection::West      => 64 * 6,
-            SlideDirection::NorthWest => 64 * 7,
+            SlideDirection::North     => 0,
+            SlideDirection::NorthEast => 1,
+            SlideDirection::East      => 2,
+            SlideDirection::SouthEast => 3,
+            SlideDirection::South     => 4,
+            SlideDirection::SouthWest => 5,
+            SlideDirection::West      => 6,
+            SlideDirection::NorthWest => 7,
         }
     }
 
@@ -108,12 +108,12 @@ impl PawnMovement {
         }
     }
 
-    pub fn get_hash_offset(&self) -> u8 {
+    pub fn get_table_index(&self) -> usize {
         return match self {
-            PawnMovement::WhiteAdvance => 64 * 0,
-            PawnMovement::WhiteAttack  => 64 * 1,
-            PawnMovement::BlackAdvance => 64 * 2,
-            PawnMovement::BlackAttack  => 64 * 3,
+            PawnMovement::WhiteAdvance => 0,
+            PawnMovement::WhiteAttack  => 1,
+            PawnMovement::BlackAdvance => 2,
+            PawnMovement::BlackAttack  => 3,
         }
     }
 
@@ -198,17 +198,36 @@ pub enum Move {
 }
 
 impl Move {
-    pub fn get_piece_movements(&self) -> Vec<PieceMovement> {
-        match self {
-            Move::NullMove(_m) => Vec::new(),
-            Move::BasicMove(m) => m.get_piece_movements(),
-            Move::Castle(m) => m.get_piece_movements(),
-            Move::Promotion(m) => m.basic_move.get_piece_movements(),
-            Move::TwoSquarePawnMove(m) => m.basic_move.get_piece_movements(),
-            Move::EnPassant(m) => m.basic_move.get_piece_movements(),
+    pub fn get_primary_movement(&self) -> Option<PieceMovement> {
+        return match self {
+            Move::NullMove(_m) => None,
+            Move::BasicMove(m) => Some(m.get_piece_movement()),
+            Move::Castle(m) => Some(m.get_king_movement()),
+            Move::Promotion(m) => Some(m.basic_move.get_piece_movement()),
+            Move::TwoSquarePawnMove(m) => Some(m.basic_move.get_piece_movement()),
+            Move::EnPassant(m) => Some(m.basic_move.get_piece_movement()),
         }
     }
 
+    pub fn get_secondary_movement(&self) -> Option<PieceMovement> {
+        return match self {
+            Move::Castle(m) => Some(m.get_rook_movement()),
+            _ => None,
+        }
+    }
+
+    pub fn iter_piece_movements(&self) -> impl Iterator<Item = PieceMovement> {
+        return [self.get_primary_movement(), self.get_secondary_movement()].into_iter().flatten();
+    }
+
+    pub fn get_piece_movements(&self) -> Vec<PieceMovement> {
+        return self.iter_piece_movements().collect();
+    }
+
+    pub fn get_moved_piece(&self) -> Option<Piece> {
+        return self.get_primary_movement().map(|m| m.get_piece());
+    }
+
     pub fn get_capture(&self) -> Option<Capture> {
         match self {
             Move::NullMove(_m) => None,
@@ -222,7 +241,7 @@ impl Move {
 
     pub fn relative_capture_value(&self) -> Option<i16> {
         self.get_capture().map(|cap| {
-            self.get_piece_movements()[0].get_piece().relative_value(cap.get_piece())
+            self.get_moved_piece().expect("Null moves cannot capture").relative_value(cap.get_piece())
         })
     }
 }
@@ -239,13 +258,13 @@ pub struct BasicMove {
 }
 
 impl BasicMove {
-    fn get_piece_movements(&self) -> Vec<PieceMovement> {
-        return [ PieceMovement {
+    fn get_piece_movement(&self) -> PieceMovement {
+        return PieceMovement {
             color: self.piece.color,
             piece_type: self.piece.piece_type,
             start_square: self.start,
             end_square: self.end,
-        } ].into_iter().collect();
+        }
     }
 
     fn get_capture(&self) -> Option<Capture> {
@@ -266,11 +285,12 @@ pub struct Castle {
 }
 
 impl Castle {
-    fn get_piece_movements(&self) -> Vec<PieceMovement> {
-        return [
-            PieceMovement { color: self.color, piece_type: PieceType::King, start_square: self.king_start, end_square: self.king_end },
-            PieceMovement { color: self.color, piece_type: PieceType::Rook, start_square: self.rook_start, end_square: self.rook_end },
-        ].into_iter().collect()
+    fn get_king_movement(&self) -> PieceMovement {
+        return PieceMovement { color: self.color, piece_type: PieceType::King, start_square: self.king_start, end_square: self.king_end }
+    }
+
+    fn get_rook_movement(&self) -> PieceMovement {
+        return PieceMovement { color: self.color, piece_type: PieceType::Rook, start_square: self.rook_start, end_square: self.rook_end }
     }
 
     fn get_capture(&self) -> Option<Capture> {
@@ -285,10 +305,10 @@ pub struct Promotion {
 }
 
 impl Promotion {
-    pub fn get_all_from_basic_move(base: &BasicMove) -> Vec<Promotion> {
-        return [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight].into_iter().map(|ptype| {
+    pub fn get_all_from_basic_move(base: &BasicMove) -> [Promotion; 4] {
+        return [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight].map(|ptype| {
             Promotion { basic_move: *base, promote_to: ptype }
-        }).collect();
+        });
     }
 }
 
@@ -0,0 +1,261 @@
+use std::{hint::black_box, time::{Duration, Instant}};
+
+use num_format::{ToFormattedString, Locale};
+use tabled::Tabled;
+
+use crate::{rules::{board::{Board, bitboards::{get_moves_for_piece, get_ray_walk_moves_for_slider}}, pieces::{Piece, PieceType}, Color}, testing::perft::PerftRunner, util::allocations::{AllocationCount, count_allocations}};
+
+
+pub struct BenchPosition {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub depth: u8,
+}
+
+
+pub static BENCH_POSITIONS: [BenchPosition; 5] = [
+    BenchPosition {
+        name: "Initial position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        depth: 5,
+    },
+    BenchPosition {
+        name: "Kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        depth: 4,
+    },
+    BenchPosition {
+        name: "Position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        depth: 5,
+    },
+    BenchPosition {
+        name: "Position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        depth: 4,
+    },
+    BenchPosition {
+        name: "Position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        depth: 4,
+    },
+];
+
+
+fn get_nodes_per_second(nodes: u64, duration: Duration) -> u64 {
+    return match duration.as_secs_f64() {
+        s if s > 0f64 => (nodes as f64 / s) as u64,
+        _ => 0,
+    }
+}
+
+
+pub struct BenchResult {
+    pub name: &'static str,
+    pub depth: u8,
+    pub nodes: u64,
+    pub duration: Duration,
+}
+
+impl BenchResult {
+    pub fn nodes_per_second(&self) -> u64 {
+        return get_nodes_per_second(self.nodes, self.duration);
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableBenchResult {
+    pub position: String,
+    pub depth: String,
+    pub nodes: String,
+    pub time: String,
+    pub nps: String,
+}
+
+impl PrintableBenchResult {
+    pub fn from_result(result: &BenchResult) -> Self {
+        return Self {
+            position: String::from(result.name),
+            depth: result.depth.to_string(),
+            nodes: result.nodes.to_formatted_string(&Locale::en),
+            time: format!("{:?}", result.duration),
+            nps: result.nodes_per_second().to_formatted_string(&Locale::en),
+        }
+    }
+}
+
+
+pub struct SliderBenchResult {
+    pub lookups: u64,
+    pub mismatches: u64,
+    pub ray_walk: Duration,
+    pub table_lookup: Duration,
+}
+
+impl SliderBenchResult {
+    pub fn speedup(&self) -> f64 {
+        return self.ray_walk.as_secs_f64() / self.table_lookup.as_secs_f64().max(f64::EPSILON);
+    }
+}
+
+
+pub struct AllocationBenchResult {
+    pub name: &'static str,
+    pub depth: u8,
+    pub nodes: u64,
+    pub vec_allocations: AllocationCount,
+    pub vec_duration: Duration,
+    pub list_allocations: AllocationCount,
+    pub list_duration: Duration,
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableAllocationBenchResult {
+    pub position: String,
+    pub depth: String,
+    pub nodes: String,
+    #[header("vec allocs/node")]
+    pub vec_allocations: String,
+    #[header("vec time")]
+    pub vec_time: String,
+    #[header("list allocs/node")]
+    pub list_allocations: String,
+    #[header("list time")]
+    pub list_time: String,
+}
+
+impl PrintableAllocationBenchResult {
+    pub fn from_result(result: &AllocationBenchResult) -> Self {
+        return Self {
+            position: String::from(result.name),
+            depth: result.depth.to_string(),
+            nodes: result.nodes.to_formatted_string(&Locale::en),
+            vec_allocations: format!("{:.3}", result.vec_allocations.per_node(result.nodes)),
+            vec_time: format!("{:?}", result.vec_duration),
+            list_allocations: format!("{:.3}", result.list_allocations.per_node(result.nodes)),
+            list_time: format!("{:?}", result.list_duration),
+        }
+    }
+}
+
+
+pub struct BenchRunner {}
+
+impl BenchRunner {
+    pub fn get_lookup_method() -> &'static str {
+        return if cfg!(feature = "pext") { "PEXT" } else { "magic" };
+    }
+
+    pub fn run_perft_bench<F>(depth: Option<u8>, mut on_result: F) -> Vec<BenchResult> where F: FnMut(&BenchResult) {
+        let warmup = Board::from_starting_position();
+        PerftRunner::do_fast_perft(warmup, 1, false);
+        return BENCH_POSITIONS.iter().map(|position| {
+            let board = Board::from_fen(position.fen).expect("Bench position FEN is invalid");
+            let depth = depth.unwrap_or(position.depth);
+            let perft = PerftRunner::do_fast_perft(board, depth, false);
+            let result = BenchResult {
+                name: position.name,
+                depth: depth,
+                nodes: perft.nodes,
+                duration: perft.duration,
+            };
+            on_result(&result);
+            result
+        }).collect();
+    }
+
+    pub fn run_allocation_bench<F>(depth: Option<u8>, mut on_result: F) -> Vec<AllocationBenchResult> where F: FnMut(&AllocationBenchResult) {
+        return BENCH_POSITIONS.iter().map(|position| {
+            let board = Board::from_fen(position.fen).expect("Bench position FEN is invalid");
+            let depth = depth.unwrap_or(position.depth - 1);
+            let start = Instant::now();
+            let (vec_nodes, vec_allocations) = count_allocations(|| PerftRunner::count_nodes_with_vec(board, depth));
+            let vec_duration = start.elapsed();
+            let start = Instant::now();
+            let (list_nodes, list_allocations) = count_allocations(|| PerftRunner::count_nodes(board, depth));
+            let list_duration = start.elapsed();
+            if vec_nodes != list_nodes {
+                panic!("Move list perft counted {} nodes but Vec perft counted {}", list_nodes, vec_nodes);
+            }
+            let result = AllocationBenchResult {
+                name: position.name,
+                depth: depth,
+                nodes: list_nodes,
+                vec_allocations: vec_allocations,
+                vec_duration: vec_duration,
+                list_allocations: list_allocations,
+                list_duration: list_duration,
+            };
+            on_result(&result);
+            result
+        }).collect();
+    }
+
+    pub fn run_slider_bench(rounds: u32) -> SliderBenchResult {
+        let mut state = 0x9E3779B97F4A7C15u64;
+        let occupancies: Vec<u64> = (0..1024).map(|_| {
+            let mut values = [0u64; 2];
+            for value in values.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *value = state;
+            }
+            values[0] & values[1]
+        }).collect();
+        let pieces = [PieceType::Bishop, PieceType::Rook, PieceType::Queen];
+        let lookups = rounds as u64 * occupancies.len() as u64 * 64 * pieces.len() as u64;
+
+        let mut mismatches = 0u64;
+        for occupancy in &occupancies {
+            for square in 0u8..=63u8 {
+                for piece_type in pieces {
+                    let enemies = occupancy & !(1u64 << square);
+                    let piece = Piece { color: Color::White, piece_type: piece_type };
+                    if get_ray_walk_moves_for_slider(square, piece_type, 0, enemies) != get_moves_for_piece(square, piece, 0, enemies, 0) {
+                        mismatches += 1;
+                    }
+                }
+            }
+        }
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for occupancy in &occupancies {
+                for square in 0u8..=63u8 {
+                    for piece_type in pieces {
+                        black_box(get_ray_walk_moves_for_slider(square, piece_type, 0, black_box(*occupancy)));
+                    }
+                }
+            }
+        }
+        let ray_walk = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for occupancy in &occupancies {
+                for square in 0u8..=63u8 {
+                    for piece_type in pieces {
+                        black_box(get_moves_for_piece(square, Piece { color: Color::White, piece_type: piece_type }, 0, black_box(*occupancy), 0));
+                    }
+                }
+            }
+        }
+        let table_lookup = start.elapsed();
+
+        return SliderBenchResult {
+            lookups: lookups,
+            mismatches: mismatches,
+            ray_walk: ray_walk,
+            table_lookup: table_lookup,
+        }
+    }
+
+    pub fn get_total(results: &[BenchResult]) -> (u64, Duration, u64) {
+        let nodes: u64 = results.iter().map(|r| r.nodes).sum();
+        let duration: Duration = results.iter().map(|r| r.duration).sum();
+        return (nodes, duration, get_nodes_per_second(nodes, duration));
+    }
+}
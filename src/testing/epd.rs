@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use num_format::{ToFormattedString, Locale};
+use tabled::Tabled;
+
+use crate::{engine::search::{SearchOptions, alpha_beta::AlphaBetaSearch}, rules::{board::Board, pieces::movement::Move}, util::{epd::EpdRecord, notation::{get_move_from_san_notation, get_move_from_uci_notation, get_san_notation_for_move}}};
+
+
+fn resolve_moves(board: &Board, notes: &[String]) -> Vec<Move> {
+    return notes.iter().filter_map(|n| get_move_from_san_notation(board, n).or_else(|_| get_move_from_uci_notation(board, n)).ok()).collect();
+}
+
+
+pub struct EpdResult {
+    pub id: String,
+    pub best_move: Option<String>,
+    pub expected: String,
+    pub solved: bool,
+    pub points: Option<u32>,
+    pub max_points: Option<u32>,
+    pub depth: u8,
+    pub nodes: u64,
+    pub duration: Duration,
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableEpdResult {
+    pub id: String,
+    #[header("move")]
+    pub best_move: String,
+    pub expected: String,
+    pub result: String,
+    pub points: String,
+    pub depth: String,
+    pub nodes: String,
+    pub time: String,
+}
+
+impl PrintableEpdResult {
+    pub fn from_result(result: &EpdResult) -> Self {
+        return Self {
+            id: result.id.clone(),
+            best_move: result.best_move.clone().unwrap_or(String::from("-")),
+            expected: result.expected.clone(),
+            result: String::from(if result.solved { "solved" } else { "FAIL" }),
+            points: match (result.points, result.max_points) {
+                (Some(p), Some(m)) => format!("{}/{}", p, m),
+                _ => String::from("-"),
+            },
+            depth: result.depth.to_string(),
+            nodes: result.nodes.to_formatted_string(&Locale::en),
+            time: format!("{:?}", result.duration),
+        }
+    }
+}
+
+
+pub struct EpdRunner {}
+
+impl EpdRunner {
+    pub fn run_record(index: usize, record: &EpdRecord, options: &SearchOptions) -> EpdResult {
+        let board = Board::from_fen(&record.fen).expect("EPD record FEN is validated when parsed");
+        let best_moves = record.get_best_moves();
+        let avoid_moves = record.get_avoid_moves();
+        let search = AlphaBetaSearch::do_search_with_options(board, options);
+        let solved = match search.mov {
+            None => false,
+            Some(mov) => {
+                (best_moves.is_empty() || resolve_moves(&board, &best_moves).contains(&mov)) &&
+                !resolve_moves(&board, &avoid_moves).contains(&mov) &&
+                !(best_moves.is_empty() && avoid_moves.is_empty())
+            },
+        };
+        let move_points: Vec<(Move, u32)> = record.get_move_points().into_iter().filter_map(|(note, points)| {
+            get_move_from_san_notation(&board, &note).ok().map(|m| (m, points))
+        }).collect();
+        let mut expected: Vec<String> = Vec::new();
+        if !best_moves.is_empty() { expected.push(format!("bm {}", best_moves.join(" "))) }
+        if !avoid_moves.is_empty() { expected.push(format!("am {}", avoid_moves.join(" "))) }
+        return EpdResult {
+            id: record.get_id().unwrap_or(format!("#{}", index + 1)),
+            best_move: search.mov.map(|m| get_san_notation_for_move(&board, &m)),
+            expected: match expected.is_empty() {
+                true => String::from("-"),
+                false => expected.join("; "),
+            },
+            solved: solved,
+            points: match move_points.is_empty() {
+                true => None,
+                false => Some(move_points.iter().find(|(m, _)| Some(*m) == search.mov).map_or(0, |(_, p)| *p)),
+            },
+            max_points: move_points.iter().map(|(_, p)| *p).max(),
+            depth: search.depth,
+            nodes: search.statistics.nodes(),
+            duration: search.statistics.duration,
+        }
+    }
+
+    pub fn run<F>(records: &[EpdRecord], options: &SearchOptions, mut on_result: F) -> Vec<EpdResult> where F: FnMut(&EpdResult) {
+        return records.iter().enumerate().map(|(index, record)| {
+            let result = Self::run_record(index, record, options);
+            on_result(&result);
+            result
+        }).collect();
+    }
+}
@@ -0,0 +1,717 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use tabled::Tabled;
+
+use crate::{engine::{evaluation::Evaluator, parameters::EvaluationParameters, search::{MAX_SEARCH_DEPTH, SearchOptions, alpha_beta::AlphaBetaSearch, time_manager::TimeManager}, strength::StrengthLevel, tablebase::{Tablebases, WdlScore}}, game::{Game, GameResult, GameTermination, clock::TimeControl}, interface::uci_client::{UciClockState, UciEngineFailure, UciEngineProcess, UciSearchLimits}, rules::{Color, board::Board, pieces::movement::Move}, util::{epd::EpdRecord, errors::InputError, pgn::{PgnGame, read_pgn_file, write_pgn_file}, random::Random}};
+
+
+static DEFAULT_DEPTH: u8 = 4;
+static DEFAULT_TIME_MARGIN: Duration = Duration::from_millis(250);
+static DEPTH_SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
+static CONFIDENCE_Z: f64 = 1.959964;
+static SPRT_PSEUDO_COUNT: f64 = 0.5;
+
+
+fn parse_setting<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, InputError> {
+    return value.parse().map_err(|_| InputError::new(&format!("'{}' is not a valid value for engine setting '{}'", value, key)));
+}
+
+
+#[derive(Clone)]
+pub struct MatchEngine {
+    pub name: String,
+    pub options: SearchOptions,
+    pub depth: Option<u8>,
+    pub strength: StrengthLevel,
+    pub use_tablebases: bool,
+    pub command: Option<String>,
+    pub uci_options: Vec<(String, String)>,
+    pub time_margin: Duration,
+}
+
+impl MatchEngine {
+    pub fn from_spec(spec: &str, default_name: &str) -> Result<Self, InputError> {
+        let mut name = String::from(default_name);
+        let mut depth: Option<u8> = None;
+        let mut time: Option<u64> = None;
+        let mut evaluator = Evaluator::default();
+        let mut parameters: Option<EvaluationParameters> = None;
+        let mut strength = StrengthLevel::default();
+        let mut use_tablebases = false;
+        let mut command: Option<String> = None;
+        let mut uci_options: Vec<(String, String)> = Vec::new();
+        let mut time_margin = DEFAULT_TIME_MARGIN;
+        for setting in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (key, value) = match setting.split_once('=') {
+                Some(kv) => kv,
+                None => return Err(InputError::new(&format!("Engine setting '{}' should be written as key=value", setting))),
+            };
+            match key {
+                "name" => name = String::from(value),
+                "depth" => depth = Some(parse_setting(key, value)?),
+                "time" => time = Some(parse_setting(key, value)?),
+                "eval" => evaluator = Evaluator::from_name(value)?,
+                "params" => parameters = Some(EvaluationParameters::from_file(value)?),
+                "skill" => strength = StrengthLevel::from_skill_name(value)?,
+                "elo" => strength = StrengthLevel::from_elo_name(value)?,
+                "tb" => use_tablebases = parse_setting(key, value)?,
+                "cmd" => command = Some(String::from(value)),
+                "margin" => time_margin = Duration::from_millis(parse_setting(key, value)?),
+                k if k.starts_with("opt.") => uci_options.push((String::from(&k[4..]), String::from(value))),
+                _ => return Err(InputError::new(&format!("'{}' is not a known engine setting", key))),
+            }
+        }
+        if command.is_some() && strength.is_limited() {
+            return Err(InputError::new("Skill and Elo settings only apply to the built-in engine, use opt.<Name>=value for external engines"));
+        }
+        if command.is_some() && parameters.is_some() {
+            return Err(InputError::new("Evaluation parameter files only apply to the built-in engine, use opt.<Name>=value for external engines"));
+        }
+        let mut options = match time {
+            Some(t) => SearchOptions::with_time_limit(Duration::from_millis(t)),
+            None => SearchOptions::new(depth.unwrap_or(DEFAULT_DEPTH)),
+        };
+        options.depth = depth.unwrap_or(options.depth).min(MAX_SEARCH_DEPTH);
+        options.evaluator = evaluator;
+        if let Some(p) = parameters {
+            options.parameters = Arc::new(p);
+        }
+        return Ok(Self {
+            name: name,
+            options: options,
+            depth: depth,
+            strength: strength,
+            use_tablebases: use_tablebases,
+            command: command,
+            uci_options: uci_options,
+            time_margin: time_margin,
+        });
+    }
+
+    pub fn describe(&self) -> String {
+        let mut settings: Vec<String> = Vec::new();
+        if let Some(command) = &self.command {
+            settings.push(format!("external '{}'", command));
+        }
+        if let Some(depth) = self.get_depth_limit(false) {
+            settings.push(format!("depth {}", depth));
+        }
+        if let Some(limit) = self.options.time_limit {
+            settings.push(format!("{}ms per move", limit.as_millis()));
+        }
+        if self.command.is_none() {
+            settings.push(format!("{} eval", self.options.evaluator.name()));
+        }
+        if *self.options.parameters != EvaluationParameters::default() {
+            settings.push(String::from("custom parameters"));
+        }
+        if self.strength.is_limited() {
+            settings.push(self.strength.name());
+        }
+        if self.use_tablebases {
+            settings.push(String::from("tablebases"));
+        }
+        for (name, value) in &self.uci_options {
+            settings.push(format!("{}={}", name, value));
+        }
+        return settings.join(", ");
+    }
+
+    fn get_depth_limit(&self, timed: bool) -> Option<u8> {
+        return match timed || self.options.time_limit.is_some() {
+            true => self.depth,
+            false => Some(self.options.depth),
+        }
+    }
+
+    fn get_uci_limits(&self, game: &Game) -> UciSearchLimits {
+        let color = game.get_current_turn();
+        let clock = game.get_clock().map(|c| UciClockState {
+            remaining: [c.get_remaining(Color::White), c.get_remaining(Color::Black)],
+            increment: [c.get_increment(Color::White), c.get_increment(Color::Black)],
+            moves_to_go: c.get_moves_to_go(color),
+        });
+        let timeout = match (clock, self.options.time_limit) {
+            (Some(c), Some(t)) => c.remaining[color as usize].min(t) + self.time_margin,
+            (Some(c), None) => c.remaining[color as usize] + self.time_margin,
+            (None, Some(t)) => t + self.time_margin,
+            (None, None) => DEPTH_SEARCH_TIMEOUT,
+        };
+        return UciSearchLimits {
+            depth: self.get_depth_limit(clock.is_some()),
+            movetime: self.options.time_limit,
+            clock: clock,
+            timeout: timeout,
+        }
+    }
+
+    fn get_search_options(&self, game: &Game) -> SearchOptions {
+        let mut options = self.options.clone();
+        if let Some(clock) = game.get_clock() {
+            let limit = TimeManager::from_clock(clock, game.get_current_turn()).get_time_limit();
+            options.time_limit = Some(options.time_limit.map_or(limit, |t| t.min(limit)));
+            options.depth = self.depth.unwrap_or(MAX_SEARCH_DEPTH);
+        }
+        return options;
+    }
+}
+
+
+struct MatchPlayer {
+    engine: MatchEngine,
+    process: Option<UciEngineProcess>,
+    random: Random,
+}
+
+impl MatchPlayer {
+    fn new(engine: MatchEngine) -> Self {
+        return Self {
+            engine: engine,
+            process: None,
+            random: Random::new(),
+        }
+    }
+
+    fn start_game(&mut self) -> Result<(), InputError> {
+        let command = match &self.engine.command {
+            Some(c) => c.clone(),
+            None => return Ok(()),
+        };
+        if let Some(process) = self.process.as_mut() {
+            if process.is_alive() && process.new_game().is_ok() {
+                return Ok(());
+            }
+        }
+        self.process = None;
+        let mut process = UciEngineProcess::spawn(&command)?;
+        for (name, value) in &self.engine.uci_options {
+            if process.set_option(name, value).is_err() {
+                return Err(InputError::new(&format!("Engine '{}' exited while setting option {}", command, name)));
+            }
+        }
+        if process.new_game().is_err() {
+            return Err(InputError::new(&format!("Engine '{}' did not respond to a new game", command)));
+        }
+        self.process = Some(process);
+        return Ok(());
+    }
+
+    fn choose_move(&mut self, game: &Game) -> Result<(Move, Option<i16>), MatchTermination> {
+        return match self.process.as_mut() {
+            Some(process) => match process.search(game, &self.engine.get_uci_limits(game)) {
+                Ok(result) => Ok((result.mov, result.score)),
+                Err(failure) => {
+                    if failure == UciEngineFailure::Crash {
+                        self.process = None;
+                    }
+                    Err(MatchTermination::from_failure(&failure))
+                },
+            },
+            None => {
+                let mut options = self.engine.get_search_options(game);
+                self.engine.strength.apply(&mut options, &mut self.random);
+                let search = AlphaBetaSearch::do_search_with_options(*game.get_board(), &options);
+                match self.engine.strength.pick_move(&search, game.get_current_turn(), &mut self.random).filter(|m| game.get_legal_moves().contains(m)) {
+                    Some(mov) => Ok((mov, Some(search.score))),
+                    None => Err(MatchTermination::Forfeit),
+                }
+            },
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            process.quit();
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct MatchOpening {
+    pub name: String,
+    board: Board,
+    moves: Vec<Move>,
+}
+
+impl MatchOpening {
+    pub fn start_position() -> Self {
+        return Self {
+            name: String::from("startpos"),
+            board: Board::from_starting_position(),
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Vec<Self>, InputError> {
+        let openings: Vec<Self> = match path.to_lowercase().ends_with(".pgn") {
+            true => read_pgn_file(path)?.iter().enumerate().map(|(index, pgn)| {
+                let mut moves: Vec<Move> = Vec::new();
+                pgn.replay(usize::MAX, |_, m| moves.push(*m))?;
+                Ok(Self {
+                    name: pgn.get_tag("Opening").map_or(format!("#{}", index + 1), String::from),
+                    board: *pgn.get_starting_game()?.get_board(),
+                    moves: moves,
+                })
+            }).collect::<Result<Vec<Self>, InputError>>()?,
+            false => {
+                let (records, errors) = EpdRecord::from_file(path)?;
+                if let Some(error) = errors.first() {
+                    return Err(InputError::new(&format!("Could not read opening in {}: {}", path, error)));
+                }
+                records
+            }.iter().enumerate().map(|(index, record)| {
+                Ok(Self {
+                    name: record.get_id().unwrap_or(format!("#{}", index + 1)),
+                    board: Board::from_fen(&record.fen)?,
+                    moves: Vec::new(),
+                })
+            }).collect::<Result<Vec<Self>, InputError>>()?,
+        };
+        if openings.is_empty() {
+            return Err(InputError::new(&format!("No openings found in {}", path)));
+        }
+        return Ok(openings);
+    }
+
+    fn to_game(&self) -> Game {
+        let mut game = Game::from_board(self.board);
+        for mov in &self.moves {
+            game.make_move(mov);
+        }
+        return game;
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MatchTermination {
+    Rules(GameTermination),
+    ResignAdjudication,
+    DrawAdjudication,
+    MoveLimit,
+    TablebaseAdjudication,
+    Forfeit,
+    IllegalMove,
+    TimeForfeit,
+    EngineCrash,
+}
+
+impl MatchTermination {
+    fn from_failure(failure: &UciEngineFailure) -> Self {
+        return match failure {
+            UciEngineFailure::Crash => Self::EngineCrash,
+            UciEngineFailure::TimeForfeit => Self::TimeForfeit,
+            UciEngineFailure::IllegalMove(_) => Self::IllegalMove,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        return match self {
+            Self::Rules(t) => t.name(),
+            Self::ResignAdjudication => "resign adjudication",
+            Self::DrawAdjudication => "draw adjudication",
+            Self::MoveLimit => "move limit",
+            Self::TablebaseAdjudication => "tablebase adjudication",
+            Self::Forfeit => "no legal move returned",
+            Self::IllegalMove => "illegal move",
+            Self::TimeForfeit => "time forfeit",
+            Self::EngineCrash => "engine crash",
+        }
+    }
+
+    fn get_pgn_termination(&self) -> &str {
+        return match self {
+            Self::Rules(GameTermination::TimeForfeit | GameTermination::TimeoutVsInsufficientMaterial) | Self::TimeForfeit => "time forfeit",
+            Self::Rules(_) => "normal",
+            Self::Forfeit | Self::IllegalMove => "rules infraction",
+            Self::EngineCrash => "abandoned",
+            _ => "adjudication",
+        }
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub struct AdjudicationOptions {
+    pub resign_score: i16,
+    pub resign_moves: u16,
+    pub draw_score: i16,
+    pub draw_moves: u16,
+    pub draw_after: u16,
+    pub max_moves: u16,
+}
+
+impl Default for AdjudicationOptions {
+    fn default() -> Self {
+        return Self {
+            resign_score: 1000,
+            resign_moves: 3,
+            draw_score: 10,
+            draw_moves: 8,
+            draw_after: 40,
+            max_moves: 200,
+        }
+    }
+}
+
+
+struct Adjudicator {
+    options: AdjudicationOptions,
+    resign_winner: Option<Color>,
+    resign_plies: u16,
+    draw_plies: u16,
+}
+
+impl Adjudicator {
+    fn new(options: AdjudicationOptions) -> Self {
+        return Self {
+            options: options,
+            resign_winner: None,
+            resign_plies: 0,
+            draw_plies: 0,
+        }
+    }
+
+    fn record(&mut self, score: Option<i16>, move_number: u16) -> Option<(GameResult, MatchTermination)> {
+        let score = match score {
+            Some(s) => s,
+            None => {
+                self.resign_winner = None;
+                self.resign_plies = 0;
+                self.draw_plies = 0;
+                return None;
+            },
+        };
+        let magnitude = (score as i32).abs();
+        if self.options.resign_moves > 0 && magnitude >= self.options.resign_score as i32 {
+            let winner = if score > 0 { Color::White } else { Color::Black };
+            self.resign_plies = if self.resign_winner == Some(winner) { self.resign_plies + 1 } else { 1 };
+            self.resign_winner = Some(winner);
+            if self.resign_plies >= self.options.resign_moves * 2 {
+                return Some((match winner {
+                    Color::White => GameResult::WhiteWins,
+                    Color::Black => GameResult::BlackWins,
+                }, MatchTermination::ResignAdjudication));
+            }
+        } else {
+            self.resign_winner = None;
+            self.resign_plies = 0;
+        }
+        if self.options.draw_moves > 0 && move_number > self.options.draw_after && magnitude <= self.options.draw_score as i32 {
+            self.draw_plies += 1;
+            if self.draw_plies >= self.options.draw_moves * 2 {
+                return Some((GameResult::Draw, MatchTermination::DrawAdjudication));
+            }
+        } else {
+            self.draw_plies = 0;
+        }
+        return None;
+    }
+}
+
+
+#[derive(Copy, Clone)]
+pub struct SprtOptions {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for SprtOptions {
+    fn default() -> Self {
+        return Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl SprtOptions {
+    pub fn get_bounds(&self) -> (f64, f64) {
+        return ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln());
+    }
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SprtVerdict {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+impl SprtVerdict {
+    pub fn name(&self) -> &str {
+        return match self {
+            Self::AcceptH0 => "H0 accepted",
+            Self::AcceptH1 => "H1 accepted",
+            Self::Continue => "inconclusive",
+        }
+    }
+}
+
+
+fn get_expected_score(elo: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+}
+
+fn get_elo_difference(score: f64) -> f64 {
+    return 400.0 * (score / (1.0 - score)).log10();
+}
+
+
+#[derive(Copy, Clone, Default)]
+pub struct MatchStatistics {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchStatistics {
+    pub fn record(&mut self, result: GameResult, engine_color: Color) {
+        match result.get_winner() {
+            Some(c) if c == engine_color => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        return self.wins + self.draws + self.losses;
+    }
+
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        return (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64;
+    }
+
+    fn get_distribution(&self, pseudo_count: f64) -> (f64, f64, f64) {
+        let wins = self.wins as f64 + pseudo_count;
+        let draws = self.draws as f64 + pseudo_count;
+        let losses = self.losses as f64 + pseudo_count;
+        let games = wins + draws + losses;
+        let score = (wins + draws / 2.0) / games;
+        let variance = (wins * (1.0 - score).powi(2) + draws * (0.5 - score).powi(2) + losses * score.powi(2)) / games;
+        return (score, variance, games);
+    }
+
+    pub fn elo(&self) -> f64 {
+        return get_elo_difference(self.score());
+    }
+
+    pub fn elo_error(&self) -> f64 {
+        if self.games() == 0 {
+            return f64::INFINITY;
+        }
+        let (score, variance, games) = self.get_distribution(SPRT_PSEUDO_COUNT);
+        let margin = CONFIDENCE_Z * (variance / games).sqrt();
+        if score + margin >= 1.0 || score - margin <= 0.0 {
+            return f64::INFINITY;
+        }
+        return (get_elo_difference(score + margin) - get_elo_difference(score - margin)) / 2.0;
+    }
+
+    pub fn llr(&self, sprt: &SprtOptions) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        let (score, variance, games) = self.get_distribution(SPRT_PSEUDO_COUNT);
+        let score0 = get_expected_score(sprt.elo0);
+        let score1 = get_expected_score(sprt.elo1);
+        return (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance / games);
+    }
+
+    pub fn get_verdict(&self, sprt: &SprtOptions) -> SprtVerdict {
+        let (lower, upper) = sprt.get_bounds();
+        let llr = self.llr(sprt);
+        if llr >= upper {
+            return SprtVerdict::AcceptH1;
+        }
+        if llr <= lower {
+            return SprtVerdict::AcceptH0;
+        }
+        return SprtVerdict::Continue;
+    }
+
+    pub fn get_summary(&self, sprt: &SprtOptions) -> Vec<PrintableMatchStatistic> {
+        let (lower, upper) = sprt.get_bounds();
+        return Vec::from([
+            PrintableMatchStatistic::new("games", self.games().to_string()),
+            PrintableMatchStatistic::new("wins", self.wins.to_string()),
+            PrintableMatchStatistic::new("draws", self.draws.to_string()),
+            PrintableMatchStatistic::new("losses", self.losses.to_string()),
+            PrintableMatchStatistic::new("score", format!("{:.1}%", self.score() * 100.0)),
+            PrintableMatchStatistic::new("elo", format!("{:+.1} +/- {:.1}", self.elo(), self.elo_error())),
+            PrintableMatchStatistic::new("sprt", format!("elo0={} elo1={} alpha={} beta={}", sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta)),
+            PrintableMatchStatistic::new("llr", format!("{:.2} ({:.2}, {:.2})", self.llr(sprt), lower, upper)),
+            PrintableMatchStatistic::new("verdict", String::from(self.get_verdict(sprt).name())),
+        ]);
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintableMatchStatistic {
+    pub statistic: String,
+    pub value: String,
+}
+
+impl PrintableMatchStatistic {
+    fn new(statistic: &str, value: String) -> Self {
+        return Self {
+            statistic: String::from(statistic),
+            value: value,
+        }
+    }
+}
+
+
+pub struct MatchGameResult {
+    pub round: usize,
+    pub white: String,
+    pub black: String,
+    pub opening: String,
+    pub result: GameResult,
+    pub termination: MatchTermination,
+    pub plies: usize,
+    pub duration: Duration,
+}
+
+
+pub struct MatchOptions {
+    pub games: usize,
+    pub openings: Vec<MatchOpening>,
+    pub adjudication: AdjudicationOptions,
+    pub sprt: SprtOptions,
+    pub stop_on_sprt: bool,
+    pub pgn_path: Option<String>,
+    pub tablebases: Option<Arc<Tablebases>>,
+    pub time_control: Option<TimeControl>,
+}
+
+impl MatchOptions {
+    pub fn new(games: usize) -> Self {
+        return Self {
+            games: games,
+            openings: Vec::from([MatchOpening::start_position()]),
+            adjudication: Default::default(),
+            sprt: Default::default(),
+            stop_on_sprt: false,
+            pgn_path: None,
+            tablebases: None,
+            time_control: None,
+        }
+    }
+}
+
+
+pub struct MatchRunner {}
+
+impl MatchRunner {
+    pub fn run<F>(engines: &[MatchEngine; 2], options: &MatchOptions, mut on_game: F) -> Result<MatchStatistics, InputError> where F: FnMut(&MatchGameResult, &MatchStatistics) {
+        let mut players = engines.clone().map(|mut e| {
+            e.options.tablebases = options.tablebases.clone().filter(|_| e.use_tablebases);
+            MatchPlayer::new(e)
+        });
+        if let Some(path) = &options.pgn_path {
+            write_pgn_file(path, &[], false)?;
+        }
+        let mut statistics = MatchStatistics::default();
+        for round in 0..options.games {
+            let opening = &options.openings[(round / 2) % options.openings.len()];
+            let engine_color = if round % 2 == 0 { Color::White } else { Color::Black };
+            let [first, second] = &mut players;
+            let (white, black) = match engine_color {
+                Color::White => (first, second),
+                Color::Black => (second, first),
+            };
+            white.start_game()?;
+            black.start_game()?;
+            let start = Instant::now();
+            let (game, result, termination) = Self::play_game(white, black, opening, options);
+            statistics.record(result, engine_color);
+            let game_result = MatchGameResult {
+                round: round + 1,
+                white: white.engine.name.clone(),
+                black: black.engine.name.clone(),
+                opening: opening.name.clone(),
+                result: result,
+                termination: termination,
+                plies: game.get_moves().len() - opening.moves.len(),
+                duration: start.elapsed(),
+            };
+            if let Some(path) = &options.pgn_path {
+                write_pgn_file(path, &[Self::to_pgn(&game, &game_result)], true)?;
+            }
+            on_game(&game_result, &statistics);
+            if options.stop_on_sprt && statistics.get_verdict(&options.sprt) != SprtVerdict::Continue {
+                break;
+            }
+        }
+        players.iter_mut().for_each(|p| p.stop());
+        return Ok(statistics);
+    }
+
+    fn play_game(white: &mut MatchPlayer, black: &mut MatchPlayer, opening: &MatchOpening, options: &MatchOptions) -> (Game, GameResult, MatchTermination) {
+        let mut game = opening.to_game();
+        game.set_time_control(options.time_control.clone());
+        game.start_clock();
+        let mut adjudicator = Adjudicator::new(options.adjudication);
+        loop {
+            if let Some((result, termination)) = game.get_outcome() {
+                return (game, result, MatchTermination::Rules(termination));
+            }
+            if options.adjudication.max_moves > 0 && game.get_board().state.move_number > options.adjudication.max_moves {
+                return (game, GameResult::Draw, MatchTermination::MoveLimit);
+            }
+            if let Some(result) = Self::probe_tablebases(&game, options) {
+                return (game, result, MatchTermination::TablebaseAdjudication);
+            }
+            let color = game.get_current_turn();
+            let player = match color {
+                Color::White => &mut *white,
+                Color::Black => &mut *black,
+            };
+            let (mov, score) = match player.choose_move(&game) {
+                Ok(m) => m,
+                Err(termination) => return (game, match color {
+                    Color::White => GameResult::BlackWins,
+                    Color::Black => GameResult::WhiteWins,
+                }, termination),
+            };
+            let move_number = game.get_board().state.move_number;
+            game.make_move(&mov);
+            if let Some((result, termination)) = adjudicator.record(score, move_number) {
+                return (game, result, termination);
+            }
+        }
+    }
+
+    fn probe_tablebases(game: &Game, options: &MatchOptions) -> Option<GameResult> {
+        let wdl = options.tablebases.as_ref()?.probe_wdl(game.get_board())?;
+        return Some(match (wdl, game.get_current_turn()) {
+            (WdlScore::Win, Color::White) | (WdlScore::Loss, Color::Black) => GameResult::WhiteWins,
+            (WdlScore::Win, Color::Black) | (WdlScore::Loss, Color::White) => GameResult::BlackWins,
+            _ => GameResult::Draw,
+        });
+    }
+
+    fn to_pgn(game: &Game, result: &MatchGameResult) -> PgnGame {
+        let mut pgn = PgnGame::from_game(game, Vec::from([
+            (String::from("Event"), String::from("rust_chess match")),
+            (String::from("Site"), String::from("local")),
+            (String::from("Round"), result.round.to_string()),
+            (String::from("White"), result.white.clone()),
+            (String::from("Black"), result.black.clone()),
+        ]), result.result);
+        pgn.set_tag("Opening", &result.opening);
+        pgn.set_tag("Termination", result.termination.get_pgn_termination());
+        return pgn;
+    }
+}
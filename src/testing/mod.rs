@@ -1,2 +1,7 @@
+pub mod bench;
+pub mod epd;
+pub mod matches;
+pub mod movegen;
 pub mod perft;
+pub mod perft_suite;
 pub mod zobrist;
\ No newline at end of file
@@ -0,0 +1,218 @@
+use std::time::{Duration, Instant};
+
+use crate::{rules::{board::{Board, move_list::{GenerationStage, MoveList}}, pieces::movement::Move}, testing::perft_suite::PerftSuitePosition, util::notation::get_uci_notation_for_move};
+
+
+static MAX_REPORTED_MISMATCHES: usize = 10;
+
+
+#[derive(Clone)]
+pub struct StagedGenerationMismatch {
+    pub fen: String,
+    pub stage: GenerationStage,
+    pub missing: Vec<Move>,
+    pub unexpected: Vec<Move>,
+}
+
+impl StagedGenerationMismatch {
+    pub fn describe(&self) -> String {
+        let notate = |moves: &Vec<Move>| moves.iter().map(get_uci_notation_for_move).collect::<Vec<String>>().join(" ");
+        return format!("{:?} generator at {} is missing [{}] and has unexpected [{}]", self.stage, self.fen, notate(&self.missing), notate(&self.unexpected));
+    }
+}
+
+
+#[derive(Default)]
+pub struct StagedGenerationTestContext {
+    positions_checked: u64,
+    captures: u64,
+    quiets: u64,
+    evasions: u64,
+    mismatch_count: u64,
+    mismatches: Vec<StagedGenerationMismatch>,
+    start: Option<Instant>,
+}
+
+impl StagedGenerationTestContext {
+    pub fn init(&mut self) {
+        self.start = Some(Instant::now())
+    }
+
+    fn compare(&mut self, board: &Board, stage: GenerationStage, expected: &[Move], actual: &[Move]) {
+        let missing: Vec<Move> = expected.iter().filter(|m| !actual.contains(m)).copied().collect();
+        let unexpected: Vec<Move> = actual.iter().filter(|m| !expected.contains(m)).copied().collect();
+        if missing.is_empty() && unexpected.is_empty() && expected.len() == actual.len() { return }
+        self.mismatch_count += 1;
+        if self.mismatches.len() < MAX_REPORTED_MISMATCHES {
+            self.mismatches.push(StagedGenerationMismatch {
+                fen: board.to_fen(),
+                stage: stage,
+                missing: missing,
+                unexpected: unexpected,
+            });
+        }
+    }
+
+    pub fn process(&mut self, board: &Board, moves: &MoveList) {
+        self.positions_checked += 1;
+        let mut staged = MoveList::new();
+
+        board.generate_captures(&mut staged);
+        self.captures += staged.len() as u64;
+        let expected: Vec<Move> = moves.iter().filter(|m| m.get_capture().is_some() || matches!(m, Move::Promotion(_))).copied().collect();
+        self.compare(board, GenerationStage::Captures, &expected, &staged);
+
+        board.generate_quiet_moves(&mut staged);
+        self.quiets += staged.len() as u64;
+        let expected: Vec<Move> = moves.iter().filter(|m| m.get_capture().is_none() && !matches!(m, Move::Promotion(_))).copied().collect();
+        self.compare(board, GenerationStage::Quiets, &expected, &staged);
+
+        board.generate_check_evasions(&mut staged);
+        self.evasions += staged.len() as u64;
+        let expected: &[Move] = if board.in_check() { moves } else { &[] };
+        self.compare(board, GenerationStage::Evasions, expected, &staged);
+    }
+
+    pub fn complete(&self) -> StagedGenerationTestResult {
+        return StagedGenerationTestResult {
+            positions_checked: self.positions_checked,
+            captures: self.captures,
+            quiets: self.quiets,
+            evasions: self.evasions,
+            mismatch_count: self.mismatch_count,
+            mismatches: self.mismatches.clone(),
+            duration: self.start.expect("Staged Generation Test Context completed before it was initialized!").elapsed(),
+        }
+    }
+}
+
+
+pub struct StagedGenerationTestResult {
+    pub positions_checked: u64,
+    pub captures: u64,
+    pub quiets: u64,
+    pub evasions: u64,
+    pub mismatch_count: u64,
+    pub mismatches: Vec<StagedGenerationMismatch>,
+    pub duration: Duration,
+}
+
+impl StagedGenerationTestResult {
+    pub fn passed(&self) -> bool {
+        return self.mismatch_count == 0;
+    }
+}
+
+
+pub struct StagedGenerationTester {}
+
+impl StagedGenerationTester {
+    pub fn do_test(board: Board, depth: u8) -> StagedGenerationTestResult {
+        let mut ctx: StagedGenerationTestContext = Default::default();
+        ctx.init();
+        Self::staged_generation_test(board, depth, &mut ctx);
+        return ctx.complete();
+    }
+
+    pub fn do_suite_test(suites: &[&[PerftSuitePosition]], depth: u8) -> StagedGenerationTestResult {
+        let mut ctx: StagedGenerationTestContext = Default::default();
+        ctx.init();
+        for position in suites.iter().flat_map(|suite| suite.iter()) {
+            let board = Board::from_fen(position.fen).expect("Perft suite FEN is invalid");
+            Self::staged_generation_test(board, depth, &mut ctx);
+        }
+        return ctx.complete();
+    }
+
+    fn staged_generation_test(board: Board, depth: u8, ctx: &mut StagedGenerationTestContext) {
+        let mut moves = MoveList::new();
+        board.generate_legal_moves(&mut moves);
+        ctx.process(&board, &moves);
+        if depth == 0 { return }
+        for mov in &moves {
+            let mut new_board = board;
+            new_board.make_move(mov);
+            Self::staged_generation_test(new_board, depth - 1, ctx);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::perft_suite::{CHESS960_PERFT_SUITE, PERFT_SUITE};
+
+    use super::*;
+
+    static TEST_DEPTH: u8 = 2;
+
+    fn assert_stages_partition_legal_moves(board: Board, depth: u8) {
+        let legal = board.get_legal_moves();
+        let mut staged = MoveList::new();
+        board.generate_captures(&mut staged);
+        let captures = staged.to_vec();
+        board.generate_quiet_moves(&mut staged);
+        let quiets = staged.to_vec();
+        board.generate_check_evasions(&mut staged);
+        let evasions = staged.to_vec();
+        let fen = board.to_fen();
+        assert!(captures.iter().all(|m| !quiets.contains(m)), "Captures and quiets overlap at {}", fen);
+        assert_eq!(captures.len() + quiets.len(), legal.len(), "Captures and quiets do not add up to the legal moves at {}", fen);
+        assert!(legal.iter().all(|m| captures.contains(m) || quiets.contains(m)), "Captures and quiets are missing legal moves at {}", fen);
+        match board.in_check() {
+            true => assert!(evasions.len() == legal.len() && legal.iter().all(|m| evasions.contains(m)), "Evasions differ from the legal moves at {}", fen),
+            false => assert!(evasions.is_empty(), "Evasions generated out of check at {}", fen),
+        }
+        if depth == 0 { return }
+        for mov in &legal {
+            let mut new_board = board;
+            new_board.make_move(mov);
+            assert_stages_partition_legal_moves(new_board, depth - 1);
+        }
+    }
+
+    fn assert_legality_predicates_match_generation(board: Board, depth: u8) {
+        let legal = board.get_legal_moves();
+        let mut pseudo_legal = MoveList::new();
+        board.generate_pseudo_legal_moves(&mut pseudo_legal);
+        let filtered: Vec<Move> = pseudo_legal.iter().filter(|m| board.is_legal(m)).copied().collect();
+        let fen = board.to_fen();
+        assert!(filtered.len() == legal.len() && legal.iter().all(|m| filtered.contains(m)),
+            "Pseudo-legal moves filtered by is_legal differ from the legal moves at {}: [{}] vs [{}]", fen,
+            filtered.iter().map(get_uci_notation_for_move).collect::<Vec<String>>().join(" "),
+            legal.iter().map(get_uci_notation_for_move).collect::<Vec<String>>().join(" "));
+        for mov in &legal {
+            let mut new_board = board;
+            new_board.make_move(mov);
+            assert_eq!(board.gives_check(mov), new_board.in_check(), "gives_check is wrong for {} at {}", get_uci_notation_for_move(mov), fen);
+            if depth > 0 {
+                assert_legality_predicates_match_generation(new_board, depth - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn staged_generation_has_no_mismatches_on_suite_positions() {
+        let result = StagedGenerationTester::do_suite_test(&[&PERFT_SUITE, &CHESS960_PERFT_SUITE], TEST_DEPTH);
+        assert!(result.positions_checked > 0);
+        for stage in [GenerationStage::Captures, GenerationStage::Quiets, GenerationStage::Evasions] {
+            let failures: Vec<String> = result.mismatches.iter().filter(|m| m.stage == stage).map(|m| m.describe()).collect();
+            assert!(failures.is_empty(), "{}", failures.join("\n"));
+        }
+        assert_eq!(result.mismatch_count, 0);
+    }
+
+    #[test]
+    fn staged_generation_partitions_legal_moves_on_suite_positions() {
+        for position in PERFT_SUITE.iter().chain(CHESS960_PERFT_SUITE.iter()) {
+            assert_stages_partition_legal_moves(Board::from_fen(position.fen).unwrap(), TEST_DEPTH);
+        }
+    }
+
+    #[test]
+    fn legality_predicates_match_generation_on_suite_positions() {
+        for position in PERFT_SUITE.iter() {
+            assert_legality_predicates_match_generation(Board::from_fen(position.fen).unwrap(), TEST_DEPTH);
+        }
+    }
+}
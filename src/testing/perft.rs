@@ -1,8 +1,10 @@
+use std::time::{Duration, Instant};
+
 use crossbeam::channel::unbounded;
 use num_format::{ToFormattedString, Locale};
 use tabled::Tabled;
 
-use crate::{rules::{board::Board, pieces::movement::{Move, NullMove}}, util::concurrency::{pools::ThreadPool, tasks::Task}};
+use crate::{rules::{board::{Board, move_list::MoveList}, pieces::movement::{Move, NullMove}}, util::{concurrency::{pools::ThreadPool, tasks::Task}, notation::get_uci_notation_for_move, zobrist::ZobristHashMap}};
 
 
 enum PerftType {
@@ -12,6 +14,9 @@ enum PerftType {
     Castles,
     Promotions,
     Checks,
+    DiscoveredChecks,
+    DoubleChecks,
+    Checkmates,
 }
 
 
@@ -27,12 +32,15 @@ impl Perft {
         }
         let mut analysis_level = self.levels.iter_mut().nth(level as usize).unwrap();
         match analysis_type {
-            PerftType::Size       => analysis_level.size += 1,
-            PerftType::Captures   => analysis_level.captures += 1,
-            PerftType::EnPassants => analysis_level.en_passants += 1,
-            PerftType::Castles    => analysis_level.castles += 1,
-            PerftType::Promotions => analysis_level.promotions += 1,
-            PerftType::Checks     => analysis_level.checks += 1,
+            PerftType::Size             => analysis_level.size += 1,
+            PerftType::Captures         => analysis_level.captures += 1,
+            PerftType::EnPassants       => analysis_level.en_passants += 1,
+            PerftType::Castles          => analysis_level.castles += 1,
+            PerftType::Promotions       => analysis_level.promotions += 1,
+            PerftType::Checks           => analysis_level.checks += 1,
+            PerftType::DiscoveredChecks => analysis_level.discovered_checks += 1,
+            PerftType::DoubleChecks     => analysis_level.double_checks += 1,
+            PerftType::Checkmates       => analysis_level.checkmates += 1,
         };
     }
 
@@ -48,6 +56,18 @@ impl Perft {
         self.create_and_increment(level, PerftType::Checks);
     }
 
+    pub fn increment_discovered_checks(&mut self, level: u8) {
+        self.create_and_increment(level, PerftType::DiscoveredChecks);
+    }
+
+    pub fn increment_double_checks(&mut self, level: u8) {
+        self.create_and_increment(level, PerftType::DoubleChecks);
+    }
+
+    pub fn increment_checkmates(&mut self, level: u8) {
+        self.create_and_increment(level, PerftType::Checkmates);
+    }
+
     pub fn increment_en_passants(&mut self, level: u8) {
         self.create_and_increment(level, PerftType::EnPassants);
     }
@@ -77,22 +97,28 @@ impl Perft {
 
 #[derive(Default)]
 pub struct LevelPerft {
-    pub size: u32,
-    pub captures: u32,
-    pub en_passants: u32,
-    pub castles: u32,
-    pub promotions: u32,
-    pub checks: u32,
+    pub size: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
 }
 
 impl LevelPerft {
     pub fn merge(&mut self, other: &Self) {
-        self.size        += other.size;
-        self.captures    += other.captures;
-        self.en_passants += other.en_passants;
-        self.castles     += other.castles;
-        self.promotions  += other.promotions;
-        self.checks      += other.checks;
+        self.size              += other.size;
+        self.captures          += other.captures;
+        self.en_passants       += other.en_passants;
+        self.castles           += other.castles;
+        self.promotions        += other.promotions;
+        self.checks            += other.checks;
+        self.discovered_checks += other.discovered_checks;
+        self.double_checks     += other.double_checks;
+        self.checkmates        += other.checkmates;
     }
 }
 
@@ -105,6 +131,9 @@ pub struct PrintablePerft {
     pub castles: String,
     pub promotions: String,
     pub checks: String,
+    pub discovered_checks: String,
+    pub double_checks: String,
+    pub checkmates: String,
 }
 
 impl PrintablePerft {
@@ -116,6 +145,74 @@ impl PrintablePerft {
             castles: level.castles.to_formatted_string(&Locale::en),
             promotions: level.promotions.to_formatted_string(&Locale::en),
             checks: level.checks.to_formatted_string(&Locale::en),
+            discovered_checks: level.discovered_checks.to_formatted_string(&Locale::en),
+            double_checks: level.double_checks.to_formatted_string(&Locale::en),
+            checkmates: level.checkmates.to_formatted_string(&Locale::en),
+        }
+    }
+}
+
+
+#[derive(Default)]
+pub struct FastPerft {
+    pub nodes: u64,
+    pub hash_probes: u64,
+    pub hash_hits: u64,
+    pub duration: Duration,
+}
+
+impl FastPerft {
+    pub fn nodes_per_second(&self) -> u64 {
+        return match self.duration.as_secs_f64() {
+            s if s > 0f64 => (self.nodes as f64 / s) as u64,
+            _ => 0,
+        }
+    }
+}
+
+
+#[derive(Default)]
+struct PerftHashTable {
+    levels: Vec<ZobristHashMap<u64>>,
+    probes: u64,
+    hits: u64,
+}
+
+impl PerftHashTable {
+    fn get(&mut self, id: u64, depth: u8) -> Option<u64> {
+        self.probes += 1;
+        let result = self.levels.get(depth as usize).and_then(|level| level.get(&id)).copied();
+        if result.is_some() { self.hits += 1 }
+        return result;
+    }
+
+    fn insert(&mut self, id: u64, depth: u8, nodes: u64) {
+        while self.levels.len() <= depth as usize {
+            self.levels.push(Default::default());
+        }
+        self.levels[depth as usize].insert(id, nodes);
+    }
+}
+
+
+pub struct PerftDivide {
+    pub mov: Move,
+    pub nodes: u64,
+}
+
+
+#[derive(Tabled)]
+pub struct PrintablePerftDivide {
+    #[header("move")]
+    pub mov: String,
+    pub nodes: String,
+}
+
+impl PrintablePerftDivide {
+    pub fn from_divide(divide: &PerftDivide) -> Self {
+        return Self {
+            mov: get_uci_notation_for_move(&divide.mov),
+            nodes: divide.nodes.to_formatted_string(&Locale::en),
         }
     }
 }
@@ -178,11 +275,105 @@ impl PerftRunner {
     }
 
 
+    pub fn do_divide(board: Board, depth: u8) -> Vec<PerftDivide> {
+        let mut result: Vec<PerftDivide> = board.get_legal_moves().iter().map(|mov| {
+            let mut next_board = board;
+            next_board.make_move(mov);
+            PerftDivide {
+                mov: *mov,
+                nodes: Self::count_nodes(next_board, depth.saturating_sub(1)),
+            }
+        }).collect();
+        result.sort_by_key(|d| get_uci_notation_for_move(&d.mov));
+        return result;
+    }
+
+
+    pub fn do_fast_perft(board: Board, depth: u8, hashed: bool) -> FastPerft {
+        let start = Instant::now();
+        let mut result: FastPerft = Default::default();
+        if hashed {
+            let mut table: PerftHashTable = Default::default();
+            result.nodes = Self::count_nodes_hashed(board, depth, &mut table);
+            result.hash_probes = table.probes;
+            result.hash_hits = table.hits;
+        } else {
+            result.nodes = Self::count_nodes(board, depth);
+        }
+        result.duration = start.elapsed();
+        return result;
+    }
+
+
+    pub fn count_nodes(board: Board, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = MoveList::new();
+        board.generate_legal_moves(&mut moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        return moves.iter().map(|mov| {
+            let mut next_board = board;
+            next_board.make_move(mov);
+            Self::count_nodes(next_board, depth - 1)
+        }).sum();
+    }
+
+
+    pub fn count_nodes_with_vec(board: Board, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = board.get_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        return moves.iter().map(|mov| {
+            let mut next_board = board;
+            next_board.make_move(mov);
+            Self::count_nodes_with_vec(next_board, depth - 1)
+        }).sum();
+    }
+
+
+    fn count_nodes_hashed(board: Board, depth: u8, table: &mut PerftHashTable) -> u64 {
+        if depth <= 1 {
+            return Self::count_nodes(board, depth);
+        }
+        let id = board.zobrist.get_id();
+        if let Some(nodes) = table.get(id, depth) {
+            return nodes;
+        }
+        let mut moves = MoveList::new();
+        board.generate_legal_moves(&mut moves);
+        let nodes = moves.iter().map(|mov| {
+            let mut next_board = board;
+            next_board.make_move(mov);
+            Self::count_nodes_hashed(next_board, depth - 1, table)
+        }).sum();
+        table.insert(id, depth, nodes);
+        return nodes;
+    }
+
+
     fn perft(ctx: PerftContext) -> Perft {
         let mut result: Perft = Default::default();
         result.increment_size(ctx.depth);
         if ctx.board.in_check() {
             result.increment_checks(ctx.depth);
+            let checks = ctx.board.get_checks();
+            if checks.attacks.len() > 1 {
+                result.increment_double_checks(ctx.depth);
+            } else if checks.attacks.iter().any(|a| !ctx.last_move.iter_piece_movements().any(|m| m.end_square == a.attacking_square)) {
+                result.increment_discovered_checks(ctx.depth);
+            }
+            let mut responses = MoveList::new();
+            ctx.board.generate_legal_moves(&mut responses);
+            if responses.is_empty() {
+                result.increment_checkmates(ctx.depth);
+            }
         }
         match ctx.last_move.get_capture() {
             Some(_) => result.increment_captures(ctx.depth),
@@ -197,9 +388,10 @@ impl PerftRunner {
         if ctx.depth <= 0 {
             return result;
         }
-        let moves = ctx.board.get_legal_moves();
-        for next_move in moves {
-            let next_ctx = ctx.clone_from_move(&next_move);
+        let mut moves = MoveList::new();
+        ctx.board.generate_legal_moves(&mut moves);
+        for next_move in &moves {
+            let next_ctx = ctx.clone_from_move(next_move);
             result.merge(&Self::perft(next_ctx));
         }
         return result
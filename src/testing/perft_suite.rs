@@ -0,0 +1,255 @@
+use std::time::{Duration, Instant};
+
+use num_format::{ToFormattedString, Locale};
+use tabled::Tabled;
+
+use crate::{rules::board::Board, testing::perft::PerftRunner};
+
+
+pub struct PerftSuitePosition {
+    pub name: &'static str,
+    pub fen: &'static str,
+    pub expected: &'static [(u8, u64)],
+}
+
+
+pub static PERFT_SUITE: [PerftSuitePosition; 20] = [
+    PerftSuitePosition {
+        name: "Initial position",
+        fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        expected: &[(1, 20), (2, 400), (3, 8_902), (4, 197_281), (5, 4_865_609)],
+    },
+    PerftSuitePosition {
+        name: "Kiwipete",
+        fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        expected: &[(1, 48), (2, 2_039), (3, 97_862), (4, 4_085_603)],
+    },
+    PerftSuitePosition {
+        name: "Position 3",
+        fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        expected: &[(1, 14), (2, 191), (3, 2_812), (4, 43_238), (5, 674_624)],
+    },
+    PerftSuitePosition {
+        name: "Position 4",
+        fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        expected: &[(1, 6), (2, 264), (3, 9_467), (4, 422_333)],
+    },
+    PerftSuitePosition {
+        name: "Position 4 (mirrored)",
+        fen: "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        expected: &[(1, 6), (2, 264), (3, 9_467), (4, 422_333)],
+    },
+    PerftSuitePosition {
+        name: "Position 5",
+        fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        expected: &[(1, 44), (2, 1_486), (3, 62_379), (4, 2_103_487)],
+    },
+    PerftSuitePosition {
+        name: "Position 6",
+        fen: "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        expected: &[(1, 46), (2, 2_079), (3, 89_890), (4, 3_894_594)],
+    },
+    PerftSuitePosition {
+        name: "Illegal en passant (pinned pawn)",
+        fen: "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+        expected: &[(6, 1_134_888)],
+    },
+    PerftSuitePosition {
+        name: "Illegal en passant (diagonal pin)",
+        fen: "8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1",
+        expected: &[(6, 824_064)],
+    },
+    PerftSuitePosition {
+        name: "En passant capture gives check",
+        fen: "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+        expected: &[(6, 1_440_467)],
+    },
+    PerftSuitePosition {
+        name: "Short castling gives check",
+        fen: "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+        expected: &[(6, 661_072)],
+    },
+    PerftSuitePosition {
+        name: "Long castling gives check",
+        fen: "3k4/8/8/8/8/8/8/R3K3 w Q - 0 1",
+        expected: &[(6, 803_711)],
+    },
+    PerftSuitePosition {
+        name: "Castling rights",
+        fen: "r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1",
+        expected: &[(4, 1_274_206)],
+    },
+    PerftSuitePosition {
+        name: "Castling prevented",
+        fen: "r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1",
+        expected: &[(4, 1_720_476)],
+    },
+    PerftSuitePosition {
+        name: "Promote out of check",
+        fen: "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+        expected: &[(6, 3_821_001)],
+    },
+    PerftSuitePosition {
+        name: "Discovered check",
+        fen: "8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1",
+        expected: &[(5, 1_004_658)],
+    },
+    PerftSuitePosition {
+        name: "Promote to give check",
+        fen: "4k3/1P6/8/8/8/8/K7/8 w - - 0 1",
+        expected: &[(6, 217_342)],
+    },
+    PerftSuitePosition {
+        name: "Underpromote to give check",
+        fen: "8/P1k5/K7/8/8/8/8/8 w - - 0 1",
+        expected: &[(6, 92_683)],
+    },
+    PerftSuitePosition {
+        name: "Self stalemate",
+        fen: "K1k5/8/P7/8/8/8/8/8 w - - 0 1",
+        expected: &[(6, 2_217)],
+    },
+    PerftSuitePosition {
+        name: "Stalemate and checkmate",
+        fen: "8/k1P5/8/1K6/8/8/8/8 w - - 0 1",
+        expected: &[(7, 567_584)],
+    },
+];
+
+
+pub static CHESS960_PERFT_SUITE: [PerftSuitePosition; 8] = [
+    PerftSuitePosition {
+        name: "Chess960 position 1",
+        fen: "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        expected: &[(1, 21), (2, 528), (3, 12_189), (4, 326_672), (5, 8_146_062)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 2",
+        fen: "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        expected: &[(1, 21), (2, 807), (3, 18_002), (4, 667_366)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 3",
+        fen: "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        expected: &[(1, 20), (2, 479), (3, 10_471), (4, 273_318), (5, 6_417_013)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 4",
+        fen: "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        expected: &[(1, 22), (2, 593), (3, 13_440), (4, 382_958), (5, 9_183_776)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 5",
+        fen: "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        expected: &[(1, 28), (2, 1_120), (3, 31_058), (4, 1_171_749)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 6",
+        fen: "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+        expected: &[(1, 29), (2, 899), (3, 26_578), (4, 824_055)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 7",
+        fen: "q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9",
+        expected: &[(1, 30), (2, 860), (3, 24_566), (4, 732_757)],
+    },
+    PerftSuitePosition {
+        name: "Chess960 position 8",
+        fen: "qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9",
+        expected: &[(1, 25), (2, 635), (3, 17_054), (4, 465_806)],
+    },
+];
+
+
+pub struct PerftSuiteResult {
+    pub name: &'static str,
+    pub depth: u8,
+    pub expected: u64,
+    pub actual: u64,
+    pub duration: Duration,
+}
+
+impl PerftSuiteResult {
+    pub fn passed(&self) -> bool {
+        return self.expected == self.actual;
+    }
+}
+
+
+#[derive(Tabled)]
+pub struct PrintablePerftSuiteResult {
+    pub position: String,
+    pub depth: String,
+    pub expected: String,
+    pub actual: String,
+    pub result: String,
+    pub time: String,
+}
+
+impl PrintablePerftSuiteResult {
+    pub fn from_result(result: &PerftSuiteResult) -> Self {
+        return Self {
+            position: String::from(result.name),
+            depth: result.depth.to_string(),
+            expected: result.expected.to_formatted_string(&Locale::en),
+            actual: result.actual.to_formatted_string(&Locale::en),
+            result: String::from(if result.passed() { "pass" } else { "FAIL" }),
+            time: format!("{:?}", result.duration),
+        }
+    }
+}
+
+
+pub struct PerftSuiteRunner {}
+
+impl PerftSuiteRunner {
+    pub fn run_suite<F>(suite: &[PerftSuitePosition], max_depth: Option<u8>, mut on_result: F) -> Vec<PerftSuiteResult> where F: FnMut(&PerftSuiteResult) {
+        let mut results = Vec::new();
+        for position in suite.iter() {
+            let board = Board::from_fen(position.fen).expect("Perft suite FEN is invalid");
+            for (depth, expected) in position.expected.iter().filter(|(d, _)| max_depth.is_none_or(|max| *d <= max)) {
+                let start = Instant::now();
+                let result = PerftSuiteResult {
+                    name: position.name,
+                    depth: *depth,
+                    expected: *expected,
+                    actual: PerftRunner::count_nodes(board, *depth),
+                    duration: start.elapsed(),
+                };
+                on_result(&result);
+                results.push(result);
+            }
+        }
+        return results;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_DEPTH: u8 = 3;
+
+    fn assert_suite_passes(suite: &[PerftSuitePosition]) {
+        let mut results = Vec::new();
+        for position in suite.iter() {
+            let shallowest = position.expected.iter().map(|(d, _)| *d).min().unwrap();
+            results.extend(PerftSuiteRunner::run_suite(std::slice::from_ref(position), Some(shallowest.max(TEST_DEPTH)), |_| ()));
+        }
+        assert_eq!(results.iter().map(|r| r.name).collect::<std::collections::HashSet<_>>().len(), suite.len());
+        let failures: Vec<String> = results.iter().filter(|r| !r.passed())
+            .map(|r| format!("{} at depth {}: expected {}, found {}", r.name, r.depth, r.expected, r.actual)).collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn standard_suite_matches_expected_counts() {
+        assert_suite_passes(&PERFT_SUITE);
+    }
+
+    #[test]
+    fn chess960_suite_matches_expected_counts() {
+        assert_suite_passes(&CHESS960_PERFT_SUITE);
+    }
+}
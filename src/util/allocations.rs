@@ -0,0 +1,73 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    enabled: AtomicBool::new(false),
+    allocations: AtomicU64::new(0),
+    bytes: AtomicU64::new(0),
+};
+
+
+pub struct CountingAllocator {
+    enabled: AtomicBool,
+    allocations: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl CountingAllocator {
+    fn record(&self, size: usize) {
+        if self.enabled.load(Ordering::Relaxed) {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            self.bytes.fetch_add(size as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.record(layout.size());
+        return System.alloc(layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.record(layout.size());
+        return System.alloc_zeroed(layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.record(new_size);
+        return System.realloc(ptr, layout, new_size);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+
+#[derive(Copy, Clone, Default)]
+pub struct AllocationCount {
+    pub allocations: u64,
+    pub bytes: u64,
+}
+
+impl AllocationCount {
+    pub fn per_node(&self, nodes: u64) -> f64 {
+        return self.allocations as f64 / nodes.max(1) as f64;
+    }
+}
+
+
+pub fn count_allocations<T, F>(f: F) -> (T, AllocationCount) where F: FnOnce() -> T {
+    ALLOCATOR.allocations.store(0, Ordering::Relaxed);
+    ALLOCATOR.bytes.store(0, Ordering::Relaxed);
+    ALLOCATOR.enabled.store(true, Ordering::Relaxed);
+    let result = f();
+    ALLOCATOR.enabled.store(false, Ordering::Relaxed);
+    return (result, AllocationCount {
+        allocations: ALLOCATOR.allocations.load(Ordering::Relaxed),
+        bytes: ALLOCATOR.bytes.load(Ordering::Relaxed),
+    });
+}
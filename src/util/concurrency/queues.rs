@@ -1,4 +1,4 @@
-use std::{collections::VecDeque, hash::Hash, thread::{self, Thread}};
+use std::{collections::VecDeque, hash::Hash, thread::{self, Thread}, time::Duration};
 
 use crossbeam::channel::{Sender, Receiver, unbounded, RecvError, TryRecvError, SendError};
 use fxhash::FxHashMap;
@@ -6,6 +6,9 @@ use fxhash::FxHashMap;
 use super::channels::{LifoSender, LifoReceiver, lifo_channel};
 
 
+static PARK_TIMEOUT: Duration = Duration::from_millis(1);
+
+
 pub enum QueueType {
     FIFO,
     LIFO,
@@ -244,14 +247,16 @@ impl<T> Clone for PriorityQueueReader<T> {
 
 impl<T> PriorityQueueReader<T> {
     pub fn dequeue(&self) -> Result<T, RecvError> {
+        let mut registered = false;
         loop {
             match self.try_dequeue() {
                 Ok(message) => return Ok(message),
                 Err(TryRecvError::Disconnected) => return Err(RecvError),
-                Err(TryRecvError::Empty) => {
+                Err(TryRecvError::Empty) if !registered => {
                     self.parked_threads.send(thread::current()).expect("Error parking PriorityQueueReader thread.");
-                    thread::park();
-                }
+                    registered = true;
+                },
+                Err(TryRecvError::Empty) => thread::park_timeout(PARK_TIMEOUT),
             }
         }
     }
@@ -0,0 +1,121 @@
+use std::{fs, collections::HashMap};
+
+use crate::rules::board::Board;
+
+use super::errors::InputError;
+
+
+static MAX_REPORTED_ERRORS: usize = 10;
+
+
+fn split_operations(input: &str) -> Result<Vec<Vec<String>>, InputError> {
+    let mut operations: Vec<Vec<String>> = Vec::new();
+    let mut tokens: Vec<String> = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if quoted {
+                    tokens.push(token.clone());
+                    token.clear();
+                }
+                quoted = !quoted;
+            },
+            ';' if !quoted => {
+                if !token.is_empty() { tokens.push(token.clone()) }
+                token.clear();
+                if !tokens.is_empty() { operations.push(tokens.clone()) }
+                tokens.clear();
+            },
+            c if c.is_whitespace() && !quoted => {
+                if !token.is_empty() { tokens.push(token.clone()) }
+                token.clear();
+            },
+            c => token.push(c),
+        }
+    }
+    if quoted {
+        return Err(InputError::new("Unterminated string operand in EPD operations"));
+    }
+    if !token.is_empty() { tokens.push(token) }
+    if !tokens.is_empty() { operations.push(tokens) }
+    return Ok(operations);
+}
+
+
+pub struct EpdRecord {
+    pub fen: String,
+    pub operations: HashMap<String, Vec<String>>,
+}
+
+impl EpdRecord {
+    pub fn from_epd(line: &str) -> Result<Self, InputError> {
+        let mut fields: Vec<&str> = Vec::new();
+        let mut remainder = line.trim();
+        for _ in 0..4 {
+            let (field, rest) = remainder.split_once(char::is_whitespace).unwrap_or((remainder, ""));
+            if field.is_empty() {
+                return Err(InputError::new(&format!("EPD record '{}' does not have the four required position fields", line.trim())));
+            }
+            fields.push(field);
+            remainder = rest.trim_start();
+        }
+        let mut operations: HashMap<String, Vec<String>> = HashMap::new();
+        for mut operation in split_operations(remainder)? {
+            let opcode = operation.remove(0);
+            operations.insert(opcode, operation);
+        }
+        let halfmove_clock = operations.get("hmvc").and_then(|o| o.first()).map_or("0", |s| s.as_str());
+        let move_number = operations.get("fmvn").and_then(|o| o.first()).map_or("1", |s| s.as_str());
+        let fen = format!("{} {} {}", fields[0..4].join(" "), halfmove_clock, move_number);
+        return Ok(Self {
+            fen: Board::from_fen(&fen)?.to_fen(),
+            operations: operations,
+        });
+    }
+
+    pub fn from_file(path: &str) -> Result<(Vec<Self>, Vec<String>), InputError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => return Err(InputError::new(&format!("Could not read EPD file {}: {}", path, e))),
+        };
+        let mut records: Vec<Self> = Vec::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (number, line) in contents.lines().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#')) {
+            match Self::from_epd(line) {
+                Ok(r) => records.push(r),
+                Err(e) if errors.len() < MAX_REPORTED_ERRORS => errors.push(format!("Line {}: {}", number + 1, e.msg)),
+                Err(_) => (),
+            }
+        }
+        return Ok((records, errors));
+    }
+
+    pub fn get_id(&self) -> Option<String> {
+        return self.operations.get("id").map(|o| o.join(" "));
+    }
+
+    pub fn get_best_moves(&self) -> Vec<String> {
+        return self.operations.get("bm").cloned().unwrap_or_default();
+    }
+
+    pub fn get_avoid_moves(&self) -> Vec<String> {
+        return self.operations.get("am").cloned().unwrap_or_default();
+    }
+
+    pub fn get_comment(&self) -> Option<String> {
+        return self.operations.get("c0").map(|o| o.join(" "));
+    }
+
+    pub fn get_move_points(&self) -> HashMap<String, u32> {
+        let comment = match self.get_comment() {
+            Some(c) => c,
+            None => return HashMap::new(),
+        };
+        return comment.split(',').filter_map(|pair| {
+            let (mov, points) = pair.trim().split_once('=')?;
+            Some((String::from(mov.trim()), points.trim().parse().ok()?))
+        }).collect();
+    }
+}
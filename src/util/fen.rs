@@ -1,9 +1,10 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use crate::rules::Color;
 
-use crate::rules::board::squares::BoardSquare;
-use crate::rules::pieces::{PieceType, Piece};
+use crate::rules::board::state::CastleRight;
+use crate::rules::board::squares::{BoardSquare, get_col_and_row_from_square};
+use crate::rules::pieces::{PieceType, Piece, movement::CastleType};
 
 use super::errors::InputError;
 
@@ -11,6 +12,30 @@ use super::errors::InputError;
 pub static STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 
+pub fn get_chess960_starting_position(index: u16) -> Result<String, InputError> {
+    if index >= 960 {
+        return Err(InputError::new(&format!("Chess960 starting position index must be between 0 and 959, found {}", index)));
+    }
+    let mut back_rank: [Option<char>; 8] = [None; 8];
+    let mut remainder = index as usize;
+    back_rank[remainder % 4 * 2 + 1] = Some('b');
+    remainder /= 4;
+    back_rank[remainder % 4 * 2] = Some('b');
+    remainder /= 4;
+    let mut empty_files: Vec<usize> = (0..8).filter(|f| back_rank[*f].is_none()).collect();
+    back_rank[empty_files.remove(remainder % 6)] = Some('q');
+    remainder /= 6;
+    let (first_knight, second_knight) = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)][remainder];
+    back_rank[empty_files.remove(second_knight)] = Some('n');
+    back_rank[empty_files.remove(first_knight)] = Some('n');
+    for (file, piece) in empty_files.into_iter().zip(['r', 'k', 'r']) {
+        back_rank[file] = Some(piece);
+    }
+    let black: String = back_rank.iter().map(|p| p.unwrap()).collect();
+    return Ok(format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black, black.to_uppercase()));
+}
+
+
 pub fn get_notation_for_piece(piece: Piece) -> char {
     let c = match piece.piece_type {
         PieceType::Pawn   => 'P',
@@ -55,19 +80,30 @@ fn get_notation_for_row(row: [Option<Piece>; 8]) -> String {
 }
 
 
-fn get_row_from_notation(fen: &str) -> [Option<Piece>; 8] {
+fn get_row_from_notation(fen: &str) -> Result<[Option<Piece>; 8], InputError> {
     let mut row: [Option<Piece>; 8] = Default::default();
     let mut index: usize = 0;
     for note in fen.chars() {
         match get_piece_for_notation(note) {
-            Ok(piece) => { row[index] = Some(piece); index += 1; },
+            Ok(piece) => {
+                if index >= 8 { return Err(InputError::new(&format!("FEN rank '{}' describes more than 8 squares", fen))) }
+                row[index] = Some(piece);
+                index += 1;
+            },
             Err(_e) => {
-                let empty_count = note.to_string().parse::<u8>().unwrap();
-                for _ in 1..=empty_count { row[index] = None; index += 1; }
+                let empty_count = match note.to_digit(10) {
+                    Some(d) if (1..=8).contains(&d) => d as usize,
+                    _ => return Err(InputError::new(&format!("FEN rank '{}' contains invalid character '{}'", fen, note))),
+                };
+                if index + empty_count > 8 { return Err(InputError::new(&format!("FEN rank '{}' describes more than 8 squares", fen))) }
+                index += empty_count;
             }
         }
     }
-    return row;
+    if index != 8 {
+        return Err(InputError::new(&format!("FEN rank '{}' describes {} squares instead of 8", fen, index)));
+    }
+    return Ok(row);
 }
 
 
@@ -76,12 +112,16 @@ pub fn get_notation_for_board(board: [[Option<Piece>; 8]; 8]) -> String {
 }
 
 
-fn get_board_from_notation(fen: &str) -> [[Option<Piece>; 8]; 8] {
+fn get_board_from_notation(fen: &str) -> Result<[[Option<Piece>; 8]; 8], InputError> {
     let mut board: [[Option<Piece>; 8]; 8] = Default::default();
-    for (index, row_string) in fen.split("/").enumerate() {
-        board[index] = get_row_from_notation(row_string);
+    let rows: Vec<&str> = fen.split("/").collect();
+    if rows.len() != 8 {
+        return Err(InputError::new(&format!("FEN piece placement has {} ranks instead of 8", rows.len())));
+    }
+    for (index, row_string) in rows.into_iter().enumerate() {
+        board[index] = get_row_from_notation(row_string)?;
     }
-    return board;
+    return Ok(board);
 }
 
 
@@ -93,35 +133,92 @@ fn get_notation_for_to_move(color: Color) -> String {
 }
 
 
-fn get_to_move_from_notation(fen: &str) -> Color {
+fn get_to_move_from_notation(fen: &str) -> Result<Color, InputError> {
     return match fen {
-        "w" => Color::White,
-        "b" => Color::Black,
-        _ => panic!("Invalid FEN string!"),
+        "w" => Ok(Color::White),
+        "b" => Ok(Color::Black),
+        x => Err(InputError::new(&format!("FEN side to move must be 'w' or 'b', found '{}'", x))),
+    }
+}
+
+
+fn get_back_rank(board: &[[Option<Piece>; 8]; 8], color: Color) -> [Option<Piece>; 8] {
+    return match color {
+        Color::White => board[7],
+        Color::Black => board[0],
+    }
+}
+
+
+fn find_back_rank_king(board: &[[Option<Piece>; 8]; 8], color: Color) -> Option<u8> {
+    let king = Some(Piece { color: color, piece_type: PieceType::King });
+    return get_back_rank(board, color).iter().position(|p| *p == king).map(|col| col as u8);
+}
+
+
+fn find_outermost_rook(board: &[[Option<Piece>; 8]; 8], color: Color, side: CastleType) -> Option<u8> {
+    let king_file = find_back_rank_king(board, color)?;
+    let rank = get_back_rank(board, color);
+    let rook = Some(Piece { color: color, piece_type: PieceType::Rook });
+    return match side {
+        CastleType::Kingside  => (king_file + 1..8).rev().find(|col| rank[*col as usize] == rook),
+        CastleType::Queenside => (0..king_file).find(|col| rank[*col as usize] == rook),
     }
 }
 
 
-fn get_notation_for_castling(castling: &Castling) -> String {
-    let pairs = [(castling.white_kingside, 'K'), (castling.white_queenside, 'Q'), (castling.black_kingside, 'k'), (castling.black_kingside, 'q')];
-    return match pairs.into_iter().filter_map(|(flag, note)| match flag { true => Some(note), false => None}).collect::<String>() {
+fn get_notation_for_castle_right(right: &CastleRight, file: u8, board: &[[Option<Piece>; 8]; 8]) -> char {
+    let note = match find_outermost_rook(board, right.color, right.side) == Some(file) {
+        true => match right.side {
+            CastleType::Kingside  => 'K',
+            CastleType::Queenside => 'Q',
+        },
+        false => (b'A' + file) as char,
+    };
+    return if right.color == Color::White { note } else { note.to_ascii_lowercase() };
+}
+
+
+fn get_notation_for_castling(castling: &Castling, board: &[[Option<Piece>; 8]; 8]) -> String {
+    return match Castling::RIGHTS.iter().filter_map(|right| {
+        castling.get_file(right).map(|file| get_notation_for_castle_right(right, file, board))
+    }).collect::<String>() {
         x if x.is_empty() => String::from("-"),
         y => y,
     }
 }
 
 
-fn get_castling_from_notation(fen: &str) -> Castling {
+fn get_castling_from_notation(fen: &str, board: &[[Option<Piece>; 8]; 8]) -> Result<Castling, InputError> {
+    let mut castling = Castling::none();
     if fen.eq("-") {
-        return Castling { white_kingside: false, white_queenside: false, black_kingside: false, black_queenside: false }
+        return Ok(castling);
     }
-    let chars: HashSet<char> = fen.chars().collect();
-    return Castling {
-        white_kingside : chars.contains(&'K'),
-        white_queenside: chars.contains(&'Q'),
-        black_kingside : chars.contains(&'k'),
-        black_queenside: chars.contains(&'q'),
+    for note in fen.chars() {
+        let color = if note.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let king_file = match find_back_rank_king(board, color) {
+            Some(f) => f,
+            None => return Err(InputError::new(&format!("FEN castling right '{}' requires the {} king on its back rank", note, color.value()))),
+        };
+        let (side, file) = match note.to_ascii_lowercase() {
+            'k' => (CastleType::Kingside, find_outermost_rook(board, color, CastleType::Kingside)),
+            'q' => (CastleType::Queenside, find_outermost_rook(board, color, CastleType::Queenside)),
+            f @ 'a'..='h' => {
+                let file = f as u8 - b'a';
+                (if file > king_file { CastleType::Kingside } else { CastleType::Queenside }, Some(file))
+            },
+            _ => return Err(InputError::new(&format!("FEN castling availability '{}' contains invalid character '{}'", fen, note))),
+        };
+        let right = CastleRight { color: color, side: side };
+        match file {
+            None => return Err(InputError::new(&format!("FEN castling right '{}' has no {} rook to castle with", note, color.value()))),
+            Some(_) if castling.get_file(&right).is_some() => {
+                return Err(InputError::new(&format!("FEN castling availability '{}' lists the same right more than once", fen)));
+            },
+            Some(f) => castling.set_file(&right, f),
+        }
     }
+    return Ok(castling);
 }
 
 
@@ -132,17 +229,57 @@ fn get_notation_for_en_passant(square: Option<BoardSquare>) -> String {
     }
 }
 
-fn get_en_passant_from_notation(fen: &str) -> Option<BoardSquare> {
-    if fen.eq("-") { return None };
-    return Some(BoardSquare::from_notation(fen));
+fn get_en_passant_from_notation(fen: &str) -> Result<Option<BoardSquare>, InputError> {
+    if fen.eq("-") { return Ok(None) };
+    let chars: Vec<char> = fen.chars().collect();
+    if chars.len() != 2 || !('a'..='h').contains(&chars[0]) || !(chars[1] == '3' || chars[1] == '6') {
+        return Err(InputError::new(&format!("FEN en passant target '{}' is not a square on the third or sixth rank", fen)));
+    }
+    return Ok(Some(BoardSquare::from_notation(fen)));
+}
+
+
+fn parse_fen_number<T: std::str::FromStr>(fen: &str, name: &str) -> Result<T, InputError> {
+    return fen.parse::<T>().map_err(|_| InputError::new(&format!("FEN {} '{}' is not a valid number", name, fen)));
 }
 
 
 pub struct Castling {
-    pub white_kingside: bool,
-    pub white_queenside: bool,
-    pub black_kingside: bool,
-    pub black_queenside: bool,
+    pub white_kingside: Option<u8>,
+    pub white_queenside: Option<u8>,
+    pub black_kingside: Option<u8>,
+    pub black_queenside: Option<u8>,
+}
+
+impl Castling {
+    pub const RIGHTS: [CastleRight; 4] = [
+        CastleRight { color: Color::White, side: CastleType::Kingside  },
+        CastleRight { color: Color::White, side: CastleType::Queenside },
+        CastleRight { color: Color::Black, side: CastleType::Kingside  },
+        CastleRight { color: Color::Black, side: CastleType::Queenside },
+    ];
+
+    pub fn none() -> Self {
+        return Self { white_kingside: None, white_queenside: None, black_kingside: None, black_queenside: None };
+    }
+
+    pub fn get_file(&self, right: &CastleRight) -> Option<u8> {
+        return match (right.color, right.side) {
+            (Color::White, CastleType::Kingside)  => self.white_kingside,
+            (Color::White, CastleType::Queenside) => self.white_queenside,
+            (Color::Black, CastleType::Kingside)  => self.black_kingside,
+            (Color::Black, CastleType::Queenside) => self.black_queenside,
+        }
+    }
+
+    pub fn set_file(&mut self, right: &CastleRight, file: u8) {
+        match (right.color, right.side) {
+            (Color::White, CastleType::Kingside)  => self.white_kingside  = Some(file),
+            (Color::White, CastleType::Queenside) => self.white_queenside = Some(file),
+            (Color::Black, CastleType::Kingside)  => self.black_kingside  = Some(file),
+            (Color::Black, CastleType::Queenside) => self.black_queenside = Some(file),
+        }
+    }
 }
 
 
@@ -152,28 +289,89 @@ pub struct FenBoardState {
     pub castling: Castling,
     pub en_passant: Option<BoardSquare>,
     pub halfmove_timer: u8,
-    pub move_number: u8,
+    pub move_number: u16,
 }
 
 
 impl FenBoardState {
-    pub fn from_fen(fen: &str) -> Self {
-        let mut fields: VecDeque<&str> = fen.split(" ").collect();
-        return Self {
-            board: get_board_from_notation(fields.pop_front().unwrap()),
-            to_move: get_to_move_from_notation(fields.pop_front().unwrap()),
-            castling: get_castling_from_notation(fields.pop_front().unwrap()),
-            en_passant: get_en_passant_from_notation(fields.pop_front().unwrap()),
-            halfmove_timer: fields.pop_front().unwrap().parse::<u8>().unwrap(),
-            move_number: fields.pop_front().unwrap().parse::<u8>().unwrap(),
+    pub fn from_fen(fen: &str) -> Result<Self, InputError> {
+        let mut fields: VecDeque<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(InputError::new(&format!("FEN must have 6 fields (or 4 without move counters), found {}", fields.len())));
+        }
+        let board = get_board_from_notation(fields.pop_front().unwrap())?;
+        let to_move = get_to_move_from_notation(fields.pop_front().unwrap())?;
+        let castling = get_castling_from_notation(fields.pop_front().unwrap(), &board)?;
+        let state = Self {
+            board: board,
+            to_move: to_move,
+            castling: castling,
+            en_passant: get_en_passant_from_notation(fields.pop_front().unwrap())?,
+            halfmove_timer: parse_fen_number(fields.pop_front().unwrap_or("0"), "halfmove clock")?,
+            move_number: parse_fen_number(fields.pop_front().unwrap_or("1"), "fullmove number")?,
+        };
+        state.validate()?;
+        return Ok(state);
+    }
+
+    fn piece_at(&self, col: usize, row: usize) -> Option<Piece> {
+        return self.board[7 - row][col];
+    }
+
+    fn count_pieces(&self, piece: Piece) -> usize {
+        return self.board.iter().flatten().filter(|p| **p == Some(piece)).count();
+    }
+
+    fn validate(&self) -> Result<(), InputError> {
+        for color in Color::iter() {
+            let kings = self.count_pieces(Piece { color: color, piece_type: PieceType::King });
+            if kings != 1 {
+                return Err(InputError::new(&format!("FEN position has {} {} kings instead of exactly one", kings, color.value())));
+            }
+        }
+        if self.board[0].iter().chain(self.board[7].iter()).any(|p| matches!(p, Some(Piece { piece_type: PieceType::Pawn, .. }))) {
+            return Err(InputError::new("FEN position has pawns on the first or eighth rank"));
+        }
+        for right in Castling::RIGHTS.iter() {
+            let file = match self.castling.get_file(right) {
+                Some(f) => f,
+                None => continue,
+            };
+            let row = match right.color { Color::White => 0, Color::Black => 7 };
+            let placed = match find_back_rank_king(&self.board, right.color) {
+                Some(king_file) => match right.side {
+                    CastleType::Kingside  => file > king_file,
+                    CastleType::Queenside => file < king_file,
+                },
+                None => false,
+            };
+            if !placed || self.piece_at(file as usize, row) != Some(Piece { color: right.color, piece_type: PieceType::Rook }) {
+                return Err(InputError::new(&format!("FEN castling right '{}' does not match the king and rook placement",
+                    get_notation_for_castle_right(right, file, &self.board))));
+            }
+        }
+        if let Some(square) = self.en_passant {
+            let [col, row] = get_col_and_row_from_square(square.value());
+            let (expected_row, pawn_row, start_row, pawn_color) = match self.to_move {
+                Color::White => (5, 4, 6, Color::Black),
+                Color::Black => (2, 3, 1, Color::White),
+            };
+            if row as usize != expected_row ||
+               self.piece_at(col as usize, pawn_row) != Some(Piece { color: pawn_color, piece_type: PieceType::Pawn }) ||
+               self.piece_at(col as usize, row as usize).is_some() ||
+               self.piece_at(col as usize, start_row).is_some()
+            {
+                return Err(InputError::new(&format!("FEN en passant target {} is not consistent with the last move", square.get_notation_string())));
+            }
         }
+        return Ok(());
     }
 
     pub fn to_fen(&self) -> String {
         let mut fields: Vec<String> = Vec::new();
         fields.push(get_notation_for_board(self.board));
         fields.push(get_notation_for_to_move(self.to_move));
-        fields.push(get_notation_for_castling(&self.castling));
+        fields.push(get_notation_for_castling(&self.castling, &self.board));
         fields.push(get_notation_for_en_passant(self.en_passant));
         fields.push(self.halfmove_timer.to_string());
         fields.push(self.move_number.to_string());
@@ -1,6 +1,12 @@
+pub mod allocations;
 pub mod concurrency;
+pub mod epd;
 pub mod errors;
 pub mod fen;
+pub mod notation;
+pub mod pgn;
+pub mod polyglot;
+pub mod random;
 pub mod zobrist;
 
 pub use std::ops::ControlFlow;
@@ -0,0 +1,211 @@
+use crate::rules::{board::{Board, squares::{get_col_and_row_from_square, get_notation_string_for_square, get_square_from_col_and_row}}, pieces::{PieceType, movement::{Castle, CastleType, Move}}};
+
+use super::errors::InputError;
+
+
+fn get_notation_for_promotion(piece_type: PieceType) -> &'static str {
+    return match piece_type {
+        PieceType::Knight => "n",
+        PieceType::Bishop => "b",
+        PieceType::Rook   => "r",
+        _                 => "q",
+    }
+}
+
+
+fn get_san_notation_for_piece_type(piece_type: PieceType) -> &'static str {
+    return match piece_type {
+        PieceType::Pawn   => "",
+        PieceType::Knight => "N",
+        PieceType::Bishop => "B",
+        PieceType::Rook   => "R",
+        PieceType::Queen  => "Q",
+        PieceType::King   => "K",
+    }
+}
+
+
+fn get_promotion_from_notation(note: char) -> Result<PieceType, InputError> {
+    return match note {
+        'n' => Ok(PieceType::Knight),
+        'b' => Ok(PieceType::Bishop),
+        'r' => Ok(PieceType::Rook),
+        'q' => Ok(PieceType::Queen),
+        _ => Err(InputError::new(&format!("'{}' is not a valid promotion piece", note))),
+    }
+}
+
+
+fn get_square_from_uci_notation(col: char, row: char) -> Result<u8, InputError> {
+    if !('a'..='h').contains(&col) || !('1'..='8').contains(&row) {
+        return Err(InputError::new(&format!("'{}{}' is not a valid square", col, row)));
+    }
+    return Ok(get_square_from_col_and_row(col as u8 - b'a', row as u8 - b'1'));
+}
+
+
+fn is_standard_castle(castle: &Castle) -> bool {
+    let rook_file = match castle.side {
+        CastleType::Kingside => 7,
+        CastleType::Queenside => 0,
+    };
+    return get_col_and_row_from_square(castle.king_start)[0] == 4 && get_col_and_row_from_square(castle.rook_start)[0] == rook_file;
+}
+
+
+fn get_uci_notation_for_castle(castle: &Castle, chess960: bool) -> String {
+    let end = match chess960 || !is_standard_castle(castle) {
+        true => castle.rook_start,
+        false => castle.king_end,
+    };
+    return format!("{}{}", get_notation_string_for_square(castle.king_start).unwrap(), get_notation_string_for_square(end).unwrap());
+}
+
+
+pub fn get_chess960_uci_notation_for_move(mov: &Move) -> String {
+    return match mov {
+        Move::Castle(c) => get_uci_notation_for_castle(c, true),
+        _ => get_uci_notation_for_move(mov),
+    }
+}
+
+
+pub fn get_chess960_uci_notation_for_moves(moves: &[Move]) -> String {
+    return moves.iter().map(get_chess960_uci_notation_for_move).collect::<Vec<String>>().join(" ");
+}
+
+
+pub fn get_uci_notation_for_move(mov: &Move) -> String {
+    return match mov {
+        Move::NullMove(_) => String::from("0000"),
+        Move::Castle(c) => get_uci_notation_for_castle(c, false),
+        Move::Promotion(p) => format!("{}{}{}",
+            get_notation_string_for_square(p.basic_move.start).unwrap(),
+            get_notation_string_for_square(p.basic_move.end).unwrap(),
+            get_notation_for_promotion(p.promote_to)),
+        _ => {
+            let movement = mov.get_piece_movements()[0];
+            format!("{}{}", get_notation_string_for_square(movement.start_square).unwrap(), get_notation_string_for_square(movement.end_square).unwrap())
+        }
+    }
+}
+
+
+pub fn get_uci_notation_for_moves(moves: &[Move]) -> String {
+    return moves.iter().map(get_uci_notation_for_move).collect::<Vec<String>>().join(" ");
+}
+
+
+pub fn get_move_from_uci_notation(board: &Board, note: &str) -> Result<Move, InputError> {
+    let chars: Vec<char> = note.trim().to_lowercase().chars().collect();
+    if chars.len() != 4 && chars.len() != 5 {
+        return Err(InputError::new(&format!("'{}' is not a valid UCI move", note)));
+    }
+    let start = get_square_from_uci_notation(chars[0], chars[1])?;
+    let end = get_square_from_uci_notation(chars[2], chars[3])?;
+    let promotion = match chars.get(4) {
+        Some(c) => Some(get_promotion_from_notation(*c)?),
+        None => None,
+    };
+    return board.get_legal_moves().into_iter().find(|m| {
+        match m {
+            Move::NullMove(_) => false,
+            Move::Castle(c) => c.king_start == start && (c.rook_start == end || (c.king_end == end && is_standard_castle(c))) && promotion.is_none(),
+            Move::Promotion(p) => p.basic_move.start == start && p.basic_move.end == end && promotion == Some(p.promote_to),
+            _ => {
+                let movement = m.get_piece_movements()[0];
+                movement.start_square == start && movement.end_square == end && promotion.is_none()
+            }
+        }
+    }).ok_or_else(|| InputError::new(&format!("'{}' is not a legal move in this position", note)));
+}
+
+
+fn get_san_disambiguation(board: &Board, mov: &Move) -> String {
+    let movement = mov.get_piece_movements()[0];
+    let rivals: Vec<u8> = board.get_legal_moves().iter().filter_map(|m| {
+        match m {
+            Move::Castle(_) | Move::NullMove(_) => None,
+            _ => {
+                let other = m.get_piece_movements()[0];
+                match other.piece_type == movement.piece_type && other.end_square == movement.end_square && other.start_square != movement.start_square {
+                    true => Some(other.start_square),
+                    false => None,
+                }
+            }
+        }
+    }).collect();
+    if rivals.is_empty() {
+        return String::new();
+    }
+    let notation = get_notation_string_for_square(movement.start_square).unwrap();
+    let [col, row] = get_col_and_row_from_square(movement.start_square);
+    if rivals.iter().all(|s| get_col_and_row_from_square(*s)[0] != col) {
+        return notation[0..1].to_string();
+    }
+    if rivals.iter().all(|s| get_col_and_row_from_square(*s)[1] != row) {
+        return notation[1..2].to_string();
+    }
+    return notation;
+}
+
+
+pub fn get_san_notation_for_move(board: &Board, mov: &Move) -> String {
+    let mut san = match mov {
+        Move::NullMove(_) => return String::from("--"),
+        Move::Castle(c) => String::from(match c.side {
+            CastleType::Kingside => "O-O",
+            CastleType::Queenside => "O-O-O",
+        }),
+        _ => {
+            let movement = mov.get_piece_movements()[0];
+            let capture = match mov.get_capture() {
+                Some(_) => "x",
+                None => "",
+            };
+            let prefix = match movement.piece_type {
+                PieceType::Pawn => match capture {
+                    "x" => get_notation_string_for_square(movement.start_square).unwrap()[0..1].to_string(),
+                    _ => String::new(),
+                },
+                piece_type => format!("{}{}", get_san_notation_for_piece_type(piece_type), get_san_disambiguation(board, mov)),
+            };
+            let promotion = match mov {
+                Move::Promotion(p) => format!("={}", get_san_notation_for_piece_type(p.promote_to)),
+                _ => String::new(),
+            };
+            format!("{}{}{}{}", prefix, capture, get_notation_string_for_square(movement.end_square).unwrap(), promotion)
+        }
+    };
+    let mut next_board = *board;
+    next_board.make_move(mov);
+    if next_board.in_check() {
+        san.push(if next_board.get_legal_moves().is_empty() { '#' } else { '+' });
+    }
+    return san;
+}
+
+
+fn normalize_san_notation(note: &str) -> String {
+    return note.trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .replace('0', "O")
+        .replace('=', "");
+}
+
+
+pub fn get_move_from_san_notation(board: &Board, note: &str) -> Result<Move, InputError> {
+    let target = normalize_san_notation(note);
+    let moves = board.get_legal_moves();
+    let notations: Vec<String> = moves.iter().map(|m| normalize_san_notation(&get_san_notation_for_move(board, m))).collect();
+    if let Some(index) = notations.iter().position(|n| *n == target) {
+        return Ok(moves[index]);
+    }
+    let loose_target = target.replace('x', "");
+    let loose_matches: Vec<usize> = notations.iter().enumerate().filter(|(_, n)| n.replace('x', "") == loose_target).map(|(i, _)| i).collect();
+    return match loose_matches.as_slice() {
+        [index] => Ok(moves[*index]),
+        _ => Err(InputError::new(&format!("'{}' is not a legal move in this position", note))),
+    }
+}
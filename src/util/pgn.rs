@@ -0,0 +1,216 @@
+use std::{fs::{self, OpenOptions}, io::Write};
+
+use crate::{game::{Game, GameResult}, rules::{Color, board::Board, pieces::movement::Move}, util::{errors::InputError, fen::STARTING_POSITION, notation::{get_move_from_san_notation, get_san_notation_for_move}}};
+
+
+static PGN_LINE_LENGTH: usize = 80;
+
+
+#[derive(Clone, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: Option<GameResult>,
+}
+
+impl PgnGame {
+    pub fn from_game(game: &Game, tags: Vec<(String, String)>, result: GameResult) -> Self {
+        let mut board = *game.get_starting_board();
+        let mut pgn = Self {
+            tags: tags,
+            moves: Vec::new(),
+            result: Some(result),
+        };
+        pgn.set_tag("Result", result.get_notation());
+        let fen = board.to_fen();
+        if fen != STARTING_POSITION {
+            pgn.set_tag("SetUp", "1");
+            pgn.set_tag("FEN", &fen);
+        }
+        if let Some(clock) = game.get_clock() {
+            pgn.set_tag("TimeControl", &clock.get_control().to_pgn_notation());
+        }
+        for mov in game.get_moves() {
+            pgn.moves.push(get_san_notation_for_move(&board, &mov));
+            board.make_move(&mov);
+        }
+        return pgn;
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = String::from(value),
+            None => self.tags.push((String::from(name), String::from(value))),
+        }
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        return self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+    }
+
+    pub fn get_result(&self) -> GameResult {
+        return self.result.or_else(|| self.get_tag("Result").and_then(GameResult::from_notation)).unwrap_or(GameResult::Unfinished);
+    }
+
+    pub fn get_starting_game(&self) -> Result<Game, InputError> {
+        return match self.get_tag("FEN") {
+            Some(fen) => Game::from_fen(fen),
+            None => Ok(Game::new()),
+        }
+    }
+
+    pub fn replay<F>(&self, max_ply: usize, mut on_move: F) -> Result<Game, InputError> where F: FnMut(&Game, &Move) {
+        let mut game = self.get_starting_game()?;
+        for note in self.moves.iter().take(max_ply) {
+            let mov = get_move_from_san_notation(game.get_board(), note)?;
+            on_move(&game, &mov);
+            game.make_move(&mov);
+        }
+        return Ok(game);
+    }
+
+    pub fn to_pgn(&self) -> Result<String, InputError> {
+        let mut text = String::new();
+        for (name, value) in &self.tags {
+            text.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        text.push('\n');
+        let board = match self.get_tag("FEN") {
+            Some(fen) => Board::from_fen(fen)?,
+            None => Board::from_starting_position(),
+        };
+        let mut color = board.state.get_move_color();
+        let mut move_number = board.state.move_number;
+        let mut tokens: Vec<String> = Vec::new();
+        for (index, note) in self.moves.iter().enumerate() {
+            match color {
+                Color::White => tokens.push(format!("{}.", move_number)),
+                Color::Black => if index == 0 { tokens.push(format!("{}...", move_number)) },
+            }
+            tokens.push(note.clone());
+            if color == Color::Black {
+                move_number += 1;
+            }
+            color = color.swap();
+        }
+        tokens.push(String::from(self.get_result().get_notation()));
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > PGN_LINE_LENGTH {
+                text.push_str(&line);
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        text.push_str(&line);
+        text.push_str("\n\n");
+        return Ok(text);
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.tags.is_empty() && self.moves.is_empty() && self.result.is_none();
+    }
+}
+
+
+fn parse_tag(line: &str) -> Result<(String, String), InputError> {
+    let inner = line.trim().trim_start_matches('[').trim_end_matches(']');
+    let (name, value) = match inner.split_once(char::is_whitespace) {
+        Some(parts) => parts,
+        None => return Err(InputError::new(&format!("Malformed PGN tag '{}'", line.trim()))),
+    };
+    let value = value.trim().trim_matches('"').replace("\\\"", "\"").replace("\\\\", "\\");
+    return Ok((String::from(name), value));
+}
+
+fn strip_move_number(token: &str) -> &str {
+    let digits = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+    if digits == 0 || !token[digits..].starts_with('.') { return token }
+    return token[digits..].trim_start_matches('.');
+}
+
+
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, InputError> {
+    let mut games: Vec<PgnGame> = Vec::new();
+    let mut current = PgnGame::default();
+    let mut in_comment = false;
+    let mut variation_depth = 0usize;
+    for line in text.lines() {
+        if !in_comment && variation_depth == 0 && line.trim_start().starts_with('[') {
+            if !current.moves.is_empty() || current.result.is_some() {
+                games.push(std::mem::take(&mut current));
+            }
+            current.tags.push(parse_tag(line)?);
+            continue;
+        }
+        if !in_comment && line.starts_with('%') { continue }
+        let mut token = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_comment {
+                if c == '}' { in_comment = false }
+                continue;
+            }
+            let outside_variation = variation_depth == 0;
+            let separator = match c {
+                '{' => { in_comment = true; true },
+                ';' => { while chars.next().is_some() {}; true },
+                '(' => { variation_depth += 1; true },
+                ')' => { variation_depth = variation_depth.saturating_sub(1); true },
+                c if c.is_whitespace() => true,
+                _ => false,
+            };
+            if !separator {
+                token.push(c);
+                if chars.peek().is_some() { continue }
+            }
+            if outside_variation {
+                take_token(&token, &mut current, &mut games);
+            }
+            token.clear();
+        }
+    }
+    if !current.is_empty() {
+        games.push(current);
+    }
+    return Ok(games);
+}
+
+fn take_token(token: &str, current: &mut PgnGame, games: &mut Vec<PgnGame>) {
+    let note = strip_move_number(token);
+    if note.is_empty() || note.starts_with('$') { return }
+    match GameResult::from_notation(note) {
+        Some(result) => {
+            current.result = Some(result);
+            games.push(std::mem::take(current));
+        },
+        None => current.moves.push(String::from(note)),
+    }
+}
+
+
+pub fn read_pgn_file(path: &str) -> Result<Vec<PgnGame>, InputError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => return Err(InputError::new(&format!("Could not read PGN file {}: {}", path, e))),
+    };
+    return parse_pgn(&contents);
+}
+
+
+pub fn write_pgn_file(path: &str, games: &[PgnGame], append: bool) -> Result<(), InputError> {
+    let mut file = match OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path) {
+        Ok(f) => f,
+        Err(e) => return Err(InputError::new(&format!("Could not open PGN file {}: {}", path, e))),
+    };
+    for game in games {
+        if let Err(e) = file.write_all(game.to_pgn()?.as_bytes()) {
+            return Err(InputError::new(&format!("Could not write PGN file {}: {}", path, e)));
+        }
+    }
+    return Ok(());
+}
@@ -115,10 +115,10 @@ impl ZobristId {
         let mut changes: Vec<BoardChange> = Vec::new();
         if state.to_move == Color::Black { changes.push(BoardChange::BlackToMove) };
         if state.en_passant.is_some() { changes.push(BoardChange::EnPassantTarget(state.en_passant.unwrap().value())) };
-        if state.castling.white_kingside  { changes.push(BoardChange::CastleRight(CastleRight { color: Color::White, side: CastleType::Kingside  })) };
-        if state.castling.white_queenside { changes.push(BoardChange::CastleRight(CastleRight { color: Color::White, side: CastleType::Queenside })) };
-        if state.castling.black_kingside  { changes.push(BoardChange::CastleRight(CastleRight { color: Color::Black, side: CastleType::Kingside  })) };
-        if state.castling.black_queenside { changes.push(BoardChange::CastleRight(CastleRight { color: Color::Black, side: CastleType::Queenside })) };
+        if state.castling.white_kingside.is_some()  { changes.push(BoardChange::CastleRight(CastleRight { color: Color::White, side: CastleType::Kingside  })) };
+        if state.castling.white_queenside.is_some() { changes.push(BoardChange::CastleRight(CastleRight { color: Color::White, side: CastleType::Queenside })) };
+        if state.castling.black_kingside.is_some()  { changes.push(BoardChange::CastleRight(CastleRight { color: Color::Black, side: CastleType::Kingside  })) };
+        if state.castling.black_queenside.is_some() { changes.push(BoardChange::CastleRight(CastleRight { color: Color::Black, side: CastleType::Queenside })) };
         for (row_index, row) in state.board.iter().rev().enumerate() {
             for (col_index, square) in row.iter().enumerate() {
                 match square {
@@ -156,7 +156,7 @@ impl ZobristId {
     }
     
     pub fn update_move(&mut self, new_move: &Move) {
-        for movement in new_move.get_piece_movements() {
+        for movement in new_move.iter_piece_movements() {
             self.update(BoardChange::PieceLocation(PieceLocation {
                 square: movement.start_square,
                 piece: movement.get_piece(),